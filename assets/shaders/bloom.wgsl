@@ -0,0 +1,48 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct BloomUniforms {
+    threshold: f32, // Luminance above which pixels bloom (0.0-1.0)
+    intensity: f32, // Strength of the glow added back to the image
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: BloomUniforms;
+@group(0) @binding(3) var bloom_texture: texture_2d<f32>; // Blurred highlights
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, s_sampler, tex_coords);
+    let bloom = textureSample(bloom_texture, s_sampler, tex_coords).rgb;
+
+    return vec4<f32>(color.rgb + bloom * uniforms.intensity, color.a);
+}
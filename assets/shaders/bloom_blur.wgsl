@@ -0,0 +1,69 @@
+// Bright pass and blur of the bloom effect
+//
+// The effect prepends BLUR_DIRECTION (vec2<f32>, one texel step) and
+// APPLY_THRESHOLD (bool) constants to build the horizontal and vertical passes.
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct BloomUniforms {
+    threshold: f32, // Luminance above which pixels bloom (0.0-1.0)
+    intensity: f32, // Strength of the glow added back to the image
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: BloomUniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+// Keep only the part of a color above the luminance threshold
+fn bright_pass(color: vec3<f32>) -> vec3<f32> {
+    if !APPLY_THRESHOLD {
+        return color;
+    }
+    let luminance = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return color * (max(luminance - uniforms.threshold, 0.0) / max(luminance, 0.0001));
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    // 9-tap Gaussian, taps spaced two texels apart for a wider glow
+    var weights = array<f32, 5>(0.2270270, 0.1945946, 0.1216216, 0.0540541, 0.0162162);
+    let step = BLUR_DIRECTION * 2.0 / vec2<f32>(textureDimensions(input_texture));
+
+    var sum = bright_pass(textureSample(input_texture, s_sampler, tex_coords).rgb) * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = step * f32(i);
+        sum += bright_pass(textureSample(input_texture, s_sampler, tex_coords + offset).rgb) * weights[i];
+        sum += bright_pass(textureSample(input_texture, s_sampler, tex_coords - offset).rgb) * weights[i];
+    }
+
+    return vec4<f32>(sum, 1.0);
+}
@@ -0,0 +1,64 @@
+// One pass of the separable Gaussian blur
+//
+// The effect prepends a BLUR_DIRECTION (vec2<f32>, one texel step) constant to
+// build the horizontal and vertical passes.
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct BlurUniforms {
+    texel_size: vec2<f32>, // Size of one input pixel in texture coordinates
+    radius: f32,           // Pixels sampled on each side (0.0-32.0)
+    sigma: f32,            // Standard deviation of the Gaussian in pixels
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: BlurUniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let step = BLUR_DIRECTION * uniforms.texel_size;
+    let radius = i32(clamp(uniforms.radius, 0.0, 32.0));
+    let two_sigma_sq = 2.0 * max(uniforms.sigma, 0.01) * max(uniforms.sigma, 0.01);
+
+    var sum = textureSample(input_texture, s_sampler, tex_coords);
+    var total_weight = 1.0;
+    for (var i = 1; i <= radius; i++) {
+        let offset = step * f32(i);
+        let weight = exp(-f32(i * i) / two_sigma_sq);
+        sum += textureSample(input_texture, s_sampler, tex_coords + offset) * weight;
+        sum += textureSample(input_texture, s_sampler, tex_coords - offset) * weight;
+        total_weight += 2.0 * weight;
+    }
+
+    return sum / total_weight;
+}
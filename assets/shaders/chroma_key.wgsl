@@ -0,0 +1,60 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct ChromaKeyUniforms {
+    key_color: vec3<f32>, // RGB color to remove
+    threshold: f32,       // Chroma distance below which pixels are fully transparent
+    smoothness: f32,      // Distance over which alpha fades back in
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: ChromaKeyUniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+// BT.709 blue and red difference, brightness on the backdrop doesn't matter
+fn chroma(rgb: vec3<f32>) -> vec2<f32> {
+    let luma = dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    return vec2<f32>((rgb.b - luma) / 1.8556, (rgb.r - luma) / 1.5748);
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, s_sampler, tex_coords);
+
+    let distance = length(chroma(color.rgb) - chroma(uniforms.key_color));
+    let alpha = smoothstep(
+        uniforms.threshold,
+        uniforms.threshold + max(uniforms.smoothness, 1e-4),
+        distance
+    );
+
+    return vec4<f32>(color.rgb, color.a * alpha);
+}
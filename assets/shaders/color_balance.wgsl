@@ -0,0 +1,59 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+// Per-channel RGB gains for each tonal range (w is padding)
+struct ColorBalanceUniforms {
+    shadows: vec4<f32>,
+    midtones: vec4<f32>,
+    highlights: vec4<f32>,
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: ColorBalanceUniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, s_sampler, tex_coords);
+
+    // Split the image into tonal ranges using BT.709 luma
+    let luma = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let shadow_weight = 1.0 - smoothstep(0.0, 0.5, luma);
+    let highlight_weight = smoothstep(0.5, 1.0, luma);
+    let midtone_weight = 1.0 - shadow_weight - highlight_weight;
+
+    // Blend the gains of each range based on how much the pixel belongs to it
+    let gain = uniforms.shadows.rgb * shadow_weight
+        + uniforms.midtones.rgb * midtone_weight
+        + uniforms.highlights.rgb * highlight_weight;
+
+    return vec4<f32>(clamp(color.rgb * gain, vec3<f32>(0.0), vec3<f32>(1.0)), color.a);
+}
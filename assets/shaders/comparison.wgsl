@@ -5,6 +5,9 @@ struct VertexOutput {
 
 struct Uniforms {
     line_position: f32,
+    line_width: f32,      // Width of the dividing line in pixels
+    orientation: u32,     // 0 = left/right split, 1 = top/bottom split
+    line_color: vec4<f32>, // RGB color of the line, alpha is its opacity
 }
 
 @group(0) @binding(0) var original_texture: texture_2d<f32>;
@@ -39,21 +42,24 @@ fn vs_main(@builtin(vertex_index) vertex_idx: u32) -> VertexOutput {
 }
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    // Calculate how close we are to the dividing line
-    let line_distance = abs(in.uv.x - uniforms.line_position);
+    // Position across the split, x for left/right and y for top/bottom
+    let split_coord = select(in.uv.x, in.uv.y, uniforms.orientation == 1u);
+
+    // Calculate how close we are to the dividing line, in pixels of the output
+    let pixel_size = fwidth(split_coord);
+    let line_distance = abs(split_coord - uniforms.line_position) / max(pixel_size, 1e-6);
     
     // Sample the appropriate texture using ternary operator style
     let color = select(
         textureSample(processed_texture, texture_sampler, in.uv),
         textureSample(original_texture, texture_sampler, in.uv),
-        in.uv.x < uniforms.line_position
+        split_coord < uniforms.line_position
     );
     
-    // Create a line effect with a dark color with opacity
-    if (line_distance < 0.002) {
-        // Dark line color with opacity
-        let line_color = vec3<f32>(0.1, 0.1, 0.1); // Almost black
-        let opacity = 0.9;
+    // Draw the dividing line over the content
+    if (line_distance < uniforms.line_width * 0.5) {
+        let line_color = uniforms.line_color.rgb;
+        let opacity = uniforms.line_color.a;
         
         // Blend the line color with the original content
         return vec4<f32>(
@@ -0,0 +1,59 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct LutUniforms {
+    domain_min: vec4<f32>, // Input mapped to the first LUT entry, w unused
+    domain_max: vec4<f32>, // Input mapped to the last LUT entry, w unused
+    intensity: f32,        // 0.0 = input, 1.0 = fully graded
+    size: f32,             // Entries per axis
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: LutUniforms;
+@group(0) @binding(3) var lut_texture: texture_3d<f32>;
+@group(0) @binding(4) var lut_sampler: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, s_sampler, tex_coords);
+
+    // Normalize to the LUT domain
+    let range = max(uniforms.domain_max.rgb - uniforms.domain_min.rgb, vec3<f32>(1e-6));
+    let normalized = clamp((color.rgb - uniforms.domain_min.rgb) / range, vec3<f32>(0.0), vec3<f32>(1.0));
+
+    // Entries sit at texel centers, so map 0-1 onto the first to the last center
+    let scale = (uniforms.size - 1.0) / uniforms.size;
+    let offset = 0.5 / uniforms.size;
+    let graded = textureSample(lut_texture, lut_sampler, normalized * scale + offset).rgb;
+
+    return vec4<f32>(mix(color.rgb, graded, uniforms.intensity), color.a);
+}
@@ -0,0 +1,66 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct SimpleColorUniforms {
+    mode: u32, // 0 = none, 1 = grayscale, 2 = sepia, 3 = invert
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: SimpleColorUniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, s_sampler, tex_coords);
+
+    switch uniforms.mode {
+        // Rec. 709 luminance in all channels
+        case 1u: {
+            let luminance = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+            return vec4<f32>(vec3<f32>(luminance), color.a);
+        }
+        // Classic sepia tone matrix
+        case 2u: {
+            let sepia = vec3<f32>(
+                dot(color.rgb, vec3<f32>(0.393, 0.769, 0.189)),
+                dot(color.rgb, vec3<f32>(0.349, 0.686, 0.168)),
+                dot(color.rgb, vec3<f32>(0.272, 0.534, 0.131)),
+            );
+            return vec4<f32>(min(sepia, vec3<f32>(1.0)), color.a);
+        }
+        case 3u: {
+            return vec4<f32>(vec3<f32>(1.0) - color.rgb, color.a);
+        }
+        default: {
+            return color;
+        }
+    }
+}
@@ -0,0 +1,60 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct StereoUniforms {
+    stereo_layout: u32, // 0 = side by side, 1 = top-bottom, 2/3 = anaglyph of those
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: StereoUniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+// Map the output coordinates into one eye of the packed frame
+fn eye_coords(tex_coords: vec2<f32>, top_bottom: bool, eye: f32) -> vec2<f32> {
+    if top_bottom {
+        return vec2<f32>(tex_coords.x, (tex_coords.y + eye) * 0.5);
+    }
+    return vec2<f32>((tex_coords.x + eye) * 0.5, tex_coords.y);
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let top_bottom = uniforms.stereo_layout == 1u || uniforms.stereo_layout == 3u;
+    let left = textureSample(input_texture, s_sampler, eye_coords(tex_coords, top_bottom, 0.0));
+    let right = textureSample(input_texture, s_sampler, eye_coords(tex_coords, top_bottom, 1.0));
+
+    if uniforms.stereo_layout < 2u {
+        return left;
+    }
+
+    // Red from the left eye, green and blue from the right
+    return vec4<f32>(left.r, right.g, right.b, left.a);
+}
@@ -0,0 +1,57 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct TemporalDenoiseUniforms {
+    blend: f32,       // Weight of the previous frame (0 = no denoise)
+    has_history: u32, // Whether the history texture contains a previous frame
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: TemporalDenoiseUniforms;
+@group(0) @binding(3) var history_texture: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let current = textureSample(input_texture, s_sampler, tex_coords);
+    let previous = textureSample(history_texture, s_sampler, tex_coords);
+
+    if (uniforms.has_history == 0u) {
+        return current;
+    }
+
+    // Blend less where the pixel changed a lot to avoid ghosting on motion
+    let difference = length(current.rgb - previous.rgb);
+    let weight = uniforms.blend * (1.0 - smoothstep(0.05, 0.2, difference));
+
+    // Exponential moving average with the previous output
+    return vec4<f32>(mix(current.rgb, previous.rgb, weight), current.a);
+}
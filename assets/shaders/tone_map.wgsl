@@ -0,0 +1,136 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+struct ToneMapUniforms {
+    transfer: u32,       // 0 = PQ, 1 = HLG
+    curve: u32,          // 0 = Reinhard, 1 = Hable
+    peak_luminance: f32, // Brightest luminance of the video in nits
+}
+
+// SDR reference white in nits (BT.2408), mapped to 1.0 before the curve
+const REFERENCE_WHITE: f32 = 203.0;
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var s_sampler: sampler;
+@group(0) @binding(2) var<uniform> uniforms: ToneMapUniforms;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    var pos = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>(-1.0,  1.0),
+        vec2<f32>( 1.0, -1.0),
+        vec2<f32>( 1.0,  1.0),
+    );
+
+    var tex_coords = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+    );
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pos[vertex_index], 0.0, 1.0);
+    output.tex_coords = tex_coords[vertex_index];
+    return output;
+}
+
+// SMPTE ST 2084 EOTF, nonlinear signal to absolute luminance in nits
+fn pq_to_nits(signal: vec3<f32>) -> vec3<f32> {
+    let m1 = 0.1593017578125;
+    let m2 = 78.84375;
+    let c1 = 0.8359375;
+    let c2 = 18.8515625;
+    let c3 = 18.6875;
+
+    let p = pow(max(signal, vec3<f32>(0.0)), vec3<f32>(1.0 / m2));
+    let linear = pow(max(p - c1, vec3<f32>(0.0)) / (c2 - c3 * p), vec3<f32>(1.0 / m1));
+    return linear * 10000.0;
+}
+
+// ARIB STD-B67 inverse OETF and OOTF, nonlinear signal to display luminance in nits
+fn hlg_to_nits(signal: vec3<f32>, peak: f32) -> vec3<f32> {
+    let a = 0.17883277;
+    let b = 0.28466892;
+    let c = 0.55991073;
+
+    let low = signal * signal / 3.0;
+    let high = (exp((signal - c) / a) + b) / 12.0;
+    let scene = select(high, low, signal <= vec3<f32>(0.5));
+
+    // System gamma of 1.2 at 1000 nits, adjusted for other peaks (BT.2100)
+    let gamma = 1.2 + 0.42 * log(peak / 1000.0) / log(10.0);
+    let luma = dot(scene, vec3<f32>(0.2627, 0.6780, 0.0593));
+    return peak * pow(max(luma, 1e-6), gamma - 1.0) * scene;
+}
+
+// Extended Reinhard, `white` maps to 1.0
+fn reinhard(x: vec3<f32>, white: f32) -> vec3<f32> {
+    return x * (1.0 + x / (white * white)) / (1.0 + x);
+}
+
+fn hable_partial(x: vec3<f32>) -> vec3<f32> {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
+}
+
+// Hable filmic curve, `white` maps to 1.0
+fn hable(x: vec3<f32>, white: f32) -> vec3<f32> {
+    return hable_partial(x * 2.0) / hable_partial(vec3<f32>(white));
+}
+
+// BT.709 inverse EOTF (gamma 2.4) for the SDR output
+fn encode_sdr(linear: vec3<f32>) -> vec3<f32> {
+    return pow(clamp(linear, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(1.0 / 2.4));
+}
+
+@fragment
+fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, s_sampler, tex_coords);
+    let peak = max(uniforms.peak_luminance, REFERENCE_WHITE);
+
+    var nits: vec3<f32>;
+    switch uniforms.transfer {
+        case 1u: {
+            nits = hlg_to_nits(color.rgb, peak);
+        }
+        default: {
+            nits = pq_to_nits(color.rgb);
+        }
+    }
+
+    // Relative to SDR white, so the peak of the video lands at `white`
+    let relative = nits / REFERENCE_WHITE;
+    let white = peak / REFERENCE_WHITE;
+
+    var mapped: vec3<f32>;
+    switch uniforms.curve {
+        case 1u: {
+            mapped = hable(relative, white);
+        }
+        default: {
+            mapped = reinhard(relative, white);
+        }
+    }
+
+    // BT.2020 to BT.709 primaries in linear light (BT.2087)
+    let to_bt709 = mat3x3<f32>(
+        vec3<f32>( 1.6605, -0.1246, -0.0182),
+        vec3<f32>(-0.5876,  1.1329, -0.1006),
+        vec3<f32>(-0.0728, -0.0083,  1.1187),
+    );
+
+    return vec4<f32>(encode_sdr(to_bt709 * mapped), color.a);
+}
@@ -8,6 +8,7 @@ struct ShaderUniforms {
     comparison_position: f32,
     color_threshold: f32, 
     color_blend_mode: u32, // 0: Sharp, 1: Soft Blend, 2: Adaptive
+    algorithm: u32, // 0: Bilinear, 1: Bicubic, 2: Lanczos
 }
 
 @group(0) @binding(0) var input_texture: texture_2d<f32>;
@@ -130,6 +131,73 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     return output;
 }
 
+// Fetch a texel, clamping the coordinates to the texture
+fn load_texel(coords: vec2<i32>) -> vec4<f32> {
+    let max_coords = vec2<i32>(textureDimensions(input_texture)) - vec2<i32>(1);
+    return textureLoad(input_texture, clamp(coords, vec2<i32>(0), max_coords), 0);
+}
+
+// Catmull-Rom cubic weight for a distance in texels
+fn cubic_weight(x: f32) -> f32 {
+    let ax = abs(x);
+    if (ax < 1.0) {
+        return 1.5 * ax * ax * ax - 2.5 * ax * ax + 1.0;
+    }
+    if (ax < 2.0) {
+        return -0.5 * ax * ax * ax + 2.5 * ax * ax - 4.0 * ax + 2.0;
+    }
+    return 0.0;
+}
+
+// Lanczos weight with a radius of 2 texels
+fn lanczos_weight(x: f32) -> f32 {
+    let ax = abs(x);
+    if (ax < 0.0001) {
+        return 1.0;
+    }
+    if (ax >= 2.0) {
+        return 0.0;
+    }
+    let pi_x = 3.14159265 * ax;
+    return 2.0 * sin(pi_x) * sin(pi_x / 2.0) / (pi_x * pi_x);
+}
+
+// Resample the input with the selected kernel
+//
+// The texels are loaded directly, so the result doesn't depend on the sampler filtering.
+fn resample(tex_coords: vec2<f32>) -> vec4<f32> {
+    let position = tex_coords * vec2<f32>(textureDimensions(input_texture)) - 0.5;
+    let base = vec2<i32>(floor(position));
+    let f = position - floor(position);
+
+    if (uniforms.algorithm == 0u) {
+        // Bilinear: blend the 2x2 surrounding texels
+        let top = mix(load_texel(base), load_texel(base + vec2<i32>(1, 0)), f.x);
+        let bottom = mix(load_texel(base + vec2<i32>(0, 1)), load_texel(base + vec2<i32>(1, 1)), f.x);
+        return mix(top, bottom, f.y);
+    }
+
+    // Bicubic and Lanczos: weigh the 4x4 surrounding texels
+    var sum = vec4<f32>(0.0);
+    var weight_sum = 0.0;
+    for (var y = -1; y <= 2; y++) {
+        for (var x = -1; x <= 2; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) - f;
+            var weight: f32;
+            if (uniforms.algorithm == 1u) {
+                weight = cubic_weight(offset.x) * cubic_weight(offset.y);
+            } else {
+                weight = lanczos_weight(offset.x) * lanczos_weight(offset.y);
+            }
+            sum += load_texel(base + vec2<i32>(x, y)) * weight;
+            weight_sum += weight;
+        }
+    }
+
+    // Normalize the weights and clamp the ringing of the sharper kernels
+    return clamp(sum / weight_sum, vec4<f32>(0.0), vec4<f32>(1.0));
+}
+
 fn get_luma(color: vec4<f32>) -> f32 {
     return dot(color.rgb, vec3<f32>(0.299, 0.587, 0.114));
 }
@@ -185,7 +253,7 @@ fn apply_upscale(color: vec4<f32>, tex_coords: vec2<f32>) -> vec4<f32> {
 
 @fragment
 fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
-    let color = textureSample(input_texture, s_sampler, tex_coords);
+    let color = resample(tex_coords);
     
     return apply_upscale(color, tex_coords);
 }
\ No newline at end of file
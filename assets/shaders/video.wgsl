@@ -5,7 +5,11 @@ struct VertexOutput {
 
 struct Uniforms {
     rect: vec4<f32>,
-    color_space: u32,  
+    color_space: u32,
+    region: vec4<f32>, // Normalized source region to sample [x, y, width, height]
+    y_range: vec2<f32>, // Min and max of Y, 16-235 for limited and 0-255 for full range
+    uv_range: vec2<f32>, // Min and max of U and V
+    sample_scale: f32, // Scales sampled values to 8-bit levels, above 1.0 for 16-bit planes
 }
 
 @group(0) @binding(0)
@@ -20,10 +24,13 @@ var s: sampler;
 @group(0) @binding(3)
 var<uniform> uniforms: Uniforms;
 
+@group(0) @binding(4)
+var tex_a: texture_2d<f32>; // Single opaque texel for videos without alpha
+
 fn convert_yuv_bt709(y: f32, u: f32, v: f32) -> vec3<f32> {
-    let y_range = (y - 16.0/255.0) * (255.0/219.0);
-    let u_range = (u - 128.0/255.0) * (255.0/224.0);
-    let v_range = (v - 128.0/255.0) * (255.0/224.0);
+    let y_range = (y - uniforms.y_range.x) / (uniforms.y_range.y - uniforms.y_range.x);
+    let u_range = (u - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
+    let v_range = (v - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
     
     let r = y_range + 1.5748 * v_range;
     let g = y_range - 0.1873 * u_range - 0.4681 * v_range;
@@ -33,9 +40,9 @@ fn convert_yuv_bt709(y: f32, u: f32, v: f32) -> vec3<f32> {
 }
 
 fn convert_yuv_bt601(y: f32, u: f32, v: f32) -> vec3<f32> {
-    let y_range = (y - 16.0/255.0) * (255.0/219.0);
-    let u_range = (u - 128.0/255.0) * (255.0/224.0);
-    let v_range = (v - 128.0/255.0) * (255.0/224.0);
+    let y_range = (y - uniforms.y_range.x) / (uniforms.y_range.y - uniforms.y_range.x);
+    let u_range = (u - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
+    let v_range = (v - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
     
     let r = y_range + 1.402 * v_range;
     let g = y_range - 0.344 * u_range - 0.714 * v_range;
@@ -44,6 +51,18 @@ fn convert_yuv_bt601(y: f32, u: f32, v: f32) -> vec3<f32> {
     return clamp(vec3<f32>(r, g, b), vec3<f32>(0.0), vec3<f32>(1.0));
 }
 
+fn convert_yuv_bt2020(y: f32, u: f32, v: f32) -> vec3<f32> {
+    let y_range = (y - uniforms.y_range.x) / (uniforms.y_range.y - uniforms.y_range.x);
+    let u_range = (u - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
+    let v_range = (v - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
+    
+    let r = y_range + 1.4746 * v_range;
+    let g = y_range - 0.1646 * u_range - 0.5714 * v_range;
+    let b = y_range + 1.8814 * u_range;
+    
+    return clamp(vec3<f32>(r, g, b), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
 @vertex
 fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
     // Define a full-screen quad in normalized device coordinates (-1 to 1)
@@ -72,6 +91,11 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
     return out;
 }
 
+// Scale from Y plane to UV plane coordinates, 1.0 for even sizes
+fn chroma_scale(y_size: vec2<u32>, uv_size: vec2<u32>) -> vec2<f32> {
+    return vec2<f32>(y_size) / (2.0 * vec2<f32>(uv_size));
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // Texture size diagnostics
@@ -84,12 +108,16 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         return vec4<f32>(1.0, 0.0, 0.0, 1.0); // Red for invalid texture size
     }
 
-    // Safe UV clamping
-    let safe_uv = clamp(in.uv, vec2<f32>(0.0), vec2<f32>(1.0));
+    // Map the quad onto the sampled region, with safe UV clamping
+    let region_uv = uniforms.region.xy + in.uv * uniforms.region.zw;
+    let safe_uv = clamp(region_uv, vec2<f32>(0.0), vec2<f32>(1.0));
     
-    // Sample Y and UV planes
-    let y = textureSample(tex_y, s, safe_uv).r;
-    let uv = textureSample(tex_uv, s, safe_uv).rg;
+    // Sample Y, UV and alpha planes, 16-bit planes are scaled to the 8-bit levels of the ranges.
+    // The UV plane is rounded up for odd sizes, so its coordinates are scaled to match
+    let y = min(textureSample(tex_y, s, safe_uv).r * uniforms.sample_scale, 1.0);
+    let uv_coords = safe_uv * chroma_scale(y_tex_size, uv_tex_size);
+    let uv = min(textureSample(tex_uv, s, uv_coords).rg * uniforms.sample_scale, vec2<f32>(1.0));
+    let alpha = textureSample(tex_a, s, safe_uv).r;
     
     // Validate input values
     if (y < 0.0 || y > 1.0 || 
@@ -107,16 +135,19 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
         case 1u: { // BT.601
             rgb = convert_yuv_bt601(y, uv.r, uv.g);
         }
+        case 2u: { // BT.2020
+            rgb = convert_yuv_bt2020(y, uv.r, uv.g);
+        }
         default: { // Fallback to BT.709
             rgb = convert_yuv_bt709(y, uv.r, uv.g);
         }
     }
     
     // Create final color
-    let final_color = vec4<f32>(rgb, 1.0);
+    let final_color = vec4<f32>(rgb, alpha);
     
-    // Validate output color
-    if (length(final_color.rgb) < 0.001) {
+    // Validate output color, fully transparent areas are often black
+    if (length(final_color.rgb) < 0.001 && alpha > 0.0) {
         return vec4<f32>(0.0, 0.0, 1.0, 1.0); // Blue for zero-intensity conversion
     }
     
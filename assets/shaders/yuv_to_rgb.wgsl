@@ -1,19 +1,24 @@
 struct YUVToRGBUniforms {
-    color_space: u32, // 0 for BT.709, 1 for BT.601, etc.
+    color_space: u32, // 0 for BT.709, 1 for BT.601, 2 for BT.2020
+    region: vec4<f32>, // Normalized source region to sample [x, y, width, height]
+    y_range: vec2<f32>, // Min and max of Y, 16-235 for limited and 0-255 for full range
+    uv_range: vec2<f32>, // Min and max of U and V
+    sample_scale: f32, // Scales sampled values to 8-bit levels, above 1.0 for 16-bit planes
 }
 
 @group(0) @binding(0) var input_texture_y: texture_2d<f32>;
 @group(0) @binding(1) var input_texture_uv: texture_2d<f32>;
 @group(0) @binding(2) var s_sampler: sampler;
 @group(0) @binding(3) var<uniform> uniforms: YUVToRGBUniforms;
+@group(0) @binding(4) var input_texture_a: texture_2d<f32>; // Opaque for videos without alpha
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
 }
 fn convert_yuv_bt709(y: f32, u: f32, v: f32) -> vec3<f32> {
-    let y_range = (y - 16.0/255.0) * (255.0/219.0);
-    let u_range = (u - 128.0/255.0) * (255.0/224.0);
-    let v_range = (v - 128.0/255.0) * (255.0/224.0);
+    let y_range = (y - uniforms.y_range.x) / (uniforms.y_range.y - uniforms.y_range.x);
+    let u_range = (u - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
+    let v_range = (v - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
     
     return vec3<f32>(
         y_range + 1.5748 * v_range,
@@ -23,9 +28,9 @@ fn convert_yuv_bt709(y: f32, u: f32, v: f32) -> vec3<f32> {
 }
 
 fn convert_yuv_bt601(y: f32, u: f32, v: f32) -> vec3<f32> {
-    let y_range = (y - 16.0/255.0) * (255.0/219.0);
-    let u_range = (u - 128.0/255.0) * (255.0/224.0);
-    let v_range = (v - 128.0/255.0) * (255.0/224.0);
+    let y_range = (y - uniforms.y_range.x) / (uniforms.y_range.y - uniforms.y_range.x);
+    let u_range = (u - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
+    let v_range = (v - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
     
     return vec3<f32>(
         y_range + 1.402 * v_range,
@@ -33,6 +38,18 @@ fn convert_yuv_bt601(y: f32, u: f32, v: f32) -> vec3<f32> {
         y_range + 1.772 * u_range
     );
 }
+
+fn convert_yuv_bt2020(y: f32, u: f32, v: f32) -> vec3<f32> {
+    let y_range = (y - uniforms.y_range.x) / (uniforms.y_range.y - uniforms.y_range.x);
+    let u_range = (u - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
+    let v_range = (v - 128.0/255.0) / (uniforms.uv_range.y - uniforms.uv_range.x);
+    
+    return vec3<f32>(
+        y_range + 1.4746 * v_range,
+        y_range - 0.1646 * u_range - 0.5714 * v_range,
+        y_range + 1.8814 * u_range
+    );
+}
 @vertex
 fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     var pos = array<vec2<f32>, 6>(
@@ -59,14 +76,25 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     return output;
 }
 
+// Scale from Y plane to UV plane coordinates, 1.0 for even sizes
+fn chroma_scale(y_size: vec2<u32>, uv_size: vec2<u32>) -> vec2<f32> {
+    return vec2<f32>(y_size) / (2.0 * vec2<f32>(uv_size));
+}
+
 @fragment
 fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
-    let y = textureSample(input_texture_y, s_sampler, tex_coords).r;
+    // Map the output onto the cropped region of the frame
+    let region_coords = uniforms.region.xy + tex_coords * uniforms.region.zw;
+    let y = min(textureSample(input_texture_y, s_sampler, region_coords).r * uniforms.sample_scale, 1.0);
     
-    // Important: For UV sampling we need to handle the half-resolution correctly
-    // The UV texture is typically half the size of the Y texture in each dimension
-    let uv_coords = tex_coords;
-    let uv = textureSample(input_texture_uv, s_sampler, uv_coords).rg;
+    // The UV plane is rounded up for odd sizes, e.g. 960 samples for 1919
+    // pixels, so its last sample covers only one pixel
+    let uv_coords = region_coords * chroma_scale(
+        textureDimensions(input_texture_y),
+        textureDimensions(input_texture_uv),
+    );
+    let uv = min(textureSample(input_texture_uv, s_sampler, uv_coords).rg * uniforms.sample_scale, vec2<f32>(1.0));
+    let alpha = textureSample(input_texture_a, s_sampler, region_coords).r;
     
     var rgb: vec3<f32>;
     switch (uniforms.color_space) {
@@ -76,6 +104,9 @@ fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
         case 1u: {
             rgb = convert_yuv_bt601(y, uv.r, uv.g);
         }
+        case 2u: {
+            rgb = convert_yuv_bt2020(y, uv.r, uv.g);
+        }
         default: {
             rgb = convert_yuv_bt709(y, uv.r, uv.g);
         }
@@ -84,5 +115,5 @@ fn fs_main(@location(0) tex_coords: vec2<f32>) -> @location(0) vec4<f32> {
     // Apply brightness and clamp
     rgb = clamp(rgb, vec3<f32>(0.0), vec3<f32>(1.0));
     
-    return vec4<f32>(rgb, 1.0);
+    return vec4<f32>(rgb, alpha);
 }
\ No newline at end of file
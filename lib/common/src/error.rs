@@ -10,6 +10,10 @@ pub enum VideoError {
     #[error("Frame decode error: {0}")]
     Decode(String),
 
+    /// Playback failed at a frame, see `VideoStream::last_error`
+    #[error("Playback failed at frame {frame}: {message}")]
+    PlaybackFailed { frame: u64, message: String },
+
     #[error("Frame processing error: {0}")]
     FrameProcessing(String),
 
@@ -22,6 +26,62 @@ pub enum VideoError {
     #[error("Invalid timestamp")]
     InvalidTimestamp,
 
+    #[error("Invalid timecode {0}")]
+    InvalidTimecode(String),
+
     #[error("Stream not found: {0}")]
     StreamNotFound(&'static str),
+
+    /// The file opened fine but has no playable video, e.g. audio or cover art only
+    #[error(
+        "No video stream in {format} container{}",
+        if *audio_only { " (audio only)" } else { "" }
+    )]
+    NoVideoStream { format: String, audio_only: bool },
+
+    #[error("No playable video stream at index {0}")]
+    InvalidStreamIndex(usize),
+
+    /// The video decodes to a pixel format frames can't be converted from
+    #[error("Unsupported pixel format {format:?}, supported formats: {supported}")]
+    UnsupportedPixelFormat {
+        format: ffmpeg_next::format::Pixel,
+        supported: String, // Names of the supported formats, comma separated
+    },
+
+    /// The start frame is not before the end frame, or the end is past the video
+    #[error("Invalid frame range {start}-{end}, the video has {total} frames")]
+    InvalidFrameRange { start: u64, end: u64, total: u64 },
+
+    #[error("Video loading error: {0}")]
+    Load(String),
+
+    #[error("Audio error: {0}")]
+    Audio(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_video_tells_audio_only_files_apart() {
+        let audio_only = VideoError::NoVideoStream {
+            format: "mp3".into(),
+            audio_only: true,
+        };
+        let empty = VideoError::NoVideoStream {
+            format: "matroska,webm".into(),
+            audio_only: false,
+        };
+
+        assert_eq!(
+            audio_only.to_string(),
+            "No video stream in mp3 container (audio only)"
+        );
+        assert_eq!(
+            empty.to_string(),
+            "No video stream in matroska,webm container"
+        );
+    }
 }
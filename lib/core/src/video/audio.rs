@@ -0,0 +1,407 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use ffmpeg_next::{
+    self as ffmpeg,
+    format::{sample::Type as SampleType, Sample},
+    ChannelLayout,
+};
+use orbit_video_player_common::VideoError;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+};
+use tracing::{debug, error, info, trace, warn};
+
+// Extra room in resampled frames for samples the resampler held back
+const RESAMPLER_HEADROOM: usize = 256;
+
+/// Audio track of a video, played on the default output device
+///
+/// Packets are demuxed on the decode thread of `VideoStream` and decoded here
+/// into interleaved f32 samples at the rate and channel count of the output
+/// device. The output runs on its own thread and is controlled through
+/// `AudioOutput`.
+pub struct AudioTrack {
+    stream_index: usize,
+    time_base: f64,    // Seconds per PTS unit of the audio stream
+    start_offset: f64, // Start time of the video stream, audio times are relative to it
+    decoder: ffmpeg::decoder::Audio,
+    resampler: ffmpeg::software::resampling::Context,
+    output: AudioOutput,
+}
+
+/// Playback side of an audio track, shared with the thread decoding it
+///
+/// Its playback position is the clock the video is synced against.
+#[derive(Clone)]
+pub struct AudioOutput {
+    sample_rate: u32,
+    channels: u16,
+    shared: Arc<SharedState>,
+    _stop: mpsc::Sender<()>, // Stops the output thread once every copy is dropped
+}
+
+/// State shared with the output callback
+struct SharedState {
+    buffer: Mutex<SampleBuffer>,
+    volume: AtomicU32, // Bits of an f32 gain
+    muted: AtomicBool,
+    paused: AtomicBool,
+}
+
+/// Decoded samples waiting to be played and the clock derived from them
+#[derive(Default)]
+struct SampleBuffer {
+    samples: VecDeque<f32>,  // Interleaved samples
+    start_time: Option<f64>, // Time of the first sample queued since the last reset
+    played: u64,             // Sample frames played since the last reset
+    skip_until: Option<f64>, // Drop decoded audio before this time, set when seeking
+}
+
+impl SharedState {
+    /// Write queued samples to the output, padding with silence when paused or starved
+    fn fill<T>(&self, data: &mut [T], channels: usize)
+    where
+        T: cpal::SizedSample + cpal::FromSample<f32>,
+    {
+        let gain = if self.muted.load(Ordering::Relaxed) {
+            0.0
+        } else {
+            f32::from_bits(self.volume.load(Ordering::Relaxed))
+        };
+
+        let mut buffer = self.buffer.lock().unwrap();
+        let available = if self.paused.load(Ordering::Relaxed) {
+            0
+        } else {
+            // Whole frames only, so channels stay in order
+            buffer.samples.len().min(data.len()) / channels * channels
+        };
+
+        for (output, sample) in data.iter_mut().zip(buffer.samples.drain(..available)) {
+            *output = T::from_sample(sample * gain);
+        }
+        data[available..].fill(T::EQUILIBRIUM);
+        buffer.played += (available / channels) as u64;
+    }
+}
+
+impl AudioTrack {
+    /// Open the best audio stream of the input and start playing to the default output device
+    ///
+    /// Returns `Ok(None)` if the input has no audio stream. `start_offset` is the
+    /// start time of the video stream in seconds.
+    pub fn open(
+        format_context: &ffmpeg::format::context::Input,
+        start_offset: f64,
+    ) -> Result<Option<Self>, VideoError> {
+        let Some(stream) = format_context.streams().best(ffmpeg::media::Type::Audio) else {
+            debug!("No audio stream found");
+            return Ok(None);
+        };
+        let stream_index = stream.index();
+        let time_base = f64::from(stream.time_base());
+
+        let context = ffmpeg::codec::Context::from_parameters(stream.parameters())?;
+        let decoder = context.decoder().audio()?;
+        info!(
+            "Audio stream {}: {:?}, {} Hz, {} channels",
+            stream_index,
+            decoder.id(),
+            decoder.rate(),
+            decoder.channels()
+        );
+
+        let shared = Arc::new(SharedState {
+            buffer: Mutex::new(SampleBuffer::default()),
+            volume: AtomicU32::new(1.0f32.to_bits()),
+            muted: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+        });
+        let (sample_rate, channels, stop) = start_output(shared.clone())?;
+
+        // Some decoders leave the layout empty, assume the default one for the channel count
+        let input_layout = match decoder.channel_layout() {
+            layout if layout.is_empty() => ChannelLayout::default(decoder.channels() as i32),
+            layout => layout,
+        };
+        let resampler = ffmpeg::software::resampling::Context::get(
+            decoder.format(),
+            input_layout,
+            decoder.rate(),
+            Sample::F32(SampleType::Packed),
+            ChannelLayout::default(channels as i32),
+            sample_rate,
+        )?;
+
+        Ok(Some(Self {
+            stream_index,
+            time_base,
+            start_offset,
+            decoder,
+            resampler,
+            output: AudioOutput {
+                sample_rate,
+                channels,
+                shared,
+                _stop: stop,
+            },
+        }))
+    }
+
+    /// Get the container index of the audio stream
+    pub fn stream_index(&self) -> usize {
+        self.stream_index
+    }
+
+    /// Get the playback side of the track, clone it to control playback from another thread
+    pub fn output(&self) -> &AudioOutput {
+        &self.output
+    }
+
+    /// Decode a packet and queue its samples for playback
+    ///
+    /// Packets of other streams are ignored. Audio that fails to decode is
+    /// skipped, so it never interrupts the video.
+    pub fn send_packet(&mut self, packet: &ffmpeg::Packet) {
+        if packet.stream() != self.stream_index {
+            return;
+        }
+
+        if let Err(e) = self.decoder.send_packet(packet) {
+            warn!("Skipping audio packet: {}", e);
+            return;
+        }
+
+        let mut frame = ffmpeg::frame::Audio::empty();
+        while self.decoder.receive_frame(&mut frame).is_ok() {
+            if let Err(e) = self.queue_frame(&frame) {
+                warn!("Failed to resample audio frame: {}", e);
+            }
+        }
+    }
+
+    /// Resample a decoded frame and append it to the sample buffer
+    fn queue_frame(&mut self, frame: &ffmpeg::frame::Audio) -> Result<(), VideoError> {
+        let time = frame
+            .pts()
+            .map(|pts| pts as f64 * self.time_base - self.start_offset);
+
+        let sample_rate = self.output.sample_rate;
+        let channels = self.output.channels as usize;
+
+        // Allocate enough room for the resampled frame so nothing piles up in the resampler
+        let capacity = frame.samples() * sample_rate as usize / frame.rate().max(1) as usize
+            + RESAMPLER_HEADROOM;
+        let mut output = ffmpeg::frame::Audio::new(
+            Sample::F32(SampleType::Packed),
+            capacity,
+            ChannelLayout::default(channels as i32),
+        );
+        self.resampler.run(frame, &mut output)?;
+
+        // Drop the part of the frame before the seek target
+        let frames = output.samples();
+        let mut buffer = self.output.shared.buffer.lock().unwrap();
+        let skip = match (buffer.skip_until, time) {
+            (Some(target), Some(time)) if time < target => {
+                ((target - time) * sample_rate as f64).round() as usize
+            }
+            _ => 0,
+        };
+        if skip >= frames {
+            trace!("Dropping audio frame before seek target at {:?}", time);
+            return Ok(());
+        }
+        let start_time = time
+            .map(|time| time + skip as f64 / sample_rate as f64)
+            .or(buffer.skip_until)
+            .unwrap_or(0.0);
+        buffer.skip_until = None;
+
+        let bytes = &output.data(0)[skip * channels * 4..frames * channels * 4];
+        if buffer.start_time.is_none() {
+            debug!("Audio clock starts at {:.3}s", start_time);
+            buffer.start_time = Some(start_time);
+        }
+        buffer.samples.extend(
+            bytes
+                .chunks_exact(4)
+                .map(|sample| f32::from_ne_bytes([sample[0], sample[1], sample[2], sample[3]])),
+        );
+        Ok(())
+    }
+
+    /// Drop all buffered audio and restart at the given time, e.g. after seeking
+    pub fn flush(&mut self, time_s: f64) {
+        debug!("Flushing audio, restarting at {:.3}s", time_s);
+        self.decoder.flush();
+        *self.output.shared.buffer.lock().unwrap() = SampleBuffer {
+            skip_until: Some(time_s),
+            ..SampleBuffer::default()
+        };
+    }
+}
+
+impl AudioOutput {
+    /// Drop buffered audio before the given time, e.g. to catch up after stepping through frames
+    pub fn skip_to(&self, time_s: f64) {
+        let mut buffer = self.shared.buffer.lock().unwrap();
+        let Some(start_time) = buffer.start_time else {
+            buffer.skip_until = Some(time_s);
+            return;
+        };
+
+        let position = start_time + buffer.played as f64 / self.sample_rate as f64;
+        if time_s > position {
+            let channels = self.channels as usize;
+            let frames = ((time_s - position) * self.sample_rate as f64) as usize;
+            let dropped = (frames * channels).min(buffer.samples.len()) / channels * channels;
+            buffer.samples.drain(..dropped);
+            buffer.played += (dropped / channels) as u64;
+            trace!(
+                "Skipped {} audio frames to {:.3}s",
+                dropped / channels,
+                time_s
+            );
+        }
+    }
+
+    /// Get the duration of the audio waiting to be played in seconds
+    pub fn buffered(&self) -> f64 {
+        let buffer = self.shared.buffer.lock().unwrap();
+        buffer.samples.len() as f64 / self.channels as f64 / self.sample_rate as f64
+    }
+
+    /// Get the time of the sample being played, `None` if no audio is playing
+    ///
+    /// The clock stops when the buffer runs dry, e.g. at the end of the audio,
+    /// and the video falls back to its own timer then.
+    pub fn clock(&self) -> Option<f64> {
+        if self.shared.paused.load(Ordering::Relaxed) {
+            return None;
+        }
+        let buffer = self.shared.buffer.lock().unwrap();
+        if buffer.samples.is_empty() {
+            return None;
+        }
+        buffer
+            .start_time
+            .map(|start_time| start_time + buffer.played as f64 / self.sample_rate as f64)
+    }
+
+    /// Pause or resume the output, paused output plays silence
+    pub fn set_paused(&self, paused: bool) {
+        self.shared.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Set the volume as a gain, 1.0 plays the audio unchanged
+    pub fn set_volume(&self, volume: f32) {
+        self.shared
+            .volume
+            .store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Get the volume as a gain
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.shared.volume.load(Ordering::Relaxed))
+    }
+
+    /// Mute or unmute the output without changing the volume
+    pub fn set_muted(&self, muted: bool) {
+        self.shared.muted.store(muted, Ordering::Relaxed);
+    }
+
+    /// Check if the output is muted
+    pub fn is_muted(&self) -> bool {
+        self.shared.muted.load(Ordering::Relaxed)
+    }
+}
+
+/// Start the output stream on its own thread and get its sample rate and channel count
+///
+/// The stream isn't `Send` on every platform, so it lives on the thread until
+/// the returned sender is dropped.
+fn start_output(shared: Arc<SharedState>) -> Result<(u32, u16, mpsc::Sender<()>), VideoError> {
+    let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+    let (ready_sender, ready_receiver) = mpsc::channel();
+
+    thread::Builder::new()
+        .name("audio-output".into())
+        .spawn(move || {
+            let stream = match build_output_stream(shared) {
+                Ok((stream, sample_rate, channels)) => {
+                    let _ = ready_sender.send(Ok((sample_rate, channels)));
+                    stream
+                }
+                Err(e) => {
+                    let _ = ready_sender.send(Err(e));
+                    return;
+                }
+            };
+
+            // Keep the stream playing until the track is dropped
+            let _ = stop_receiver.recv();
+            debug!("Stopping audio output");
+            drop(stream);
+        })
+        .map_err(|e| VideoError::Audio(format!("Failed to start audio thread: {}", e)))?;
+
+    let (sample_rate, channels) = ready_receiver
+        .recv()
+        .map_err(|_| VideoError::Audio("Audio output thread terminated".into()))??;
+    Ok((sample_rate, channels, stop_sender))
+}
+
+/// Open the default output device in its preferred configuration
+fn build_output_stream(shared: Arc<SharedState>) -> Result<(cpal::Stream, u32, u16), VideoError> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| VideoError::Audio("No audio output device".into()))?;
+    let supported = device
+        .default_output_config()
+        .map_err(|e| VideoError::Audio(e.to_string()))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+    debug!(
+        "Audio output: {} Hz, {} channels, {:?}",
+        config.sample_rate.0, config.channels, sample_format
+    );
+
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, shared),
+        cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, shared),
+        cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, shared),
+        format => Err(VideoError::Audio(format!(
+            "Unsupported output sample format {:?}",
+            format
+        ))),
+    }?;
+    stream
+        .play()
+        .map_err(|e| VideoError::Audio(e.to_string()))?;
+
+    Ok((stream, config.sample_rate.0, config.channels))
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    shared: Arc<SharedState>,
+) -> Result<cpal::Stream, VideoError>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| shared.fill(data, channels),
+            |e| error!("Audio output error: {}", e),
+            None,
+        )
+        .map_err(|e| VideoError::Audio(e.to_string()))
+}
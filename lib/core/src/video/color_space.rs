@@ -1,9 +1,24 @@
+use ffmpeg_next::color;
+
 pub struct ColorSpaceConfig {
     pub matrix: [[f32; 3]; 3],
     pub y_range: [f32; 2],
     pub uv_range: [f32; 2],
 }
 
+impl ColorSpaceConfig {
+    /// Y and UV ranges for video of the given range
+    ///
+    /// The configs hold the limited ranges, which are also used when the range
+    /// is unspecified as most video is limited range.
+    pub fn ranges(&self, range: ColorRange) -> ([f32; 2], [f32; 2]) {
+        match range {
+            ColorRange::Full => ([0.0, 1.0], [0.0, 1.0]),
+            ColorRange::Limited | ColorRange::Unspecified => (self.y_range, self.uv_range),
+        }
+    }
+}
+
 pub const BT709_CONFIG: ColorSpaceConfig = ColorSpaceConfig {
     matrix: [
         [1.0, 0.0, 1.5748],
@@ -13,3 +28,207 @@ pub const BT709_CONFIG: ColorSpaceConfig = ColorSpaceConfig {
     y_range: [16.0 / 255.0, 235.0 / 255.0],
     uv_range: [16.0 / 255.0, 240.0 / 255.0],
 };
+
+pub const BT601_CONFIG: ColorSpaceConfig = ColorSpaceConfig {
+    matrix: [
+        [1.0, 0.0, 1.402],
+        [1.0, -0.344136, -0.714136],
+        [1.0, 1.772, 0.0],
+    ],
+    y_range: [16.0 / 255.0, 235.0 / 255.0],
+    uv_range: [16.0 / 255.0, 240.0 / 255.0],
+};
+
+/// Non-constant luminance BT.2020, which is what practically all HDR content uses
+pub const BT2020_CONFIG: ColorSpaceConfig = ColorSpaceConfig {
+    matrix: [
+        [1.0, 0.0, 1.4746],
+        [1.0, -0.164553, -0.571353],
+        [1.0, 1.8814, 0.0],
+    ],
+    y_range: [16.0 / 255.0, 235.0 / 255.0],
+    uv_range: [16.0 / 255.0, 240.0 / 255.0],
+};
+
+/// Color properties detected for a video stream
+///
+/// Wraps the FFmpeg color enums so callers don't depend on `ffmpeg_next` types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ColorInfo {
+    pub space: ColorSpace,
+    pub primaries: ColorPrimaries,
+    pub transfer: ColorTransfer,
+    pub range: ColorRange,
+}
+
+/// YUV to RGB conversion matrix of a video
+///
+/// The discriminants match the `color_space` switch in the video shaders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    Bt709 = 0,
+    Bt601 = 1,
+    Bt2020 = 2,
+    Rgb = 3,
+    #[default]
+    Unspecified = 4,
+}
+
+/// Videos up to this height are SD, which is BT.601 when the color space is unspecified
+const MAX_SD_HEIGHT: u32 = 576;
+
+impl ColorSpace {
+    /// Resolve an unspecified color space from the video height like other players
+    ///
+    /// Untagged SD video (up to 576 lines) is BT.601, larger video BT.709.
+    /// Tagged color spaces are kept.
+    pub fn or_default_for_height(self, height: u32) -> Self {
+        match self {
+            ColorSpace::Unspecified if height <= MAX_SD_HEIGHT => ColorSpace::Bt601,
+            ColorSpace::Unspecified => ColorSpace::Bt709,
+            space => space,
+        }
+    }
+
+    /// YUV to RGB conversion for this color space, `None` if there's no matrix for it
+    pub fn config(self) -> Option<&'static ColorSpaceConfig> {
+        match self {
+            ColorSpace::Bt709 => Some(&BT709_CONFIG),
+            ColorSpace::Bt601 => Some(&BT601_CONFIG),
+            ColorSpace::Bt2020 => Some(&BT2020_CONFIG),
+            ColorSpace::Rgb | ColorSpace::Unspecified => None,
+        }
+    }
+}
+
+/// Color primaries of a video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorPrimaries {
+    Bt709,
+    Bt601,
+    Bt2020,
+    DisplayP3,
+    #[default]
+    Unspecified,
+}
+
+/// Transfer characteristic (gamma curve) of a video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorTransfer {
+    Bt709,
+    Srgb,
+    Linear,
+    Pq,
+    Hlg,
+    #[default]
+    Unspecified,
+}
+
+/// Range of the YUV values of a video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorRange {
+    /// Limited (MPEG/TV) range, 16-235 for luma
+    Limited,
+    /// Full (JPEG/PC) range, 0-255 for luma
+    Full,
+    #[default]
+    Unspecified,
+}
+
+impl From<color::Space> for ColorSpace {
+    fn from(space: color::Space) -> Self {
+        match space {
+            color::Space::BT709 => ColorSpace::Bt709,
+            color::Space::BT470BG | color::Space::SMPTE170M => ColorSpace::Bt601,
+            color::Space::BT2020NCL | color::Space::BT2020CL => ColorSpace::Bt2020,
+            color::Space::RGB => ColorSpace::Rgb,
+            _ => ColorSpace::Unspecified,
+        }
+    }
+}
+
+impl From<color::Primaries> for ColorPrimaries {
+    fn from(primaries: color::Primaries) -> Self {
+        match primaries {
+            color::Primaries::BT709 => ColorPrimaries::Bt709,
+            color::Primaries::BT470BG | color::Primaries::SMPTE170M => ColorPrimaries::Bt601,
+            color::Primaries::BT2020 => ColorPrimaries::Bt2020,
+            color::Primaries::SMPTE431 | color::Primaries::SMPTE432 => ColorPrimaries::DisplayP3,
+            _ => ColorPrimaries::Unspecified,
+        }
+    }
+}
+
+impl From<color::TransferCharacteristic> for ColorTransfer {
+    fn from(transfer: color::TransferCharacteristic) -> Self {
+        match transfer {
+            color::TransferCharacteristic::BT709
+            | color::TransferCharacteristic::BT2020_10
+            | color::TransferCharacteristic::BT2020_12 => ColorTransfer::Bt709,
+            color::TransferCharacteristic::IEC61966_2_1 => ColorTransfer::Srgb,
+            color::TransferCharacteristic::Linear => ColorTransfer::Linear,
+            color::TransferCharacteristic::SMPTE2084 => ColorTransfer::Pq,
+            color::TransferCharacteristic::ARIB_STD_B67 => ColorTransfer::Hlg,
+            _ => ColorTransfer::Unspecified,
+        }
+    }
+}
+
+impl From<color::Range> for ColorRange {
+    fn from(range: color::Range) -> Self {
+        match range {
+            color::Range::MPEG => ColorRange::Limited,
+            color::Range::JPEG => ColorRange::Full,
+            _ => ColorRange::Unspecified,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tagged_color_spaces_select_their_matrix() {
+        let cases = [
+            (color::Space::BT709, ColorSpace::Bt709, 0),
+            (color::Space::BT470BG, ColorSpace::Bt601, 1),
+            (color::Space::SMPTE170M, ColorSpace::Bt601, 1),
+            (color::Space::BT2020NCL, ColorSpace::Bt2020, 2),
+        ];
+        for (space, expected, shader_index) in cases {
+            let color_space = ColorSpace::from(space);
+            assert_eq!(color_space, expected, "{:?}", space);
+            assert_eq!(color_space as u32, shader_index, "{:?}", space);
+        }
+
+        let matrix = |space: ColorSpace| space.config().unwrap().matrix;
+        assert_eq!(matrix(ColorSpace::Bt709), BT709_CONFIG.matrix);
+        assert_eq!(matrix(ColorSpace::Bt601), BT601_CONFIG.matrix);
+        assert_eq!(matrix(ColorSpace::Bt2020), BT2020_CONFIG.matrix);
+        assert_ne!(BT601_CONFIG.matrix, BT709_CONFIG.matrix);
+        assert_ne!(BT2020_CONFIG.matrix, BT709_CONFIG.matrix);
+    }
+
+    #[test]
+    fn unspecified_color_space_falls_back_by_height() {
+        let unspecified = ColorSpace::from(color::Space::Unspecified);
+        assert_eq!(unspecified, ColorSpace::Unspecified);
+        assert!(unspecified.config().is_none());
+
+        assert_eq!(unspecified.or_default_for_height(480), ColorSpace::Bt601);
+        assert_eq!(unspecified.or_default_for_height(576), ColorSpace::Bt601);
+        assert_eq!(unspecified.or_default_for_height(720), ColorSpace::Bt709);
+        assert_eq!(unspecified.or_default_for_height(2160), ColorSpace::Bt709);
+
+        // Tagged video keeps its matrix regardless of the size
+        assert_eq!(
+            ColorSpace::Bt709.or_default_for_height(480),
+            ColorSpace::Bt709
+        );
+        assert_eq!(
+            ColorSpace::Bt601.or_default_for_height(1080),
+            ColorSpace::Bt601
+        );
+    }
+}
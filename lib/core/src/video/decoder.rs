@@ -0,0 +1,876 @@
+use ffmpeg_next::{
+    self as ffmpeg,
+    error::{EAGAIN, EINTR, EIO, ETIMEDOUT},
+    ffi::{
+        av_hwframe_transfer_data, AVColorPrimaries, AVColorRange, AVColorSpace,
+        AVColorTransferCharacteristic,
+    },
+};
+use orbit_video_player_common::VideoError;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{debug, error, trace, warn};
+
+use super::{
+    audio::AudioTrack,
+    color_space::ColorRange,
+    stream::{chroma_size, past_end_frame, FrameTiming, OutputPixelFormat, QueuedFrame, SeekMode},
+};
+
+// Decoded frames waiting for the stream to move them into its queue
+const CHANNEL_SIZE: usize = 2;
+const MAX_PACKETS_PER_FRAME: usize = 100;
+const READ_RETRY_BASE_DELAY: Duration = Duration::from_millis(10);
+const READ_RETRY_MAX_DELAY: Duration = Duration::from_millis(500);
+const MIN_AUDIO_BUFFER: f64 = 0.5; // Seconds of audio to keep decoded ahead
+const MAX_PENDING_PACKETS: usize = 256;
+
+/// Check if a packet read error may succeed when retried
+///
+/// End of file is not transient and is handled separately by the caller.
+fn is_transient_read_error(error: &ffmpeg::Error) -> bool {
+    matches!(
+        error,
+        ffmpeg::Error::Other {
+            errno: EAGAIN | EINTR | ETIMEDOUT | EIO
+        }
+    )
+}
+
+/// Check if a decoding error means the packet data is corrupt
+///
+/// Corrupt packets are skipped, other errors are fatal.
+fn is_corrupt_data_error(error: &ffmpeg::Error) -> bool {
+    matches!(error, ffmpeg::Error::InvalidData)
+}
+
+/// Copy a frame decoded on the GPU to system memory
+///
+/// Software decoded frames, e.g. of codecs the hardware doesn't support, are returned as is.
+fn download_frame(frame: ffmpeg::frame::Video) -> Result<ffmpeg::frame::Video, VideoError> {
+    if unsafe { (*frame.as_ptr()).hw_frames_ctx.is_null() } {
+        return Ok(frame);
+    }
+
+    let mut sw_frame = ffmpeg::frame::Video::empty();
+    let result = unsafe { av_hwframe_transfer_data(sw_frame.as_mut_ptr(), frame.as_ptr(), 0) };
+    if result < 0 {
+        return Err(VideoError::Decode(format!(
+            "Failed to download hardware frame: {}",
+            ffmpeg::Error::from(result)
+        )));
+    }
+    sw_frame.set_pts(frame.pts());
+    Ok(sw_frame)
+}
+
+/// Append `rows` rows of `row_size` bytes of a plane, skipping the line padding
+///
+/// `stride` is the number of bytes per line of the plane, at least `row_size`.
+pub(crate) fn copy_plane(
+    buffer: &mut Vec<u8>,
+    plane: &[u8],
+    stride: usize,
+    row_size: usize,
+    rows: usize,
+) {
+    for y in 0..rows {
+        let start = y * stride;
+        buffer.extend_from_slice(&plane[start..start + row_size]);
+    }
+}
+
+fn thread_terminated() -> VideoError {
+    VideoError::Decode("Decode thread terminated".into())
+}
+
+/// Counters kept by the decode thread, read by the stream
+#[derive(Debug, Default)]
+pub(crate) struct DecodeStats {
+    pub bytes_decoded: AtomicU64, // Size of all video packets sent to the decoder
+    pub corrupt_frames: AtomicU64, // Number of corrupt packets skipped
+}
+
+/// Message from the decode thread
+pub(crate) enum Decoded {
+    /// The next frame, with the time it took to decode unless it is the target of a seek
+    Frame(QueuedFrame, Option<Duration>),
+    /// The decoder was drained or the end frame reached, nothing follows until the next seek
+    End,
+    /// Decoding failed, nothing follows until the next seek
+    Failed(VideoError),
+    /// The frame requested with `DecodeThread::grab`
+    Grabbed(Result<Vec<u8>, VideoError>),
+}
+
+/// Request to the decode thread, handled before decoding the next frame
+enum Command {
+    Seek {
+        generation: u64,
+        time_s: f64,
+        mode: SeekMode,
+    },
+    Grab {
+        time_s: f64,
+        shown_frame: u64,
+    },
+    SetEndFrame(Option<u64>),
+}
+
+/// Handle of the thread decoding frames ahead of playback
+///
+/// Frames arrive through a bounded channel, so the thread only decodes as far
+/// ahead as the stream takes them. The thread stops once the handle is dropped.
+pub(crate) struct DecodeThread {
+    commands: mpsc::Sender<Command>,
+    decoded: mpsc::Receiver<(u64, Decoded)>, // Messages tagged with the seek they belong to
+    generation: u64,                         // Number of seeks sent, earlier messages are dropped
+    finished: bool,                          // `End` or `Failed` was received for the current seek
+    stats: Arc<DecodeStats>,
+}
+
+impl DecodeThread {
+    /// Start decoding on a new thread from where the input was sought to
+    pub fn spawn(decoder: FrameDecoder) -> Result<Self, VideoError> {
+        let (command_sender, command_receiver) = mpsc::channel();
+        let (decoded_sender, decoded_receiver) = mpsc::sync_channel(CHANNEL_SIZE);
+        let stats = decoder.stats.clone();
+
+        thread::Builder::new()
+            .name("video-decode".into())
+            .spawn(move || decoder.run(command_receiver, decoded_sender))
+            .map_err(|e| VideoError::Decode(format!("Failed to start decode thread: {}", e)))?;
+
+        Ok(Self {
+            commands: command_sender,
+            decoded: decoded_receiver,
+            generation: 0,
+            finished: false,
+            stats,
+        })
+    }
+
+    /// Flush the decoder and restart decoding at a time
+    ///
+    /// Frames decoded before the seek are dropped, the first frame received
+    /// afterwards is the one at the time.
+    pub fn seek(&mut self, time_s: f64, mode: SeekMode) -> Result<(), VideoError> {
+        self.generation += 1;
+        self.finished = false;
+        self.send(Command::Seek {
+            generation: self.generation,
+            time_s,
+            mode,
+        })
+    }
+
+    /// Request the frame at a time without changing the playback position
+    ///
+    /// The frame arrives as `Decoded::Grabbed` after the frames decoded before
+    /// it. `shown_frame` is the frame on screen, the audio restarts there.
+    pub fn grab(&self, time_s: f64, shown_frame: u64) -> Result<(), VideoError> {
+        self.send(Command::Grab {
+            time_s,
+            shown_frame,
+        })
+    }
+
+    /// Change the last frame to decode, applies from the next seek on
+    pub fn set_end_frame(&self, end_frame: Option<u64>) -> Result<(), VideoError> {
+        self.send(Command::SetEndFrame(end_frame))
+    }
+
+    fn send(&self, command: Command) -> Result<(), VideoError> {
+        self.commands.send(command).map_err(|_| thread_terminated())
+    }
+
+    /// Get the next message if one is ready
+    ///
+    /// Returns `None` if there is none yet or the current seek has finished.
+    pub fn try_recv(&mut self) -> Result<Option<Decoded>, VideoError> {
+        while !self.finished {
+            match self.decoded.try_recv() {
+                Ok((generation, decoded)) => {
+                    if let Some(decoded) = self.accept(generation, decoded) {
+                        return Ok(Some(decoded));
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => return Ok(None),
+                Err(mpsc::TryRecvError::Disconnected) => return Err(thread_terminated()),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Wait for the next message
+    ///
+    /// Check `is_finished` first, nothing but grabbed frames arrive afterwards.
+    pub fn recv(&mut self) -> Result<Decoded, VideoError> {
+        loop {
+            let (generation, decoded) = self.decoded.recv().map_err(|_| thread_terminated())?;
+            if let Some(decoded) = self.accept(generation, decoded) {
+                return Ok(decoded);
+            }
+        }
+    }
+
+    /// Drop messages of earlier seeks and track the end of the current one
+    fn accept(&mut self, generation: u64, decoded: Decoded) -> Option<Decoded> {
+        if generation != self.generation {
+            trace!("Dropping message decoded before seek {}", self.generation);
+            return None;
+        }
+        if matches!(decoded, Decoded::End | Decoded::Failed(_)) {
+            self.finished = true;
+        }
+        Some(decoded)
+    }
+
+    /// Check if no more frames arrive until the next seek
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Get the total size in bytes of the video packets decoded so far
+    pub fn bytes_decoded(&self) -> u64 {
+        self.stats.bytes_decoded.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of corrupt packets skipped so far
+    pub fn corrupt_frames(&self) -> u64 {
+        self.stats.corrupt_frames.load(Ordering::Relaxed)
+    }
+}
+
+/// Decoding state of a video stream, moved to the decode thread
+///
+/// Reads packets, decodes and converts frames to the queued layout, and feeds
+/// the audio packets to the audio track on the way.
+pub(crate) struct FrameDecoder {
+    pub decoder: ffmpeg::decoder::Video,
+    pub format_context: ffmpeg::format::context::Input,
+    pub video_stream_index: usize,
+    pub timing: FrameTiming,
+    pub next_frame: u64, // Number of the next frame to decode
+    pub end_frame: Option<u64>,
+    pub end_of_file: bool, // Whether the decoder has been fully drained
+    pub max_read_retries: u32,
+    pub max_corrupt_packets: u32,
+    pub repeat_frame_on_corrupt: bool,
+    pub consecutive_corrupt: u32, // Corrupt packets since the last decoded frame
+    pub has_alpha: bool,
+    pub high_bit_depth: bool,
+    pub output_format: OutputPixelFormat,
+    pub color_range: ColorRange,
+    pub frame_buffer: Vec<u8>,           // Buffer for processing frames
+    pub yuv_frame: ffmpeg::frame::Video, // Reusable frame object
+    pub scaler: ffmpeg::software::scaling::Context,
+    pub audio: Option<AudioTrack>,
+    pub pending_packets: VecDeque<ffmpeg::Packet>, // Packets read ahead to keep the audio buffered
+    pub resumed_frame: Option<QueuedFrame>,        // Decoded when resuming after a grab, sent next
+    pub stats: Arc<DecodeStats>,
+}
+
+// SAFETY: the codec and format contexts, frames and packets are `Send` in
+// ffmpeg-next. The video scaler (`SwsContext`) and the audio resampler of
+// `audio` (`SwrContext`) are not, only because they are wrapped as raw pointers:
+// neither is tied to the thread that created it, libswscale and libswresample
+// only require that a context isn't used from two threads at once.
+// `VideoStream::new` creates them, moves them into the `FrameDecoder` without
+// keeping any other handle, and `DecodeThread::spawn` moves the `FrameDecoder`
+// to the decode thread as a whole. From then on only that thread touches the
+// contexts, through `&mut self`.
+unsafe impl Send for FrameDecoder {}
+
+impl FrameDecoder {
+    /// Decode until the stream is dropped, handling commands between frames
+    ///
+    /// Blocks on the channel while it is full, and waits for a command once
+    /// there is nothing left to decode.
+    fn run(mut self, commands: mpsc::Receiver<Command>, decoded: mpsc::SyncSender<(u64, Decoded)>) {
+        let mut generation = 0;
+        let mut idle = false; // Nothing to decode until the next seek
+
+        loop {
+            let command = if idle {
+                match commands.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => break,
+                }
+            } else {
+                match commands.try_recv() {
+                    Ok(command) => Some(command),
+                    Err(mpsc::TryRecvError::Empty) => None,
+                    Err(mpsc::TryRecvError::Disconnected) => break,
+                }
+            };
+
+            let message = match command {
+                Some(Command::Seek {
+                    generation: seek,
+                    time_s,
+                    mode,
+                }) => {
+                    generation = seek;
+                    idle = false;
+                    match self.seek(time_s, mode) {
+                        Ok(Some(frame)) => Decoded::Frame(frame, None),
+                        Ok(None) => Decoded::End,
+                        Err(e) => Decoded::Failed(e),
+                    }
+                }
+                Some(Command::Grab {
+                    time_s,
+                    shown_frame,
+                }) => Decoded::Grabbed(self.grab_frame_at(time_s, shown_frame)),
+                Some(Command::SetEndFrame(end_frame)) => {
+                    self.end_frame = end_frame;
+                    continue;
+                }
+                None => {
+                    let started = Instant::now();
+                    match self.decode_next_frame() {
+                        Ok(Some(frame)) => Decoded::Frame(frame, Some(started.elapsed())),
+                        Ok(None) => Decoded::End,
+                        Err(e) => Decoded::Failed(e),
+                    }
+                }
+            };
+            if matches!(message, Decoded::End | Decoded::Failed(_)) {
+                idle = true;
+            }
+
+            // The stream was dropped
+            if decoded.send((generation, message)).is_err() {
+                break;
+            }
+
+            if !idle {
+                if let Err(e) = self.buffer_audio() {
+                    idle = true;
+                    if decoded.send((generation, Decoded::Failed(e))).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        debug!("Decode thread stopped");
+    }
+
+    /// Check if there are no more frames to decode
+    ///
+    /// This is the case once the configured end frame has been decoded or the
+    /// decoder has been drained at the end of the file.
+    fn reached_end(&self) -> bool {
+        self.end_of_file || past_end_frame(self.next_frame, self.end_frame)
+    }
+
+    /// Process a decoded frame into a Y plane followed by an interleaved UV plane
+    fn process_video_frame(&mut self, frame: &ffmpeg::frame::Video) -> Result<Vec<u8>, VideoError> {
+        self.frame_buffer.clear();
+
+        // Preserve color properties in output frame
+        unsafe {
+            (*self.yuv_frame.as_mut_ptr()).colorspace = AVColorSpace::AVCOL_SPC_BT709;
+            (*self.yuv_frame.as_mut_ptr()).color_primaries = AVColorPrimaries::AVCOL_PRI_BT709;
+            (*self.yuv_frame.as_mut_ptr()).color_trc =
+                AVColorTransferCharacteristic::AVCOL_TRC_BT709;
+            (*self.yuv_frame.as_mut_ptr()).color_range = match self.color_range {
+                ColorRange::Full => AVColorRange::AVCOL_RANGE_JPEG,
+                _ => AVColorRange::AVCOL_RANGE_MPEG,
+            };
+        }
+
+        // Hardware decoders output a different format than the codec, e.g. NV12
+        if frame.format() != self.scaler.input().format {
+            debug!(
+                "Decoded format changed to {:?}, recreating scaler",
+                frame.format()
+            );
+            let input = self.scaler.input();
+            let output = self.scaler.output();
+            self.scaler.cached(
+                frame.format(),
+                input.width,
+                input.height,
+                output.format,
+                output.width,
+                output.height,
+                ffmpeg::software::scaling::Flags::BITEXACT
+                    | ffmpeg::software::scaling::Flags::ACCURATE_RND,
+            );
+        }
+
+        // Convert frame format if needed
+        self.scaler.run(frame, &mut self.yuv_frame)?;
+
+        // Copy at the output size, smaller than the decoder's for a target size
+        let width = self.scaler.output().width as usize;
+        let height = self.scaler.output().height as usize;
+        let (uv_width, uv_height) = chroma_size(width as u32, height as u32);
+        let (uv_width, uv_height) = (uv_width as usize, uv_height as usize);
+        // Bytes per sample, rows are copied as bytes
+        let sample_size = if self.high_bit_depth { 2 } else { 1 };
+        let row_size = width * sample_size;
+
+        // Copy Y plane row by row (full resolution), skipping the line padding
+        copy_plane(
+            &mut self.frame_buffer,
+            self.yuv_frame.data(0),
+            self.yuv_frame.stride(0),
+            row_size,
+            height,
+        );
+
+        match self.output_format {
+            // Copy the already interleaved UV plane row by row (half resolution)
+            OutputPixelFormat::Nv12 => copy_plane(
+                &mut self.frame_buffer,
+                self.yuv_frame.data(1),
+                self.yuv_frame.stride(1),
+                uv_width * 2,
+                uv_height,
+            ),
+            // Interleave U and V planes (half resolution)
+            OutputPixelFormat::Yuv420p => {
+                let u_stride = self.yuv_frame.stride(1);
+                let v_stride = self.yuv_frame.stride(2);
+                let uv_row_size = uv_width * sample_size;
+                for y in 0..uv_height {
+                    let u_line = &self.yuv_frame.data(1)[y * u_stride..y * u_stride + uv_row_size];
+                    let v_line = &self.yuv_frame.data(2)[y * v_stride..y * v_stride + uv_row_size];
+
+                    for (u, v) in u_line
+                        .chunks_exact(sample_size)
+                        .zip(v_line.chunks_exact(sample_size))
+                    {
+                        self.frame_buffer.extend_from_slice(u);
+                        self.frame_buffer.extend_from_slice(v);
+                    }
+                }
+            }
+        }
+
+        // Copy the alpha plane (full resolution) after the chroma
+        if self.has_alpha {
+            copy_plane(
+                &mut self.frame_buffer,
+                self.yuv_frame.data(3),
+                self.yuv_frame.stride(3),
+                row_size,
+                height,
+            );
+        }
+
+        trace!(
+            "Processed frame with size: {} bytes",
+            self.frame_buffer.len()
+        );
+        Ok(self.frame_buffer.clone())
+    }
+
+    /// Convert a decoded frame for the queue, numbered as the next frame
+    fn queue_frame(&mut self, frame: ffmpeg::frame::Video) -> Result<QueuedFrame, VideoError> {
+        let frame = download_frame(frame)?;
+        let data = self.process_video_frame(&frame)?;
+        trace!("Decoded frame {}", self.next_frame);
+
+        let frame = QueuedFrame {
+            data,
+            frame_number: self.next_frame,
+        };
+        self.next_frame += 1;
+        Ok(frame)
+    }
+
+    /// Decode the next frame from the video stream
+    ///
+    /// Returns `None` if there are no more frames to decode.
+    fn decode_next_frame(&mut self) -> Result<Option<QueuedFrame>, VideoError> {
+        if let Some(frame) = self.resumed_frame.take() {
+            return Ok(Some(frame));
+        }
+
+        // Stop decoding once the end has been reached
+        if self.reached_end() {
+            trace!("End reached at frame {}, skipping decode", self.next_frame);
+            return Ok(None);
+        }
+
+        let mut packets_sent = 0;
+        let mut frame = ffmpeg::frame::Video::empty();
+
+        loop {
+            // Prevent infinite loop by limiting packet processing
+            if packets_sent >= MAX_PACKETS_PER_FRAME {
+                return Err(VideoError::Decode(
+                    "Too many packets sent without decoding frame".into(),
+                ));
+            }
+
+            match self.decoder.receive_frame(&mut frame) {
+                Ok(_) => {
+                    trace!(
+                        "Decoded frame - PTS: {}, Timestamp: {}",
+                        frame.pts().unwrap_or(-1),
+                        frame.timestamp().unwrap_or(-1),
+                    );
+                    self.consecutive_corrupt = 0;
+                    return self.queue_frame(frame).map(Some);
+                }
+                Err(ffmpeg::Error::Other { errno: EAGAIN }) => {
+                    // Need more input data
+                    if let Some(packet) = self.read_packet()? {
+                        if packet.stream() == self.video_stream_index {
+                            self.stats
+                                .bytes_decoded
+                                .fetch_add(packet.size() as u64, Ordering::Relaxed);
+                            packets_sent += 1;
+                            match self.decoder.send_packet(&packet) {
+                                Ok(()) => {}
+                                Err(e) if is_corrupt_data_error(&e) => {
+                                    self.skip_corrupt_packet(&e)?;
+                                    if let Some(frame) = self.repeat_last_frame() {
+                                        return Ok(Some(frame));
+                                    }
+                                }
+                                Err(e) => return Err(e.into()),
+                            }
+                        } else if let Some(audio) = &mut self.audio {
+                            audio.send_packet(&packet);
+                        }
+                    } else {
+                        // End of stream, flush decoder and drain the remaining frames
+                        self.decoder.send_eof()?;
+                    }
+                }
+                Err(ffmpeg::Error::Eof) => {
+                    debug!("Decoder drained at frame {}", self.next_frame);
+                    self.end_of_file = true;
+                    return Ok(None);
+                }
+                Err(e) if is_corrupt_data_error(&e) => {
+                    self.skip_corrupt_packet(&e)?;
+                    if let Some(frame) = self.repeat_last_frame() {
+                        return Ok(Some(frame));
+                    }
+                }
+                Err(e) => return Err(VideoError::Decode(e.to_string())),
+            }
+        }
+    }
+
+    /// Count a packet the decoder rejected as corrupt
+    ///
+    /// Fails once more than `max_corrupt_packets` corrupt packets follow each other.
+    fn skip_corrupt_packet(&mut self, error: &ffmpeg::Error) -> Result<(), VideoError> {
+        self.stats.corrupt_frames.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_corrupt += 1;
+
+        if self.consecutive_corrupt > self.max_corrupt_packets {
+            error!(
+                "Giving up after {} corrupt packets in a row: {}",
+                self.consecutive_corrupt, error
+            );
+            return Err(VideoError::Decode(format!(
+                "{} corrupt packets in a row: {}",
+                self.consecutive_corrupt, error
+            )));
+        }
+
+        warn!(
+            "Skipping corrupt packet at frame {} ({} in a row): {}",
+            self.next_frame, self.consecutive_corrupt, error
+        );
+        Ok(())
+    }
+
+    /// Get the previous good frame again in place of a corrupt packet
+    ///
+    /// Only if enabled with `repeat_frame_on_corrupt` and a frame has been
+    /// decoded since the last seek.
+    fn repeat_last_frame(&mut self) -> Option<QueuedFrame> {
+        if !self.repeat_frame_on_corrupt || self.frame_buffer.is_empty() {
+            return None;
+        }
+
+        debug!("Repeating previous frame as frame {}", self.next_frame);
+        let frame = QueuedFrame {
+            data: self.frame_buffer.clone(),
+            frame_number: self.next_frame,
+        };
+        self.next_frame += 1;
+        Some(frame)
+    }
+
+    /// Read the next packet, starting with the ones read ahead for the audio
+    fn read_packet(&mut self) -> Result<Option<ffmpeg::Packet>, VideoError> {
+        match self.pending_packets.pop_front() {
+            Some(packet) => Ok(Some(packet)),
+            None => self.read_input_packet(),
+        }
+    }
+
+    /// Read the next packet from the input
+    ///
+    /// Transient read errors are retried up to `max_read_retries` times with
+    /// exponential backoff. Returns `None` at the end of the file.
+    fn read_input_packet(&mut self) -> Result<Option<ffmpeg::Packet>, VideoError> {
+        let mut retries = 0;
+
+        loop {
+            let mut packet = ffmpeg::Packet::empty();
+            match packet.read(&mut self.format_context) {
+                Ok(()) => return Ok(Some(packet)),
+                Err(ffmpeg::Error::Eof) => return Ok(None),
+                Err(e) if is_transient_read_error(&e) && retries < self.max_read_retries => {
+                    let delay = READ_RETRY_BASE_DELAY
+                        .saturating_mul(1 << retries.min(16))
+                        .min(READ_RETRY_MAX_DELAY);
+                    retries += 1;
+                    warn!(
+                        "Transient read error: {} (retry {}/{} in {:?})",
+                        e, retries, self.max_read_retries, delay
+                    );
+                    thread::sleep(delay);
+                }
+                Err(e) => {
+                    error!("Failed to read packet after {} retries: {}", retries, e);
+                    return Err(VideoError::Decode(format!("Failed to read packet: {}", e)));
+                }
+            }
+        }
+    }
+
+    /// Seek to a time and decode the frame shown there
+    ///
+    /// Returns `None` if no frame could be decoded before the end of the file.
+    fn seek(&mut self, time_s: f64, mode: SeekMode) -> Result<Option<QueuedFrame>, VideoError> {
+        let target_ts = self.seek_input(time_s)?;
+
+        // The first frame at or after the target is decoded
+        self.next_frame = self.timing.frame_at_time(time_s);
+        debug!("New current frame after seek: {}", self.next_frame);
+
+        self.decode_target(target_ts, mode)
+    }
+
+    /// Flush the decoders and seek the input to the keyframe before a time
+    ///
+    /// Returns the timestamp of the time, see `FrameTiming::time_to_ts`.
+    fn seek_input(&mut self, time_s: f64) -> Result<i64, VideoError> {
+        let target_ts = self.timing.time_to_ts(time_s);
+
+        self.pending_packets.clear();
+        self.resumed_frame = None;
+        self.decoder.flush();
+        if let Some(audio) = &mut self.audio {
+            audio.flush(time_s);
+        }
+        self.end_of_file = false;
+
+        // Perform the seek operation
+        unsafe {
+            ffmpeg::sys::avformat_seek_file(
+                self.format_context.as_mut_ptr(),
+                self.video_stream_index as i32,
+                i64::MIN,
+                target_ts,
+                target_ts,
+                ffmpeg::sys::AVSEEK_FLAG_BACKWARD,
+            )
+        };
+        Ok(target_ts)
+    }
+
+    /// Decode from the keyframe the input was sought to up to a target timestamp
+    ///
+    /// With `SeekMode::Fast` the first frame decoded after the seek is returned
+    /// instead of the one at the target, numbered from its PTS.
+    fn decode_target(
+        &mut self,
+        target_ts: i64,
+        mode: SeekMode,
+    ) -> Result<Option<QueuedFrame>, VideoError> {
+        // Don't repeat a frame from before the seek for corrupt packets
+        self.frame_buffer.clear();
+
+        // Last frame decoded before the target, used if the target is past the end
+        let mut last_frame = None;
+        loop {
+            let mut frame = ffmpeg::frame::Video::empty();
+            match self.decoder.receive_frame(&mut frame) {
+                Ok(_) => {
+                    let pts = frame.pts().unwrap_or(-1);
+                    if mode == SeekMode::Fast {
+                        // Land on the keyframe, numbered like an accurate seek to it
+                        let time_s = self.timing.ts_to_time(pts.max(0));
+                        self.next_frame = self.timing.frame_at_time(time_s);
+                        debug!(
+                            "Fast seek landed at PTS: {} (frame {})",
+                            pts, self.next_frame
+                        );
+                        return self.queue_frame(frame).map(Some);
+                    }
+                    if pts >= target_ts {
+                        debug!("Found target frame at PTS: {}", pts);
+                        return self.queue_frame(frame).map(Some);
+                    }
+                    self.consecutive_corrupt = 0;
+                    last_frame = Some(frame);
+                }
+                Err(ffmpeg::Error::Other { errno: EAGAIN }) => {
+                    match self.read_packet()? {
+                        Some(packet) if packet.stream() == self.video_stream_index => {
+                            self.stats
+                                .bytes_decoded
+                                .fetch_add(packet.size() as u64, Ordering::Relaxed);
+                            match self.decoder.send_packet(&packet) {
+                                Ok(()) => {}
+                                Err(e) if is_corrupt_data_error(&e) => {
+                                    self.skip_corrupt_packet(&e)?
+                                }
+                                Err(e) => return Err(e.into()),
+                            }
+                        }
+                        Some(packet) => {
+                            if let Some(audio) = &mut self.audio {
+                                audio.send_packet(&packet);
+                            }
+                        }
+                        None => {
+                            // End of file reached during seek, drain the remaining frames
+                            self.decoder.send_eof()?;
+                        }
+                    }
+                }
+                Err(ffmpeg::Error::Eof) => {
+                    // Seeking past the last frame, land on the last decodable one
+                    debug!("Reached end of file during seek without finding target");
+                    self.end_of_file = true;
+                    return match last_frame {
+                        Some(frame) => self.queue_frame(frame).map(Some),
+                        None => {
+                            warn!("No frame decoded before end of file");
+                            Ok(None)
+                        }
+                    };
+                }
+                Err(e) if is_corrupt_data_error(&e) => self.skip_corrupt_packet(&e)?,
+                Err(e) => return Err(VideoError::Decode(e.to_string())),
+            }
+        }
+    }
+
+    /// Decode the frame at a time, then continue decoding where it left off
+    ///
+    /// See `VideoStream::grab_frame_at`. `shown_frame` is the frame on screen,
+    /// the audio is rebuffered from there.
+    fn grab_frame_at(&mut self, time_s: f64, shown_frame: u64) -> Result<Vec<u8>, VideoError> {
+        let next_frame = self
+            .resumed_frame
+            .take()
+            .map_or(self.next_frame, |frame| frame.frame_number);
+        let end_of_file = self.end_of_file;
+        // Packets read for the grab would be queued as audio from the wrong time
+        let audio = self.audio.take();
+
+        // Decode just the frame at the time
+        let result = self.seek(time_s, SeekMode::Accurate).and_then(|frame| {
+            frame
+                .map(|frame| frame.data)
+                .ok_or_else(|| VideoError::Decode(format!("No frame decoded at {:.3}s", time_s)))
+        });
+
+        // Restore the decoding position
+        self.audio = audio;
+        self.next_frame = next_frame;
+        self.resume_decoding(end_of_file, shown_frame)?;
+
+        debug!("Grabbed frame at {:.3}s", time_s);
+        result
+    }
+
+    /// Seek back to where decoding left off before `grab_frame_at`
+    ///
+    /// The input is sought to the frame on screen so the audio restarts there,
+    /// and decoding skips ahead to `next_frame`.
+    fn resume_decoding(&mut self, end_of_file: bool, shown_frame: u64) -> Result<(), VideoError> {
+        self.end_of_file = end_of_file;
+        if self.reached_end() {
+            // Nothing left to decode until the next seek
+            self.decoder.flush();
+            self.pending_packets.clear();
+            return Ok(());
+        }
+
+        let next_ts = self.timing.time_to_ts(
+            self.timing
+                .frames_to_secs(self.next_frame.saturating_sub(1)),
+        );
+        self.seek_input(self.timing.frames_to_secs(shown_frame.saturating_sub(1)))?;
+
+        self.resumed_frame = self.decode_target(next_ts, SeekMode::Accurate)?;
+        Ok(())
+    }
+
+    /// Read packets ahead until enough audio is decoded
+    ///
+    /// The video packets read on the way are kept for the video decoder, up to
+    /// a limit for files with audio far behind the video.
+    fn buffer_audio(&mut self) -> Result<(), VideoError> {
+        while self
+            .audio
+            .as_ref()
+            .is_some_and(|audio| audio.output().buffered() < MIN_AUDIO_BUFFER)
+            && self.pending_packets.len() < MAX_PENDING_PACKETS
+        {
+            let Some(packet) = self.read_input_packet()? else {
+                break;
+            };
+            match &mut self.audio {
+                Some(audio) if packet.stream() == audio.stream_index() => {
+                    audio.send_packet(&packet)
+                }
+                _ => self.pending_packets.push_back(packet),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for FrameDecoder {
+    fn drop(&mut self) {
+        debug!("Flushing decoder");
+        let _ = self.decoder.send_packet(&ffmpeg::Packet::empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_invalid_data_is_skipped_as_corrupt() {
+        assert!(is_corrupt_data_error(&ffmpeg::Error::InvalidData));
+
+        // Waiting for input, the end of the stream and I/O errors are handled elsewhere
+        assert!(!is_corrupt_data_error(&ffmpeg::Error::Other {
+            errno: EAGAIN
+        }));
+        assert!(!is_corrupt_data_error(&ffmpeg::Error::Eof));
+        assert!(!is_corrupt_data_error(&ffmpeg::Error::Other { errno: EIO }));
+        assert!(!is_transient_read_error(&ffmpeg::Error::InvalidData));
+        assert!(is_transient_read_error(&ffmpeg::Error::Other {
+            errno: EIO
+        }));
+    }
+}
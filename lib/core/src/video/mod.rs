@@ -1,10 +1,13 @@
+pub mod audio;
 pub mod color_space;
+mod decoder;
 pub mod pipeline;
 pub mod primitive;
 pub mod render_passes;
 pub mod shader;
 pub mod stream;
 pub mod texture_manager;
+pub mod timecode;
 
 pub use primitive::VideoPrimitive;
 pub use shader::ShaderEffect;
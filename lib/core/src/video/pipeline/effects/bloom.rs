@@ -0,0 +1,445 @@
+use super::Effect;
+use crate::video::{
+    render_passes::RenderPasses,
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::{num::NonZero, sync::Arc};
+use tracing::{debug, trace, warn};
+
+/// Effect that makes highlights glow
+///
+/// Bright pixels are extracted and blurred at half resolution in two passes
+/// of its own before the effect is rendered, then added back to the input.
+#[derive(Clone, Debug)]
+pub struct BloomEffect {
+    pub state: BloomEffectState,
+    pub format: wgpu::TextureFormat,
+    passes: Option<BlurPasses>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BloomEffectState {
+    pub threshold: f32, // Luminance above which pixels bloom (0.0-1.0)
+    pub intensity: f32, // Strength of the glow (0.0-2.0)
+}
+
+impl Default for BloomEffectState {
+    fn default() -> Self {
+        Self {
+            threshold: 0.7,
+            intensity: 0.8,
+        }
+    }
+}
+
+/// Pipelines of the blur passes, sharing the uniforms of the main effect
+#[derive(Clone)]
+struct BlurPasses {
+    horizontal: Arc<ShaderEffect>, // Bright pass and horizontal blur, input to first texture
+    vertical: Arc<ShaderEffect>,   // Vertical blur, first to second texture
+    targets: Option<BlurTargets>,
+}
+
+/// Half resolution textures of the blur passes with their bind groups
+#[derive(Clone)]
+struct BlurTargets {
+    horizontal: Arc<wgpu::Texture>,
+    vertical: Arc<wgpu::Texture>,
+    horizontal_bind_group: Arc<wgpu::BindGroup>,
+    vertical_bind_group: Arc<wgpu::BindGroup>,
+}
+
+impl std::fmt::Debug for BlurPasses {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlurPasses")
+            .field(
+                "size",
+                &self.targets.as_ref().map(|targets| targets.vertical.size()),
+            )
+            .finish()
+    }
+}
+
+impl BloomEffect {
+    /// Create a new bloom effect
+    pub fn new(state: BloomEffectState, format: wgpu::TextureFormat) -> Self {
+        Self {
+            state,
+            format,
+            passes: None,
+        }
+    }
+
+    /// Create the bind group layout shared by the main effect and the blur passes
+    ///
+    /// Input texture (binding 0), sampler (binding 1) and the uniform buffer
+    /// with threshold and intensity (binding 2), plus the blurred highlights
+    /// (binding 3) for the main effect.
+    fn create_bind_group_layout(
+        device: &wgpu::Device,
+        label: &str,
+        with_bloom_texture: bool,
+    ) -> wgpu::BindGroupLayout {
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let mut entries = vec![
+            texture_entry(0),
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(NonZero::new(8).unwrap()), // 2 x f32
+                },
+                count: None,
+            },
+        ];
+        if with_bloom_texture {
+            entries.push(texture_entry(3));
+        }
+
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(label),
+            entries: &entries,
+        })
+    }
+
+    /// Build one blur pass, the direction is compiled into the shader
+    fn create_blur_pass(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        name: &str,
+        direction: [f32; 2],
+        apply_threshold: bool,
+    ) -> anyhow::Result<ShaderEffect> {
+        let shader_source = format!(
+            "const BLUR_DIRECTION: vec2<f32> = vec2<f32>({:.1}, {:.1});\n\
+             const APPLY_THRESHOLD: bool = {};\n{}",
+            direction[0],
+            direction[1],
+            apply_threshold,
+            include_str!("../../../../../../assets/shaders/bloom_blur.wgsl")
+        );
+
+        ShaderEffectBuilder::new(name)
+            .with_shader_source(&shader_source)
+            .with_bind_group_layout(Self::create_bind_group_layout(
+                device,
+                &format!("{}_bind_group_layout", name),
+                false,
+            ))
+            .build(device, queue, self.format)
+    }
+
+    /// Create a blur pass texture
+    fn create_blur_texture(
+        &self,
+        device: &wgpu::Device,
+        size: wgpu::Extent3d,
+        label: &str,
+    ) -> Arc<wgpu::Texture> {
+        Arc::new(device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        }))
+    }
+
+    /// Create the blur textures for the input size and bind them to the passes
+    ///
+    /// The textures are only recreated when the input size changes, the bind
+    /// groups every frame since the input view does.
+    fn update_blur_targets(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        input_view: &TextureView,
+        input_size: wgpu::Extent3d,
+    ) -> anyhow::Result<()> {
+        let size = wgpu::Extent3d {
+            width: input_size.width.div_ceil(2),
+            height: input_size.height.div_ceil(2),
+            depth_or_array_layers: 1,
+        };
+        let uniforms = effect
+            .uniforms
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Bloom uniforms not created"))?;
+
+        let (horizontal, vertical) = match self.passes.as_ref().and_then(|p| p.targets.as_ref()) {
+            Some(targets) if targets.vertical.size() == size => {
+                (targets.horizontal.clone(), targets.vertical.clone())
+            }
+            _ => {
+                debug!(
+                    "Creating bloom blur textures: {}x{}",
+                    size.width, size.height
+                );
+                (
+                    self.create_blur_texture(device, size, "bloom_blur_horizontal"),
+                    self.create_blur_texture(device, size, "bloom_blur_vertical"),
+                )
+            }
+        };
+
+        let passes = self
+            .passes
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Bloom blur passes not created"))?;
+
+        let bind = |pass: &ShaderEffect, view: &TextureView| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("{}_bind_group", pass.name)),
+                layout: &pass.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&pass.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: uniforms.buffer().as_entire_binding(),
+                    },
+                ],
+            })
+        };
+
+        let horizontal_bind_group = Arc::new(bind(&passes.horizontal, input_view));
+        let vertical_bind_group = Arc::new(bind(
+            &passes.vertical,
+            &horizontal.create_view(&Default::default()),
+        ));
+
+        passes.targets = Some(BlurTargets {
+            horizontal,
+            vertical,
+            horizontal_bind_group,
+            vertical_bind_group,
+        });
+        Ok(())
+    }
+}
+
+impl Effect for BloomEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with initial threshold and intensity
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        shader_uniforms.set_uniform("threshold", UniformValue::Float(self.state.threshold));
+        shader_uniforms.set_uniform("intensity", UniformValue::Float(self.state.intensity));
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/bloom.wgsl");
+        debug!("Bloom shader loaded: {} bytes", shader_source.len());
+
+        self.passes = Some(BlurPasses {
+            horizontal: Arc::new(self.create_blur_pass(
+                device,
+                queue,
+                "bloom_blur_horizontal",
+                [1.0, 0.0],
+                true,
+            )?),
+            vertical: Arc::new(self.create_blur_pass(
+                device,
+                queue,
+                "bloom_blur_vertical",
+                [0.0, 1.0],
+                false,
+            )?),
+            targets: None,
+        });
+
+        ShaderEffectBuilder::new("bloom")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(Self::create_bind_group_layout(
+                device,
+                "bloom_bind_group_layout",
+                true,
+            ))
+            .with_uniforms(shader_uniforms)
+            .with_expected_layout(&[("threshold", 0, 4), ("intensity", 4, 4)])
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!(
+                "Updating bloom uniforms - threshold: {}, intensity: {}",
+                self.state.threshold,
+                self.state.intensity
+            );
+            uniforms.set_uniform("threshold", UniformValue::Float(self.state.threshold));
+            uniforms.set_uniform("intensity", UniformValue::Float(self.state.intensity));
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let input_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+        let input_texture = texture_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture provided"))?;
+        self.update_blur_targets(device, effect, input_view, input_texture.size())?;
+
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+        let bloom_view = self
+            .passes
+            .as_ref()
+            .and_then(|passes| passes.targets.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("Bloom blur textures not created"))?
+            .vertical
+            .create_view(&Default::default());
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("bloom_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&bloom_view),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn before_render(&self, _effect: &ShaderEffect, encoder: &mut wgpu::CommandEncoder) {
+        let Some((passes, targets)) = self
+            .passes
+            .as_ref()
+            .and_then(|passes| Some((passes, passes.targets.as_ref()?)))
+        else {
+            warn!("Bloom blur passes not ready, skipping blur");
+            return;
+        };
+
+        // Bright pass with horizontal blur, then vertical blur, at half resolution
+        for (pass, bind_group, output) in [
+            (
+                &passes.horizontal,
+                &targets.horizontal_bind_group,
+                &targets.horizontal,
+            ),
+            (
+                &passes.vertical,
+                &targets.vertical_bind_group,
+                &targets.vertical,
+            ),
+        ] {
+            let size = output.size();
+            RenderPasses::apply_effect(
+                pass,
+                encoder,
+                bind_group,
+                &output.create_view(&Default::default()),
+                &iced::Rectangle {
+                    x: 0,
+                    y: 0,
+                    width: size.width,
+                    height: size.height,
+                },
+                true,
+                size.width as f32,
+                size.height as f32,
+                size.width as f32,
+                size.height as f32,
+            );
+        }
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("threshold", UniformValue::Float(threshold)) => {
+                self.state.threshold = threshold.clamp(0.0, 1.0)
+            }
+            ("intensity", UniformValue::Float(intensity)) => {
+                self.state.intensity = intensity.clamp(0.0, 2.0)
+            }
+            _ => warn!("Unknown bloom parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for bloom
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
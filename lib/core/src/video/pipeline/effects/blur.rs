@@ -0,0 +1,342 @@
+use super::Effect;
+use crate::video::{
+    render_passes::RenderPasses,
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::{num::NonZero, sync::Arc};
+use tracing::{debug, trace, warn};
+
+/// Separable Gaussian blur
+///
+/// The input is blurred horizontally into a texture of its own before the
+/// effect is rendered, the effect itself is the vertical pass over that texture.
+#[derive(Clone, Debug)]
+pub struct BlurEffect {
+    pub state: BlurEffectState,
+    pub format: wgpu::TextureFormat,
+    horizontal: Option<HorizontalPass>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BlurEffectState {
+    pub radius: f32,          // Pixels sampled on each side (0.0-32.0)
+    pub sigma: f32,           // Standard deviation of the Gaussian in pixels (0.1-16.0)
+    pub texel_size: [f32; 2], // Size of one input pixel, from the input texture
+}
+
+impl Default for BlurEffectState {
+    fn default() -> Self {
+        Self {
+            radius: 8.0,
+            sigma: 4.0,
+            texel_size: [1.0, 1.0],
+        }
+    }
+}
+
+/// Pipeline of the horizontal pass, sharing the uniforms of the main effect
+#[derive(Clone)]
+struct HorizontalPass {
+    pass: Arc<ShaderEffect>,
+    target: Option<(Arc<wgpu::Texture>, Arc<wgpu::BindGroup>)>, // Output and bind group to the input
+}
+
+impl std::fmt::Debug for HorizontalPass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HorizontalPass")
+            .field(
+                "size",
+                &self.target.as_ref().map(|(texture, _)| texture.size()),
+            )
+            .finish()
+    }
+}
+
+impl BlurEffect {
+    /// Create a new blur effect
+    pub fn new(state: BlurEffectState, format: wgpu::TextureFormat) -> Self {
+        Self {
+            state,
+            format,
+            horizontal: None,
+        }
+    }
+
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        uniforms.set_uniform("texel_size", UniformValue::Vec2(self.state.texel_size));
+        uniforms.set_uniform("radius", UniformValue::Float(self.state.radius));
+        uniforms.set_uniform("sigma", UniformValue::Float(self.state.sigma));
+    }
+
+    /// Build the pass for one direction, compiled into the shader
+    ///
+    /// Input texture (binding 0), sampler (binding 1) and the uniform buffer
+    /// with texel size, radius and sigma (binding 2).
+    fn create_pass(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        name: &str,
+        direction: [f32; 2],
+        uniforms: Option<ShaderUniforms>,
+    ) -> anyhow::Result<ShaderEffect> {
+        let shader_source = format!(
+            "const BLUR_DIRECTION: vec2<f32> = vec2<f32>({:.1}, {:.1});\n{}",
+            direction[0],
+            direction[1],
+            include_str!("../../../../../../assets/shaders/blur.wgsl")
+        );
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&format!("{}_bind_group_layout", name)),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(16).unwrap()), // vec2<f32> + 2 x f32
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let mut builder = ShaderEffectBuilder::new(name)
+            .with_shader_source(&shader_source)
+            .with_bind_group_layout(bind_group_layout);
+        if let Some(uniforms) = uniforms {
+            builder = builder.with_uniforms(uniforms).with_expected_layout(&[
+                ("texel_size", 0, 8),
+                ("radius", 8, 4),
+                ("sigma", 12, 4),
+            ]);
+        }
+        builder.build(device, queue, self.format)
+    }
+
+    /// Bind a texture and the uniforms of the main effect to a pass
+    fn bind(
+        device: &wgpu::Device,
+        pass: &ShaderEffect,
+        uniforms: &ShaderUniforms,
+        view: &TextureView,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("{}_bind_group", pass.name)),
+            layout: &pass.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&pass.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: uniforms.buffer().as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    /// Create the horizontal pass texture for the input size and bind the input to the pass
+    ///
+    /// The texture is only recreated when the input size changes, the bind
+    /// group every frame since the input view does.
+    fn update_horizontal_target(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        input_view: &TextureView,
+        size: wgpu::Extent3d,
+    ) -> anyhow::Result<()> {
+        let uniforms = effect
+            .uniforms
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Blur uniforms not created"))?;
+        let format = self.format;
+        let horizontal = self
+            .horizontal
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Blur horizontal pass not created"))?;
+
+        let texture = match &horizontal.target {
+            Some((texture, _)) if texture.size() == size => texture.clone(),
+            _ => {
+                debug!(
+                    "Creating blur horizontal texture: {}x{}",
+                    size.width, size.height
+                );
+                Arc::new(device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("blur_horizontal"),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                }))
+            }
+        };
+
+        let bind_group = Arc::new(Self::bind(device, &horizontal.pass, uniforms, input_view));
+        horizontal.target = Some((texture, bind_group));
+        Ok(())
+    }
+}
+
+impl Effect for BlurEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with the texel size, radius and sigma
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        self.set_uniforms(&mut shader_uniforms);
+        shader_uniforms.update_buffer(queue);
+
+        self.horizontal = Some(HorizontalPass {
+            pass: Arc::new(self.create_pass(device, queue, "blur_horizontal", [1.0, 0.0], None)?),
+            target: None,
+        });
+
+        self.create_pass(device, queue, "blur", [0.0, 1.0], Some(shader_uniforms))
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!(
+                "Updating blur uniforms - radius: {}, sigma: {}",
+                self.state.radius,
+                self.state.sigma
+            );
+            self.set_uniforms(uniforms);
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let input_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+        let input_texture = texture_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture provided"))?;
+
+        // Both passes step one input pixel, written in `prepare`
+        let size = input_texture.size();
+        self.state.texel_size = [
+            1.0 / size.width.max(1) as f32,
+            1.0 / size.height.max(1) as f32,
+        ];
+        self.update_horizontal_target(device, effect, input_view, size)?;
+
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        _texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        // The vertical pass reads the horizontally blurred input
+        let horizontal_view = self
+            .horizontal
+            .as_ref()
+            .and_then(|horizontal| horizontal.target.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("Blur horizontal texture not created"))?
+            .0
+            .create_view(&Default::default());
+        let uniforms = effect
+            .uniforms
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Blur uniforms not created"))?;
+
+        Ok(Self::bind(device, effect, uniforms, &horizontal_view))
+    }
+
+    fn before_render(&self, _effect: &ShaderEffect, encoder: &mut wgpu::CommandEncoder) {
+        let Some((horizontal, (output, bind_group))) = self
+            .horizontal
+            .as_ref()
+            .and_then(|horizontal| Some((horizontal, horizontal.target.as_ref()?)))
+        else {
+            warn!("Blur horizontal pass not ready, skipping it");
+            return;
+        };
+
+        let size = output.size();
+        RenderPasses::apply_effect(
+            &horizontal.pass,
+            encoder,
+            bind_group,
+            &output.create_view(&Default::default()),
+            &iced::Rectangle {
+                x: 0,
+                y: 0,
+                width: size.width,
+                height: size.height,
+            },
+            true,
+            size.width as f32,
+            size.height as f32,
+            size.width as f32,
+            size.height as f32,
+        );
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("radius", UniformValue::Float(radius)) => self.state.radius = radius.clamp(0.0, 32.0),
+            ("sigma", UniformValue::Float(sigma)) => self.state.sigma = sigma.clamp(0.1, 16.0),
+            _ => warn!("Unknown blur parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for blur
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
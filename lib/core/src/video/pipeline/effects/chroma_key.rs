@@ -0,0 +1,249 @@
+use super::Effect;
+use crate::video::{
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::num::NonZero;
+use tracing::{debug, trace, warn};
+
+/// Pure green, the default key color
+pub const GREEN_SCREEN: [f32; 3] = [0.0, 1.0, 0.0];
+
+/// Makes pixels close to a key color transparent
+///
+/// Colors are compared by chroma only, so shadows and highlights on the
+/// backdrop are keyed too. The output alpha blends the video onto whatever
+/// is behind the video container.
+#[derive(Clone, Debug)]
+pub struct ChromaKeyEffect {
+    pub state: ChromaKeyEffectState,
+    pub format: wgpu::TextureFormat,
+}
+
+#[derive(Clone, Debug)]
+pub struct ChromaKeyEffectState {
+    pub key_color: [f32; 3], // RGB color to remove (0.0-1.0)
+    pub threshold: f32,      // Chroma distance below which pixels are fully transparent
+    pub smoothness: f32,     // Distance over which alpha fades back in
+}
+
+impl Default for ChromaKeyEffectState {
+    fn default() -> Self {
+        Self {
+            key_color: GREEN_SCREEN,
+            threshold: 0.15,
+            smoothness: 0.1,
+        }
+    }
+}
+
+impl ChromaKeyEffect {
+    /// Create the effect keying out the given RGB color
+    pub fn new(key_color: [f32; 3], format: wgpu::TextureFormat) -> Self {
+        Self {
+            state: ChromaKeyEffectState {
+                key_color,
+                ..Default::default()
+            },
+            format,
+        }
+    }
+
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        uniforms.set_uniform("key_color", UniformValue::Vec3(self.state.key_color));
+        uniforms.set_uniform("threshold", UniformValue::Float(self.state.threshold));
+        uniforms.set_uniform("smoothness", UniformValue::Float(self.state.smoothness));
+    }
+}
+
+impl Effect for ChromaKeyEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with the key color, threshold and smoothness
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        self.set_uniforms(&mut shader_uniforms);
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/chroma_key.wgsl");
+        debug!("Chroma key shader loaded: {} bytes", shader_source.len());
+
+        // Create bind group layout with 3 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer with the key color, threshold and smoothness (binding 2)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("chroma_key_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(32).unwrap()), // vec3<f32> + 2 f32, padded
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        ShaderEffectBuilder::new("chroma_key")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .with_expected_layout(&[
+                ("key_color", 0, 12),
+                ("threshold", 12, 4),
+                ("smoothness", 16, 4),
+            ])
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!(
+                "Updating chroma key: color={:?}, threshold={}, smoothness={}",
+                self.state.key_color,
+                self.state.threshold,
+                self.state.smoothness
+            );
+            self.set_uniforms(uniforms);
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("chroma_key_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("key_color", UniformValue::Vec3(color)) => {
+                self.state.key_color = color.map(|c| c.clamp(0.0, 1.0))
+            }
+            ("threshold", UniformValue::Float(threshold)) => {
+                self.state.threshold = threshold.clamp(0.0, 1.0)
+            }
+            ("smoothness", UniformValue::Float(smoothness)) => {
+                self.state.smoothness = smoothness.clamp(0.0, 1.0)
+            }
+            _ => warn!("Unknown chroma key parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for chroma key
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video::shader::uniform_layout;
+
+    #[test]
+    fn uniforms_match_the_shader_struct() {
+        let state = ChromaKeyEffectState::default();
+        let values = [
+            ("key_color", UniformValue::Vec3(state.key_color)),
+            ("threshold", UniformValue::Float(state.threshold)),
+            ("smoothness", UniformValue::Float(state.smoothness)),
+        ];
+
+        // The threshold packs into the padding after the vec3, as in ChromaKeyUniforms
+        assert_eq!(
+            uniform_layout(values.iter().map(|(name, value)| (*name, value))),
+            vec![
+                ("key_color", 0, 12),
+                ("threshold", 12, 4),
+                ("smoothness", 16, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn parameters_are_clamped() {
+        let mut effect = ChromaKeyEffect::new(GREEN_SCREEN, wgpu::TextureFormat::Bgra8UnormSrgb);
+        effect.set_parameter("key_color", &UniformValue::Vec3([-0.5, 0.5, 2.0]));
+        effect.set_parameter("threshold", &UniformValue::Float(1.5));
+        effect.set_parameter("smoothness", &UniformValue::Float(-1.0));
+
+        assert_eq!(effect.state.key_color, [0.0, 0.5, 1.0]);
+        assert_eq!(effect.state.threshold, 1.0);
+        assert_eq!(effect.state.smoothness, 0.0);
+    }
+}
@@ -0,0 +1,196 @@
+use super::Effect;
+use crate::video::{
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::num::NonZero;
+use tracing::{debug, trace, warn};
+
+/// Effect for white-balance correction using per-channel RGB gains
+///
+/// Gains are applied separately to shadows, midtones and highlights and
+/// blended based on the luma of each pixel.
+#[derive(Clone, Debug)]
+pub struct ColorBalanceEffect {
+    pub state: ColorBalanceEffectState,
+    pub format: wgpu::TextureFormat,
+}
+
+#[derive(Clone, Debug)]
+pub struct ColorBalanceEffectState {
+    pub shadows: [f32; 3],
+    pub midtones: [f32; 3],
+    pub highlights: [f32; 3],
+}
+
+impl Default for ColorBalanceEffectState {
+    fn default() -> Self {
+        Self {
+            shadows: [1.0, 1.0, 1.0],
+            midtones: [1.0, 1.0, 1.0],
+            highlights: [1.0, 1.0, 1.0],
+        }
+    }
+}
+
+impl ColorBalanceEffect {
+    /// Write the gains to the uniforms
+    ///
+    /// Gains are stored as vec4 to match the 16-byte alignment of vec3 in WGSL uniforms.
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        let [r, g, b] = self.state.shadows;
+        uniforms.set_uniform("shadows", UniformValue::Vec4([r, g, b, 0.0]));
+        let [r, g, b] = self.state.midtones;
+        uniforms.set_uniform("midtones", UniformValue::Vec4([r, g, b, 0.0]));
+        let [r, g, b] = self.state.highlights;
+        uniforms.set_uniform("highlights", UniformValue::Vec4([r, g, b, 0.0]));
+    }
+}
+
+impl Effect for ColorBalanceEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with initial gains
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        self.set_uniforms(&mut shader_uniforms);
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/color_balance.wgsl");
+        debug!("Color balance shader loaded: {} bytes", shader_source.len());
+
+        // Create bind group layout with 3 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer with the gains (binding 2)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("color_balance_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(48).unwrap()), // 3 × vec4<f32>
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        ShaderEffectBuilder::new("color_balance")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .with_expected_layout(&[
+                ("shadows", 0, 16),
+                ("midtones", 16, 16),
+                ("highlights", 32, 16),
+            ])
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            self.set_uniforms(uniforms);
+            trace!(
+                "Updated color balance - shadows: {:?}, midtones: {:?}, highlights: {:?}",
+                self.state.shadows,
+                self.state.midtones,
+                self.state.highlights
+            );
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("color_balance_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("shadows", UniformValue::Vec3(gain)) => self.state.shadows = *gain,
+            ("midtones", UniformValue::Vec3(gain)) => self.state.midtones = *gain,
+            ("highlights", UniformValue::Vec3(gain)) => self.state.highlights = *gain,
+            _ => warn!("Unknown color balance parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for color balance
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
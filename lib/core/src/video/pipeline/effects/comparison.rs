@@ -1,4 +1,4 @@
-use super::Effect;
+use super::{Effect, EffectParam, EffectParamKind};
 use crate::video::{
     pipeline::manager::{VideoEntry, VideoPipelineManager},
     shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
@@ -14,28 +14,98 @@ use tracing::{debug, error, info, trace, warn};
 /// on the other, with a draggable dividing line.
 #[derive(Clone, Debug)]
 pub struct ComparisonEffect {
-    pub line_position: f32, // 0.0 to 1.0 for split position
+    pub line_position: f32,   // 0.0 to 1.0 for split position
+    pub line_color: [f32; 4], // RGB color of the dividing line, alpha is its opacity
+    pub line_width: f32,      // Width of the dividing line in pixels of the render texture
+    pub orientation: ComparisonOrientation,
     pub format: wgpu::TextureFormat,
 }
 
+/// Direction the view is split in
+///
+/// The discriminants match the `orientation` uniform of the comparison shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComparisonOrientation {
+    /// Original on the left and processed on the right, split by a vertical line
+    #[default]
+    Horizontal = 0,
+    /// Original on top and processed at the bottom, split by a horizontal line
+    Vertical = 1,
+}
+
+impl ComparisonOrientation {
+    pub const ALL: [ComparisonOrientation; 2] = [
+        ComparisonOrientation::Horizontal,
+        ComparisonOrientation::Vertical,
+    ];
+
+    /// Get the orientation for a shader index, if valid
+    pub fn from_index(index: u32) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
+
+impl std::fmt::Display for ComparisonOrientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ComparisonOrientation::Horizontal => "Left / right",
+            ComparisonOrientation::Vertical => "Top / bottom",
+        })
+    }
+}
+
+/// Default color of the dividing line (accent yellow)
+pub const DEFAULT_LINE_COLOR: [f32; 4] = [1.0, 0.8, 0.0, 1.0];
+
+/// Default width of the dividing line in pixels
+pub const DEFAULT_LINE_WIDTH: f32 = 2.0;
+
+/// Largest width of the dividing line offered to parameter UIs
+const MAX_LINE_WIDTH: f32 = 20.0;
+
+impl ComparisonEffect {
+    /// Set the color of the dividing line, alpha is its opacity
+    pub fn with_line_color(mut self, color: iced::Color) -> Self {
+        self.line_color = [color.r, color.g, color.b, color.a];
+        self
+    }
+
+    /// Set the width of the dividing line in pixels of the render texture
+    pub fn with_line_width(mut self, width: f32) -> Self {
+        self.line_width = width.max(0.0);
+        self
+    }
+
+    /// Write the line position, orientation and style to the uniforms
+    ///
+    /// The color is padded to the 16-byte alignment of vec4 in WGSL uniforms.
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        uniforms.set_uniform("line_position", UniformValue::Float(self.line_position));
+        uniforms.set_uniform("line_width", UniformValue::Float(self.line_width));
+        uniforms.set_uniform("orientation", UniformValue::Uint(self.orientation as u32));
+        uniforms.set_uniform("_pad", UniformValue::Float(0.0));
+        uniforms.set_uniform("line_color", UniformValue::Vec4(self.line_color));
+    }
+}
+
 impl Effect for ComparisonEffect {
     /// Create a new comparison effect shader
-    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> ShaderEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
         debug!(
             "Creating comparison effect shader with line position: {}",
             self.line_position
         );
 
-        // Create uniforms for the line position
+        // Create uniforms for the line position and style
         let mut shader_uniforms = ShaderUniforms::new(device, 3);
-        shader_uniforms.set_uniform("line_position", UniformValue::Float(self.line_position));
+        self.set_uniforms(&mut shader_uniforms);
         shader_uniforms.update_buffer(queue);
 
         // Create bind group layout with:
         // - binding 0: original video texture
         // - binding 1: processed result texture
         // - binding 2: sampler
-        // - binding 3: uniforms (line position)
+        // - binding 3: uniforms (line position and style)
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("comparison_bind_group_layout"),
             entries: &[
@@ -75,7 +145,7 @@ impl Effect for ComparisonEffect {
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
-                        min_binding_size: Some(NonZero::new(4).unwrap()), // Size of a float
+                        min_binding_size: Some(NonZero::new(32).unwrap()), // 2 × f32 + u32 + padding + vec4<f32>
                     },
                     count: None,
                 },
@@ -91,6 +161,12 @@ impl Effect for ComparisonEffect {
             .with_shader_source(shader_source.into())
             .with_bind_group_layout(bind_group_layout)
             .with_uniforms(shader_uniforms)
+            .with_expected_layout(&[
+                ("line_position", 0, 4),
+                ("line_width", 4, 4),
+                ("orientation", 8, 4),
+                ("line_color", 16, 16),
+            ])
             .build(device, queue, self.format)
     }
 
@@ -167,9 +243,14 @@ impl Effect for ComparisonEffect {
     fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
         // Update uniforms if needed
         if let Some(uniforms) = &mut effect.uniforms {
-            // Update the line position uniform
-            trace!("Updating comparison line position: {}", self.line_position);
-            uniforms.set_uniform("line_position", UniformValue::Float(self.line_position));
+            // Update the line position and style uniforms
+            trace!(
+                "Updating comparison line: position={}, width={}, color={:?}",
+                self.line_position,
+                self.line_width,
+                self.line_color
+            );
+            self.set_uniforms(uniforms);
             uniforms.update_buffer(queue);
         }
     }
@@ -243,8 +324,59 @@ impl Effect for ComparisonEffect {
         self.line_position = position;
     }
 
+    /// Update the orientation and style of the dividing line
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("line_color", UniformValue::Vec4(color)) => self.line_color = *color,
+            ("line_width", UniformValue::Float(width)) => self.line_width = width.max(0.0),
+            ("orientation", UniformValue::Uint(index)) => {
+                match ComparisonOrientation::from_index(*index) {
+                    Some(orientation) => self.orientation = orientation,
+                    None => warn!("Invalid comparison orientation: {}", index),
+                }
+            }
+            _ => warn!("Unknown comparison parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn parameters(&self) -> Vec<EffectParam> {
+        vec![
+            EffectParam {
+                name: "line_color",
+                kind: EffectParamKind::Color,
+                value: UniformValue::Vec4(self.line_color),
+            },
+            EffectParam {
+                name: "line_width",
+                kind: EffectParamKind::Float {
+                    min: 0.0,
+                    max: MAX_LINE_WIDTH,
+                },
+                value: UniformValue::Float(self.line_width),
+            },
+            EffectParam {
+                name: "orientation",
+                kind: EffectParamKind::Choice(
+                    ComparisonOrientation::ALL
+                        .iter()
+                        .map(|o| o.to_string())
+                        .collect(),
+                ),
+                value: UniformValue::Uint(self.orientation as u32),
+            },
+        ]
+    }
+
     /// Create a clone of this effect
     fn clone_box(&self) -> Box<dyn Effect> {
         Box::new(self.clone())
     }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
 }
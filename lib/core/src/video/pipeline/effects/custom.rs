@@ -0,0 +1,225 @@
+use super::Effect;
+use crate::video::{
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced::futures::executor::block_on;
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use tracing::{debug, trace, warn};
+
+/// Size of the uniform buffer of `ShaderUniforms`
+const MAX_UNIFORM_SIZE: usize = 256;
+
+/// Source of an effect compiled at runtime, see `CustomEffect`
+#[derive(Clone, Debug)]
+pub struct CustomEffectSource {
+    pub name: String,
+    pub wgsl_source: String,
+    pub uniforms: Vec<(String, UniformValue)>, // Initial values, in WGSL struct order
+}
+
+/// Effect compiled at runtime from application-provided WGSL
+///
+/// The shader samples the output of the previous effect with the same
+/// bindings as the built-in single-input effects:
+/// - `@binding(0)` input texture
+/// - `@binding(1)` sampler
+/// - `@binding(2)` uniform buffer holding the uniforms in order
+///
+/// and provides `vs_main` and `fs_main` entry points. Uniforms are laid out
+/// like the WGSL uniform struct: scalars align to 4 bytes, `vec2` to 8, `vec3`
+/// and `vec4` to 16, and a `mat3x3` is three columns padded to 16 bytes each
+/// (48 bytes). Padding is inserted automatically, so list the uniforms in the
+/// order of the struct fields.
+#[derive(Clone, Debug)]
+pub struct CustomEffect {
+    pub source: CustomEffectSource,
+    pub format: wgpu::TextureFormat,
+}
+
+impl CustomEffect {
+    pub fn new(source: CustomEffectSource, format: wgpu::TextureFormat) -> Self {
+        Self { source, format }
+    }
+
+    /// Write the current uniform values in their original order
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        for (name, value) in &self.source.uniforms {
+            uniforms.set_uniform(name, value.clone());
+        }
+    }
+}
+
+impl Effect for CustomEffect {
+    /// Create the shader effect, failing if the shader doesn't compile or
+    /// doesn't match the bind group layout
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        let name = &self.source.name;
+
+        // Create uniform buffer with the initial values
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        self.set_uniforms(&mut shader_uniforms);
+        let size = shader_uniforms
+            .layout()
+            .last()
+            .map_or(0, |(_, offset, size)| offset + size);
+        if size > MAX_UNIFORM_SIZE {
+            anyhow::bail!(
+                "Uniforms of effect '{}' take {} bytes, at most {} are supported",
+                name,
+                size,
+                MAX_UNIFORM_SIZE
+            );
+        }
+        shader_uniforms.update_buffer(queue);
+
+        // Create bind group layout with 3 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer (binding 2)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&format!("{}_bind_group_layout", name)),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        // Catch shader and pipeline errors instead of the default panic
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader_effect = ShaderEffectBuilder::new(name)
+            .with_shader_source(&self.source.wgsl_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .build(device, queue, self.format);
+        if let Some(error) = block_on(device.pop_error_scope()) {
+            anyhow::bail!("Shader of effect '{}' is invalid: {}", name, error);
+        }
+
+        debug!(
+            "Compiled custom effect '{}' ({} bytes of WGSL)",
+            name,
+            self.source.wgsl_source.len()
+        );
+        shader_effect
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!("Updating custom effect '{}' uniforms", self.source.name);
+            self.set_uniforms(uniforms);
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("{}_bind_group", self.source.name)),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    /// Update a uniform, keeping its type so the buffer layout stays the same
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        let uniform = self
+            .source
+            .uniforms
+            .iter_mut()
+            .find(|(uniform_name, _)| uniform_name == name);
+
+        match uniform {
+            Some((_, current))
+                if std::mem::discriminant(current) == std::mem::discriminant(value) =>
+            {
+                *current = value.clone();
+            }
+            _ => warn!(
+                "Unknown {} parameter: {} = {:?}",
+                self.source.name, name, value
+            ),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for custom effects
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,441 @@
+use super::Effect;
+use crate::video::{
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::{num::NonZero, path::Path, sync::Arc};
+use tracing::{debug, trace, warn};
+
+/// Largest LUT size, the 3D texture limit every adapter supports
+const MAX_LUT_SIZE: u32 = 256;
+
+/// 3D color lookup table, as stored in a `.cube` file
+#[derive(Clone, Debug, PartialEq)]
+pub struct CubeLut {
+    pub title: Option<String>,
+    pub size: u32,              // Entries per axis
+    pub domain_min: [f32; 3],   // Input value mapped to the first entry
+    pub domain_max: [f32; 3],   // Input value mapped to the last entry
+    pub entries: Vec<[f32; 3]>, // Output colors, red changing fastest
+}
+
+impl CubeLut {
+    /// Read a LUT from a `.cube` file
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read LUT {:?}: {}", path, e))?;
+        Self::parse(&source).map_err(|e| anyhow::anyhow!("Invalid LUT {:?}: {}", path, e))
+    }
+
+    /// Parse the Resolve/Adobe `.cube` format
+    ///
+    /// Supports `LUT_3D_SIZE` of any size up to 256, `DOMAIN_MIN`/`DOMAIN_MAX`
+    /// and Resolve's `LUT_3D_INPUT_RANGE`. 1D LUTs are rejected.
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        let mut title = None;
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut entries = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            let floats = |count: usize| -> anyhow::Result<Vec<f32>> {
+                let values = line
+                    .split_whitespace()
+                    .skip(1)
+                    .map(str::parse::<f32>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?;
+                if values.len() != count {
+                    anyhow::bail!(
+                        "line {}: expected {} values for {}",
+                        line_number,
+                        count,
+                        keyword
+                    );
+                }
+                Ok(values)
+            };
+
+            match keyword {
+                "TITLE" => {
+                    title = Some(line["TITLE".len()..].trim().trim_matches('"').to_string());
+                }
+                "LUT_3D_SIZE" => {
+                    let value: u32 = words
+                        .next()
+                        .and_then(|value| value.parse().ok())
+                        .ok_or_else(|| anyhow::anyhow!("line {}: invalid size", line_number))?;
+                    if !(2..=MAX_LUT_SIZE).contains(&value) {
+                        anyhow::bail!(
+                            "line {}: size {} is not between 2 and {}",
+                            line_number,
+                            value,
+                            MAX_LUT_SIZE
+                        );
+                    }
+                    size = Some(value);
+                }
+                "LUT_1D_SIZE" => anyhow::bail!("1D LUTs are not supported"),
+                "DOMAIN_MIN" => {
+                    let values = floats(3)?;
+                    domain_min = [values[0], values[1], values[2]];
+                }
+                "DOMAIN_MAX" => {
+                    let values = floats(3)?;
+                    domain_max = [values[0], values[1], values[2]];
+                }
+                "LUT_3D_INPUT_RANGE" => {
+                    let values = floats(2)?;
+                    domain_min = [values[0]; 3];
+                    domain_max = [values[1]; 3];
+                }
+                _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                    debug!("Ignoring LUT keyword {} on line {}", keyword, line_number);
+                }
+                _ => {
+                    let values = line
+                        .split_whitespace()
+                        .map(str::parse::<f32>)
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?;
+                    match values[..] {
+                        [r, g, b] => entries.push([r, g, b]),
+                        _ => anyhow::bail!("line {}: expected an RGB triplet", line_number),
+                    }
+                }
+            }
+        }
+
+        let size = size.ok_or_else(|| anyhow::anyhow!("missing LUT_3D_SIZE"))?;
+        let expected = (size as usize).pow(3);
+        if entries.len() != expected {
+            anyhow::bail!(
+                "expected {} entries for size {}, found {}",
+                expected,
+                size,
+                entries.len()
+            );
+        }
+        if (0..3).any(|i| domain_max[i] <= domain_min[i]) {
+            anyhow::bail!(
+                "domain max {:?} must be above domain min {:?}",
+                domain_max,
+                domain_min
+            );
+        }
+
+        Ok(Self {
+            title,
+            size,
+            domain_min,
+            domain_max,
+            entries,
+        })
+    }
+
+    /// Texel data of the 3D texture, as RGBA half floats
+    ///
+    /// The `.cube` order, red fastest and blue slowest, matches the x, y and z
+    /// order of texture rows and layers, so entries are copied as is.
+    fn texture_data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.entries.len() * 8);
+        for [r, g, b] in &self.entries {
+            for value in [*r, *g, *b, 1.0] {
+                data.extend_from_slice(&half::f16::from_f32(value).to_le_bytes());
+            }
+        }
+        data
+    }
+}
+
+/// Color grading with a 3D LUT, applied at the end of the effect chain
+///
+/// The LUT is sampled with trilinear filtering, so sizes that aren't a power
+/// of two (e.g. the common 33 and 65) work the same as others.
+#[derive(Clone, Debug)]
+pub struct LutEffect {
+    pub lut: Arc<CubeLut>,
+    pub state: LutEffectState,
+    pub format: wgpu::TextureFormat,
+    texture: Option<Arc<wgpu::Texture>>, // Created when the effect is added
+    sampler: Option<Arc<wgpu::Sampler>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LutEffectState {
+    pub intensity: f32, // Blend between the input (0.0) and the graded color (1.0)
+}
+
+impl Default for LutEffectState {
+    fn default() -> Self {
+        Self { intensity: 1.0 }
+    }
+}
+
+impl LutEffect {
+    pub fn new(lut: Arc<CubeLut>, format: wgpu::TextureFormat) -> Self {
+        Self {
+            lut,
+            state: LutEffectState::default(),
+            format,
+            texture: None,
+            sampler: None,
+        }
+    }
+
+    /// Create the effect from a `.cube` file, see [`CubeLut::parse`]
+    pub fn from_cube_file(
+        path: impl AsRef<Path>,
+        format: wgpu::TextureFormat,
+    ) -> anyhow::Result<Self> {
+        Ok(Self::new(Arc::new(CubeLut::from_file(path)?), format))
+    }
+
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        let [min_r, min_g, min_b] = self.lut.domain_min;
+        let [max_r, max_g, max_b] = self.lut.domain_max;
+        uniforms.set_uniform("domain_min", UniformValue::Vec4([min_r, min_g, min_b, 0.0]));
+        uniforms.set_uniform("domain_max", UniformValue::Vec4([max_r, max_g, max_b, 0.0]));
+        uniforms.set_uniform("intensity", UniformValue::Float(self.state.intensity));
+        uniforms.set_uniform("size", UniformValue::Float(self.lut.size as f32));
+    }
+
+    /// Upload the LUT to a 3D texture
+    fn create_lut_texture(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> wgpu::Texture {
+        let size = self.lut.size;
+        let extent = wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: size,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("lut_texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba16Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            texture.as_image_copy(),
+            &self.lut.texture_data(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(size * 8), // RGBA half floats
+                rows_per_image: Some(size),
+            },
+            extent,
+        );
+        texture
+    }
+}
+
+impl Effect for LutEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        debug!(
+            "Creating LUT effect: {} ({}x{}x{})",
+            self.lut.title.as_deref().unwrap_or("untitled"),
+            self.lut.size,
+            self.lut.size,
+            self.lut.size
+        );
+
+        self.texture = Some(Arc::new(self.create_lut_texture(device, queue)));
+        // Interpolate between LUT entries, the effect's own sampler is nearest
+        self.sampler = Some(Arc::new(device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("lut_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        })));
+
+        // Create uniform buffer with the domain and intensity
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        self.set_uniforms(&mut shader_uniforms);
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/lut.wgsl");
+        debug!("LUT shader loaded: {} bytes", shader_source.len());
+
+        // Create bind group layout with 5 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer with the domain and intensity (binding 2)
+        // 4. LUT 3D texture (binding 3)
+        // 5. LUT sampler (binding 4)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("lut_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(48).unwrap()), // 2 × vec4<f32> + 2 × f32, padded
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        ShaderEffectBuilder::new("lut")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .with_expected_layout(&[
+                ("domain_min", 0, 16),
+                ("domain_max", 16, 16),
+                ("intensity", 32, 4),
+                ("size", 36, 4),
+            ])
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!("Updating LUT intensity: {}", self.state.intensity);
+            self.set_uniforms(uniforms);
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+        let (Some(lut_texture), Some(lut_sampler)) = (&self.texture, &self.sampler) else {
+            anyhow::bail!("LUT texture not created");
+        };
+        let lut_view = lut_texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D3),
+            ..Default::default()
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("lut_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&lut_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(lut_sampler),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("intensity", UniformValue::Float(intensity)) => {
+                self.state.intensity = intensity.clamp(0.0, 1.0)
+            }
+            _ => warn!("Unknown LUT parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for the LUT
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use crate::video::{shader::ShaderUniforms, ShaderEffect};
+use crate::video::{
+    shader::{ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
 
 use iced_wgpu::wgpu::{self, Texture};
 use iced_wgpu::{primitive::Primitive, wgpu::TextureView};
@@ -13,7 +16,19 @@ use tracing::{debug, info, trace};
 
 use super::manager::{VideoEntry, VideoPipelineManager};
 
+pub mod bloom;
+pub mod blur;
+pub mod chroma_key;
+pub mod color_balance;
 pub mod comparison;
+pub mod custom;
+pub mod lut;
+pub mod posterize;
+pub mod sharpen;
+pub mod simple_color;
+pub mod stereo;
+pub mod temporal_denoise;
+pub mod tone_map;
 pub mod upscale;
 pub mod yuv_to_rgb;
 
@@ -23,7 +38,10 @@ pub mod yuv_to_rgb;
 /// providing methods for initialization, updating, and rendering.
 pub trait Effect: Send + Sync {
     /// Initialize and create a shader effect with the necessary resources
-    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> ShaderEffect;
+    ///
+    /// Fails if the shader doesn't match its bindings, e.g. for custom effects
+    /// compiled from user-supplied WGSL.
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect>;
 
     /// Update effect parameters before rendering
     fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue);
@@ -40,9 +58,56 @@ pub trait Effect: Send + Sync {
     /// Update comparison mode parameters
     fn update_comparison(&mut self, comparison_enabled: bool, comparison_position: f32);
 
+    /// Update a named effect parameter, e.g. from UI controls
+    ///
+    /// Effects without adjustable parameters can rely on the default no-op.
+    fn set_parameter(&mut self, _name: &str, _value: &UniformValue) {}
+
+    /// Describe the parameters accepted by `set_parameter` with their current values
+    ///
+    /// Lets a generic UI show a control per parameter. Effects without
+    /// adjustable parameters can rely on the default empty list.
+    fn parameters(&self) -> Vec<EffectParam> {
+        Vec::new()
+    }
+
+    /// Texture format the effect expects its input in
+    ///
+    /// `None` means the format of the intermediate textures of the effect chain.
+    /// When the input differs, the pipeline inserts a conversion or fails to add
+    /// the effect if the conversion isn't supported.
+    fn required_input_format(&self) -> Option<wgpu::TextureFormat> {
+        None
+    }
+
+    /// Record additional GPU commands before the effect is rendered
+    ///
+    /// Multi-pass effects can use this to render into their own textures, which
+    /// the effect then samples.
+    fn before_render(&self, _effect: &ShaderEffect, _encoder: &mut wgpu::CommandEncoder) {}
+
+    /// Record additional GPU commands after the effect has been rendered
+    ///
+    /// Stateful effects can use this to keep their output around for the next frame.
+    fn after_render(&self, _effect: &ShaderEffect, _encoder: &mut wgpu::CommandEncoder) {}
+
+    /// Whether the bind group can be kept while the input textures stay the same
+    ///
+    /// Effects that update their state every frame in `update_for_frame` return
+    /// `false` so it is called for every frame.
+    fn reuses_bind_group(&self) -> bool {
+        true
+    }
+
     /// Create a clone of this effect
     fn clone_box(&self) -> Box<dyn Effect>;
 
+    /// Get the effect as `Any`, to downcast it to its concrete type
+    fn as_any(&self) -> &dyn std::any::Any;
+
+    /// Get the effect as mutable `Any`, to downcast it to its concrete type
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
+
     /// Update the effect for the current frame with the given textures
     fn update_for_frame(
         &mut self,
@@ -53,11 +118,82 @@ pub trait Effect: Send + Sync {
     ) -> anyhow::Result<()>;
 }
 
+/// Named parameter values for a single effect, keyed by parameter name
+pub type EffectParameters = HashMap<String, UniformValue>;
+
+/// Tunable parameter of an effect, see `Effect::parameters`
+#[derive(Clone, Debug)]
+pub struct EffectParam {
+    pub name: &'static str, // Name passed to `set_parameter`
+    pub kind: EffectParamKind,
+    pub value: UniformValue, // Current value
+}
+
+/// Kind of control that edits an effect parameter
+#[derive(Clone, Debug, PartialEq)]
+pub enum EffectParamKind {
+    /// `Float` value within the range
+    Float { min: f32, max: f32 },
+    /// `Uint` index of one of the labeled options
+    Choice(Vec<String>),
+    /// `Vec4` RGBA color
+    Color,
+}
+
 /// Represents a single effect instance in the effect chain
 pub struct EffectEntry {
     pub effect: ShaderEffect,
     pub state: Box<dyn Effect + Send + Sync>,
     pub get_from_video: bool, // Flag indicating if this effect uses video textures as input
+    pub enabled: bool,        // Disabled effects stay in the chain but aren't rendered
+}
+
+impl EffectEntry {
+    /// Update the effect for the current frame with the given textures
+    ///
+    /// The bind group is only rebuilt when the input textures changed, e.g.
+    /// after a resize, so nothing is allocated for repeated frames while paused.
+    pub fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let input_ids: Vec<_> = texture_list
+            .iter()
+            .map(|texture| texture.global_id())
+            .collect();
+        if self.state.reuses_bind_group()
+            && self.effect.current_bind_group.is_some()
+            && self.effect.last_input_ids == input_ids
+        {
+            trace!("Reusing bind group for effect '{}'", self.effect.name);
+            return Ok(());
+        }
+
+        self.state
+            .update_for_frame(device, &mut self.effect, texture_view_list, texture_list)?;
+        self.effect.last_input_ids = input_ids;
+        Ok(())
+    }
+
+    /// Rebuild the bind group on the next frame, e.g. after its input moved
+    pub fn invalidate_bind_group(&mut self) {
+        self.effect.last_input_ids.clear();
+    }
+
+    /// Get the effect as its concrete type, e.g. `UpscaleEffect`
+    pub fn downcast_ref<T: Effect + 'static>(&self) -> Option<&T> {
+        self.state.as_any().downcast_ref()
+    }
+
+    /// Get the effect as its concrete type to change its state directly
+    ///
+    /// The state is uploaded to the uniforms of the effect when the chain is
+    /// next prepared, like parameters set with `set_parameter`.
+    pub fn downcast_mut<T: Effect + 'static>(&mut self) -> Option<&mut T> {
+        self.state.as_any_mut().downcast_mut()
+    }
 }
 
 /// Manages a chain of video effects that can be applied sequentially
@@ -84,6 +220,7 @@ impl EffectManager {
             effect,
             state,
             get_from_video: false,
+            enabled: true,
         };
         self.effects.push(entry);
 
@@ -133,6 +270,93 @@ impl EffectManager {
         self.effects.len()
     }
 
+    /// Get the effect with the given name
+    pub fn get_effect(&self, name: &str) -> Option<&EffectEntry> {
+        self.effects.iter().find(|entry| entry.effect.name == name)
+    }
+
+    /// Get the effect with the given name to change it directly
+    ///
+    /// See `EffectEntry::downcast_mut` to change the state of the effect.
+    pub fn get_effect_mut(&mut self, name: &str) -> Option<&mut EffectEntry> {
+        self.effects
+            .iter_mut()
+            .find(|entry| entry.effect.name == name)
+    }
+
+    /// Get the tunable parameters of each effect in the chain, by effect name
+    pub fn parameters(&self) -> Vec<(&str, Vec<EffectParam>)> {
+        self.effects
+            .iter()
+            .map(|entry| (entry.effect.name.as_str(), entry.state.parameters()))
+            .filter(|(_, parameters)| !parameters.is_empty())
+            .collect()
+    }
+
+    /// Set a parameter of the named effect, see `Effect::set_parameter`
+    ///
+    /// Returns `false` if there is no such effect in the chain.
+    pub fn set_parameter(&mut self, effect: &str, name: &str, value: &UniformValue) -> bool {
+        match self.get_effect_mut(effect) {
+            Some(entry) => {
+                entry.state.set_parameter(name, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the index of the texture effect `index` reads from
+    ///
+    /// That's the output of the closest enabled effect before it, as disabled
+    /// effects pass their input through. The first effect reads the video.
+    pub fn input_index(&self, index: usize) -> usize {
+        (0..index)
+            .rev()
+            .find(|&i| self.effects[i].enabled)
+            .unwrap_or(0)
+    }
+
+    /// Get the index of the last enabled effect, whose output is drawn
+    pub fn output_index(&self) -> Option<usize> {
+        self.effects.iter().rposition(|entry| entry.enabled)
+    }
+
+    /// Move the effect at `from` to `to`, shifting the effects in between
+    ///
+    /// The first stage, which converts the video to RGB, can't be moved or
+    /// replaced. The bind groups of the moved effects are rebuilt on the next
+    /// frame, as their inputs change.
+    pub fn move_effect(&mut self, from: usize, to: usize) -> anyhow::Result<()> {
+        let len = self.effects.len();
+        if from >= len || to >= len {
+            anyhow::bail!(
+                "Can't move effect {} to {}, the chain has {} effects",
+                from,
+                to,
+                len
+            );
+        }
+        // The first effect is always given the video planes, see `VideoPipelineManager`
+        if (from == 0 || to == 0) && from != to {
+            anyhow::bail!(
+                "Can't move the first effect '{}', it reads the video frame",
+                self.effects[0].effect.name
+            );
+        }
+
+        debug!(
+            "Moving effect '{}' from {} to {}",
+            self.effects[from].effect.name, from, to
+        );
+        let entry = self.effects.remove(from);
+        self.effects.insert(to, entry);
+        for entry in &mut self.effects[from.min(to)..=from.max(to)] {
+            entry.invalidate_bind_group();
+        }
+        Ok(())
+    }
+
     /// Clear all effects from the chain
     pub fn clear(&mut self) {
         debug!("Clearing all effects from the effect chain");
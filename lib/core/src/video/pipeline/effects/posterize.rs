@@ -0,0 +1,163 @@
+use super::Effect;
+use crate::video::{
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::num::NonZero;
+use tracing::{debug, trace, warn};
+
+/// Effect that quantizes each RGB channel to a fixed number of levels
+///
+/// Useful for stylization and for making banding in the source visible.
+#[derive(Clone, Debug)]
+pub struct PosterizeEffect {
+    pub state: PosterizeEffectState,
+    pub format: wgpu::TextureFormat,
+}
+
+#[derive(Clone, Debug)]
+pub struct PosterizeEffectState {
+    pub levels: u32, // Levels per channel (2-256)
+}
+
+impl Default for PosterizeEffectState {
+    fn default() -> Self {
+        Self { levels: 4 }
+    }
+}
+
+impl Effect for PosterizeEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with initial level count
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        shader_uniforms.set_uniform("levels", UniformValue::Uint(self.state.levels));
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/posterize.wgsl");
+        debug!("Posterize shader loaded: {} bytes", shader_source.len());
+
+        // Create bind group layout with 3 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer with the level count (binding 2)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("posterize_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(4).unwrap()), // u32
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        ShaderEffectBuilder::new("posterize")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!("Updating posterize levels: {}", self.state.levels);
+            uniforms.set_uniform("levels", UniformValue::Uint(self.state.levels));
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("posterize_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("levels", UniformValue::Uint(levels)) => self.state.levels = (*levels).clamp(2, 256),
+            _ => warn!("Unknown posterize parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for posterize
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -0,0 +1,184 @@
+use super::Effect;
+use crate::video::{
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::num::NonZero;
+use tracing::{debug, trace, warn};
+
+/// Unsharp mask that boosts the difference to a 3x3 blur of the input
+///
+/// Meant to be stacked after the upscale effect to restore edge contrast.
+#[derive(Clone, Debug)]
+pub struct SharpenEffect {
+    pub state: SharpenEffectState,
+    pub format: wgpu::TextureFormat,
+}
+
+#[derive(Clone, Debug)]
+pub struct SharpenEffectState {
+    pub amount: f32,          // Strength of the sharpening (0.0-2.0)
+    pub texel_size: [f32; 2], // Size of one input pixel, from the input texture
+}
+
+impl Default for SharpenEffectState {
+    fn default() -> Self {
+        Self {
+            amount: 0.5,
+            texel_size: [1.0, 1.0],
+        }
+    }
+}
+
+impl SharpenEffect {
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        uniforms.set_uniform("texel_size", UniformValue::Vec2(self.state.texel_size));
+        uniforms.set_uniform("amount", UniformValue::Float(self.state.amount));
+    }
+}
+
+impl Effect for SharpenEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with the texel size and amount
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        self.set_uniforms(&mut shader_uniforms);
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/sharpen.wgsl");
+        debug!("Sharpen shader loaded: {} bytes", shader_source.len());
+
+        // Create bind group layout with 3 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer with the texel size and amount (binding 2)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sharpen_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(16).unwrap()), // vec2<f32> + f32, padded
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        ShaderEffectBuilder::new("sharpen")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .with_expected_layout(&[("texel_size", 0, 8), ("amount", 8, 4)])
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!("Updating sharpen amount: {}", self.state.amount);
+            self.set_uniforms(uniforms);
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        // Sample the neighbors one input pixel apart, written in `prepare`
+        if let Some(input_texture) = texture_list.first() {
+            let size = input_texture.size();
+            self.state.texel_size = [
+                1.0 / size.width.max(1) as f32,
+                1.0 / size.height.max(1) as f32,
+            ];
+        }
+
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sharpen_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("amount", UniformValue::Float(amount)) => self.state.amount = amount.clamp(0.0, 2.0),
+            _ => warn!("Unknown sharpen parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for sharpen
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
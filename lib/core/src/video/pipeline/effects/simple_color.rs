@@ -0,0 +1,248 @@
+use super::Effect;
+use crate::video::{
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::{collections::HashMap, num::NonZero};
+use tracing::{debug, trace, warn};
+
+/// Effect for quick monochrome, sepia or inverted looks
+///
+/// All modes share one pipeline, switching modes only changes the uniform.
+#[derive(Clone, Debug)]
+pub struct SimpleColorEffect {
+    pub state: SimpleColorEffectState,
+    pub format: wgpu::TextureFormat,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SimpleColorEffectState {
+    pub mode: SimpleColorMode,
+}
+
+/// Per-pixel color transform applied by the simple color effect
+///
+/// The discriminants match the `mode` switch in the simple color shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimpleColorMode {
+    /// Leave the colors unchanged
+    #[default]
+    None = 0,
+    /// Luminance in all channels
+    Grayscale = 1,
+    /// Warm brown monochrome
+    Sepia = 2,
+    /// Negative of each channel
+    Invert = 3,
+}
+
+impl SimpleColorMode {
+    pub const ALL: [SimpleColorMode; 4] = [
+        SimpleColorMode::None,
+        SimpleColorMode::Grayscale,
+        SimpleColorMode::Sepia,
+        SimpleColorMode::Invert,
+    ];
+
+    /// Get the mode for a shader index, if valid
+    pub fn from_index(index: u32) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+
+    /// Key of the mode in the shader selections, `None` for no transform
+    pub fn selection_key(&self) -> Option<&'static str> {
+        match self {
+            SimpleColorMode::None => None,
+            SimpleColorMode::Grayscale => Some("grayscale"),
+            SimpleColorMode::Sepia => Some("sepia"),
+            SimpleColorMode::Invert => Some("invert"),
+        }
+    }
+
+    /// Get the first selected mode, in the order of `ALL`
+    pub fn from_selections(selections: &HashMap<String, bool>) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|mode| {
+                mode.selection_key()
+                    .is_some_and(|key| *selections.get(key).unwrap_or(&false))
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for SimpleColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SimpleColorMode::None => "None",
+            SimpleColorMode::Grayscale => "Grayscale",
+            SimpleColorMode::Sepia => "Sepia",
+            SimpleColorMode::Invert => "Invert",
+        })
+    }
+}
+
+impl Effect for SimpleColorEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with the initial mode
+        let mut shader_uniforms = ShaderUniforms::new(device, 1);
+        shader_uniforms.set_uniform("mode", UniformValue::Uint(self.state.mode as u32));
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/simple_color.wgsl");
+        debug!("Simple color shader loaded: {} bytes", shader_source.len());
+
+        // Create bind group layout with 3 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer with the mode (binding 2)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("simple_color_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(4).unwrap()), // u32
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        ShaderEffectBuilder::new("simple_color")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!("Updating simple color mode: {}", self.state.mode);
+            uniforms.set_uniform("mode", UniformValue::Uint(self.state.mode as u32));
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("simple_color_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("mode", UniformValue::Uint(index)) => match SimpleColorMode::from_index(*index) {
+                Some(mode) => self.state.mode = mode,
+                None => warn!("Invalid simple color mode: {}", index),
+            },
+            _ => warn!("Unknown simple color parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for simple color
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_round_trip_through_the_shader_index() {
+        for mode in SimpleColorMode::ALL {
+            assert_eq!(SimpleColorMode::from_index(mode as u32), Some(mode));
+        }
+        assert_eq!(SimpleColorMode::from_index(4), None);
+    }
+
+    #[test]
+    fn first_selected_mode_wins() {
+        let select = |keys: &[&str]| {
+            let selections = keys.iter().map(|key| (key.to_string(), true)).collect();
+            SimpleColorMode::from_selections(&selections)
+        };
+
+        assert_eq!(select(&[]), SimpleColorMode::None);
+        assert_eq!(select(&["grayscale"]), SimpleColorMode::Grayscale);
+        assert_eq!(select(&["invert", "sepia"]), SimpleColorMode::Sepia);
+        assert_eq!(select(&["upscale"]), SimpleColorMode::None);
+    }
+}
@@ -0,0 +1,208 @@
+use super::Effect;
+use crate::video::{
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::num::NonZero;
+use tracing::{debug, trace, warn};
+
+/// Effect that shows stereoscopic (3D) video on a regular display
+///
+/// Remaps the sampling coordinates to extract the left eye of a packed stereo
+/// frame, or blends both eyes into a red-cyan anaglyph.
+#[derive(Clone, Debug)]
+pub struct StereoEffect {
+    pub state: StereoEffectState,
+    pub format: wgpu::TextureFormat,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StereoEffectState {
+    pub layout: StereoLayout,
+}
+
+/// How the two eyes are packed into a frame and how they are displayed
+///
+/// The discriminants match the `layout` switch in the stereo shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StereoLayout {
+    /// Left eye of a side-by-side frame
+    #[default]
+    SideBySide = 0,
+    /// Top eye of a top-bottom frame
+    TopBottom = 1,
+    /// Red-cyan anaglyph of a side-by-side frame
+    AnaglyphSideBySide = 2,
+    /// Red-cyan anaglyph of a top-bottom frame
+    AnaglyphTopBottom = 3,
+}
+
+impl StereoLayout {
+    pub const ALL: [StereoLayout; 4] = [
+        StereoLayout::SideBySide,
+        StereoLayout::TopBottom,
+        StereoLayout::AnaglyphSideBySide,
+        StereoLayout::AnaglyphTopBottom,
+    ];
+
+    /// Get the layout for a shader index, if valid
+    pub fn from_index(index: u32) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
+
+impl std::fmt::Display for StereoLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StereoLayout::SideBySide => "Side by side",
+            StereoLayout::TopBottom => "Top-bottom",
+            StereoLayout::AnaglyphSideBySide => "Anaglyph (side by side)",
+            StereoLayout::AnaglyphTopBottom => "Anaglyph (top-bottom)",
+        })
+    }
+}
+
+impl Effect for StereoEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with initial layout
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        shader_uniforms.set_uniform(
+            "stereo_layout",
+            UniformValue::Uint(self.state.layout as u32),
+        );
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/stereo.wgsl");
+        debug!("Stereo shader loaded: {} bytes", shader_source.len());
+
+        // Create bind group layout with 3 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer with the layout (binding 2)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("stereo_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(4).unwrap()), // u32
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        ShaderEffectBuilder::new("stereo")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!("Updating stereo layout: {:?}", self.state.layout);
+            uniforms.set_uniform(
+                "stereo_layout",
+                UniformValue::Uint(self.state.layout as u32),
+            );
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("stereo_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("layout", UniformValue::Uint(index)) => match StereoLayout::from_index(*index) {
+                Some(layout) => self.state.layout = layout,
+                None => warn!("Invalid stereo layout: {}", index),
+            },
+            _ => warn!("Unknown stereo parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for stereo
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
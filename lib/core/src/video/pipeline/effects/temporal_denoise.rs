@@ -0,0 +1,316 @@
+use super::Effect;
+use crate::video::{
+    render_passes::RenderPasses,
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::{num::NonZero, sync::Arc};
+use tracing::{debug, trace, warn};
+
+/// Effect that reduces noise on static content by blending each frame with
+/// the previous output (exponential moving average)
+#[derive(Clone, Debug)]
+pub struct TemporalDenoiseEffect {
+    pub state: TemporalDenoiseEffectState,
+    pub format: wgpu::TextureFormat,
+    history: Option<HistoryTextures>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TemporalDenoiseEffectState {
+    pub blend: f32, // Weight of the previous frame (0.0-1.0)
+}
+
+impl Default for TemporalDenoiseEffectState {
+    fn default() -> Self {
+        Self { blend: 0.6 }
+    }
+}
+
+/// Textures holding the previous output across frames
+#[derive(Clone, Debug)]
+struct HistoryTextures {
+    history: Arc<wgpu::Texture>, // Previous output, sampled by the shader
+    scratch: Arc<wgpu::Texture>, // Current output, copied into history after rendering
+    has_history: bool,
+}
+
+impl TemporalDenoiseEffect {
+    /// Create a new temporal denoise effect
+    pub fn new(state: TemporalDenoiseEffectState, format: wgpu::TextureFormat) -> Self {
+        Self {
+            state,
+            format,
+            history: None,
+        }
+    }
+
+    /// Create a texture matching the size of the input for keeping history
+    fn create_history_texture(
+        &self,
+        device: &wgpu::Device,
+        size: wgpu::Extent3d,
+        label: &str,
+        usage: wgpu::TextureUsages,
+    ) -> Arc<wgpu::Texture> {
+        Arc::new(device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage,
+            view_formats: &[],
+        }))
+    }
+
+    /// Create the history textures if missing or if the input size changed
+    fn ensure_history(&mut self, device: &wgpu::Device, size: wgpu::Extent3d) {
+        match &mut self.history {
+            Some(history) if history.history.size() == size => {
+                // A frame has been rendered since the textures were created
+                history.has_history = true;
+            }
+            _ => {
+                debug!(
+                    "Creating temporal denoise history textures: {}x{}",
+                    size.width, size.height
+                );
+                self.history = Some(HistoryTextures {
+                    history: self.create_history_texture(
+                        device,
+                        size,
+                        "temporal_denoise_history",
+                        wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                    ),
+                    scratch: self.create_history_texture(
+                        device,
+                        size,
+                        "temporal_denoise_scratch",
+                        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                    ),
+                    has_history: false,
+                });
+            }
+        }
+    }
+
+    /// Whether the history texture contains a previous frame
+    fn has_history(&self) -> bool {
+        self.history
+            .as_ref()
+            .is_some_and(|history| history.has_history)
+    }
+}
+
+impl Effect for TemporalDenoiseEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with initial blend
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        shader_uniforms.set_uniform("blend", UniformValue::Float(self.state.blend));
+        shader_uniforms.set_uniform("has_history", UniformValue::Uint(0));
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/temporal_denoise.wgsl");
+        debug!(
+            "Temporal denoise shader loaded: {} bytes",
+            shader_source.len()
+        );
+
+        // Create bind group layout with 4 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer with blend parameters (binding 2)
+        // 4. History texture with the previous output (binding 3)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("temporal_denoise_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(8).unwrap()), // f32 + u32
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        ShaderEffectBuilder::new("temporal_denoise")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        let has_history = self.has_history();
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!(
+                "Updating temporal denoise uniforms - blend: {}, has_history: {}",
+                self.state.blend,
+                has_history
+            );
+            uniforms.set_uniform("blend", UniformValue::Float(self.state.blend));
+            uniforms.set_uniform("has_history", UniformValue::Uint(has_history as u32));
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    /// The history is only marked as filled in `update_for_frame`
+    fn reuses_bind_group(&self) -> bool {
+        false
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let input_texture = texture_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture provided"))?;
+        self.ensure_history(device, input_texture.size());
+
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+        let history_view = self
+            .history
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("History texture not created"))?
+            .history
+            .create_view(&Default::default());
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("temporal_denoise_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&history_view),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn after_render(&self, effect: &ShaderEffect, encoder: &mut wgpu::CommandEncoder) {
+        let (Some(history), Some(bind_group)) = (&self.history, effect.get_bind_group()) else {
+            warn!("Temporal denoise history not ready, skipping history update");
+            return;
+        };
+
+        // Render the blended frame at input resolution, then keep it for the next frame
+        let size = history.scratch.size();
+        let scratch_view = history.scratch.create_view(&Default::default());
+        RenderPasses::apply_effect(
+            effect,
+            encoder,
+            bind_group,
+            &scratch_view,
+            &iced::Rectangle {
+                x: 0,
+                y: 0,
+                width: size.width,
+                height: size.height,
+            },
+            true,
+            size.width as f32,
+            size.height as f32,
+            size.width as f32,
+            size.height as f32,
+        );
+
+        encoder.copy_texture_to_texture(
+            history.scratch.as_image_copy(),
+            history.history.as_image_copy(),
+            size,
+        );
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("blend", UniformValue::Float(blend)) => self.state.blend = blend.clamp(0.0, 1.0),
+            _ => warn!("Unknown temporal denoise parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for temporal denoise
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
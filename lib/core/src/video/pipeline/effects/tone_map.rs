@@ -0,0 +1,266 @@
+use super::Effect;
+use crate::video::{
+    color_space::ColorTransfer,
+    shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
+    ShaderEffect,
+};
+use iced_wgpu::wgpu::{self, Texture, TextureView};
+use std::num::NonZero;
+use tracing::{debug, trace, warn};
+
+/// Peak luminance assumed for HDR video without mastering metadata, in nits
+pub const DEFAULT_PEAK_LUMINANCE: f32 = 1000.0;
+
+// SDR reference white in nits (BT.2408), lower peaks leave nothing to compress
+const MIN_PEAK_LUMINANCE: f32 = 203.0;
+
+/// HDR transfer function decoded by the tone mapping
+///
+/// The discriminants match the `transfer` switch in the tone map shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HdrTransfer {
+    /// SMPTE ST 2084 perceptual quantizer, absolute luminance up to 10000 nits
+    Pq = 0,
+    /// ARIB STD-B67 hybrid log-gamma, relative to the display peak
+    Hlg = 1,
+}
+
+impl HdrTransfer {
+    /// Get the HDR transfer for a video transfer characteristic, `None` for SDR
+    pub fn from_color_transfer(transfer: ColorTransfer) -> Option<Self> {
+        match transfer {
+            ColorTransfer::Pq => Some(Self::Pq),
+            ColorTransfer::Hlg => Some(Self::Hlg),
+            _ => None,
+        }
+    }
+}
+
+/// Curve compressing HDR highlights into the SDR range
+///
+/// The discriminants match the `curve` switch in the tone map shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMapCurve {
+    /// Extended Reinhard, keeps midtones and rolls off towards the peak
+    #[default]
+    Reinhard = 0,
+    /// Hable (Uncharted 2) filmic curve, with a toe for more contrast
+    Hable = 1,
+}
+
+impl ToneMapCurve {
+    pub const ALL: [ToneMapCurve; 2] = [ToneMapCurve::Reinhard, ToneMapCurve::Hable];
+
+    /// Get the curve for a shader index, if valid
+    pub fn from_index(index: u32) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
+
+/// Converts BT.2020 PQ or HLG video to BT.709 SDR
+///
+/// Inserted automatically right after the YUV to RGB conversion for videos
+/// with an HDR transfer, which look washed out or blown out otherwise.
+#[derive(Clone, Debug)]
+pub struct ToneMapEffect {
+    pub state: ToneMapEffectState,
+    pub format: wgpu::TextureFormat,
+}
+
+#[derive(Clone, Debug)]
+pub struct ToneMapEffectState {
+    pub transfer: HdrTransfer,
+    pub curve: ToneMapCurve,
+    pub peak_luminance: f32, // Brightest luminance of the video in nits, mapped to SDR white
+}
+
+impl ToneMapEffect {
+    /// Create the effect for video with the given HDR transfer
+    pub fn new(transfer: HdrTransfer, format: wgpu::TextureFormat) -> Self {
+        Self {
+            state: ToneMapEffectState {
+                transfer,
+                curve: ToneMapCurve::default(),
+                peak_luminance: DEFAULT_PEAK_LUMINANCE,
+            },
+            format,
+        }
+    }
+
+    /// Set the brightest luminance of the video in nits, e.g. from its mastering metadata
+    pub fn with_peak_luminance(mut self, nits: f32) -> Self {
+        self.state.peak_luminance = nits.max(MIN_PEAK_LUMINANCE);
+        self
+    }
+
+    /// Set the curve used to compress the highlights
+    pub fn with_curve(mut self, curve: ToneMapCurve) -> Self {
+        self.state.curve = curve;
+        self
+    }
+
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        uniforms.set_uniform("transfer", UniformValue::Uint(self.state.transfer as u32));
+        uniforms.set_uniform("curve", UniformValue::Uint(self.state.curve as u32));
+        uniforms.set_uniform(
+            "peak_luminance",
+            UniformValue::Float(self.state.peak_luminance),
+        );
+    }
+}
+
+impl Effect for ToneMapEffect {
+    fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> anyhow::Result<ShaderEffect> {
+        // Create uniform buffer with the transfer, curve and peak luminance
+        let mut shader_uniforms = ShaderUniforms::new(device, 2);
+        self.set_uniforms(&mut shader_uniforms);
+        shader_uniforms.update_buffer(queue);
+
+        let shader_source = include_str!("../../../../../../assets/shaders/tone_map.wgsl");
+        debug!("Tone map shader loaded: {} bytes", shader_source.len());
+
+        // Create bind group layout with 3 entries:
+        // 1. Input texture (binding 0)
+        // 2. Sampler (binding 1)
+        // 3. Uniform buffer with the transfer, curve and peak luminance (binding 2)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("tone_map_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(NonZero::new(16).unwrap()), // 2 u32 + f32, padded
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        ShaderEffectBuilder::new("tone_map")
+            .with_shader_source(shader_source)
+            .with_bind_group_layout(bind_group_layout)
+            .with_uniforms(shader_uniforms)
+            .with_expected_layout(&[
+                ("transfer", 0, 4),
+                ("curve", 4, 4),
+                ("peak_luminance", 8, 4),
+            ])
+            .build(device, queue, self.format)
+    }
+
+    fn prepare(&mut self, effect: &mut ShaderEffect, queue: &wgpu::Queue) {
+        if let Some(uniforms) = &mut effect.uniforms {
+            trace!(
+                "Updating tone map: transfer={:?}, curve={:?}, peak={} nits",
+                self.state.transfer,
+                self.state.curve,
+                self.state.peak_luminance
+            );
+            self.set_uniforms(uniforms);
+            uniforms.update_buffer(queue);
+        }
+    }
+
+    fn update_for_frame(
+        &mut self,
+        device: &wgpu::Device,
+        effect: &mut ShaderEffect,
+        texture_view_list: &[TextureView],
+        texture_list: &[&Texture],
+    ) -> anyhow::Result<()> {
+        let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
+        effect.update_bind_group(bind_group);
+        Ok(())
+    }
+
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        effect: &ShaderEffect,
+        texture_view_list: &[TextureView],
+        _texture_list: &[&Texture],
+    ) -> anyhow::Result<wgpu::BindGroup> {
+        let input_texture_view = texture_view_list
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No texture view provided"))?;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("tone_map_bind_group"),
+            layout: &effect.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(input_texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&effect.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: effect
+                        .uniforms
+                        .as_ref()
+                        .unwrap()
+                        .buffer()
+                        .as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(bind_group)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("transfer", UniformValue::Uint(index)) => match *index {
+                0 => self.state.transfer = HdrTransfer::Pq,
+                1 => self.state.transfer = HdrTransfer::Hlg,
+                _ => warn!("Invalid HDR transfer: {}", index),
+            },
+            ("curve", UniformValue::Uint(index)) => match ToneMapCurve::from_index(*index) {
+                Some(curve) => self.state.curve = curve,
+                None => warn!("Invalid tone map curve: {}", index),
+            },
+            ("peak_luminance", UniformValue::Float(nits)) => {
+                self.state.peak_luminance = nits.max(MIN_PEAK_LUMINANCE)
+            }
+            _ => warn!("Unknown tone map parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn update_comparison(&mut self, _: bool, _: f32) {
+        // No comparison functionality needed for tone mapping
+    }
+
+    fn clone_box(&self) -> Box<dyn Effect> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -1,4 +1,4 @@
-use super::Effect;
+use super::{Effect, EffectParam, EffectParamKind};
 use crate::video::{
     pipeline::manager::{VideoEntry, VideoPipelineManager},
     shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
@@ -6,7 +6,7 @@ use crate::video::{
 };
 use iced_wgpu::wgpu::{self, Texture, TextureView};
 use std::{fs, num::NonZero};
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, warn};
 
 #[derive(Clone, Debug)]
 pub struct UpscaleEffect {
@@ -18,6 +18,7 @@ pub struct UpscaleEffect {
 pub struct UpscaleEffectState {
     pub color_threshold: f32,
     pub color_blend_mode: f32,
+    pub algorithm: UpscaleAlgorithm,
 }
 
 impl Default for UpscaleEffectState {
@@ -25,27 +26,72 @@ impl Default for UpscaleEffectState {
         Self {
             color_threshold: 0.05,
             color_blend_mode: 2.0,
+            algorithm: UpscaleAlgorithm::default(),
         }
     }
 }
 
+/// Resampling kernel used by the upscale effect
+///
+/// The discriminants match the `algorithm` switch in the upscale shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpscaleAlgorithm {
+    #[default]
+    Bilinear = 0,
+    Bicubic = 1,
+    Lanczos = 2,
+}
+
+impl UpscaleAlgorithm {
+    pub const ALL: [UpscaleAlgorithm; 3] = [
+        UpscaleAlgorithm::Bilinear,
+        UpscaleAlgorithm::Bicubic,
+        UpscaleAlgorithm::Lanczos,
+    ];
+
+    /// Get the algorithm for a shader index, if valid
+    pub fn from_index(index: u32) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
+
+impl std::fmt::Display for UpscaleAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UpscaleAlgorithm::Bilinear => "Bilinear",
+            UpscaleAlgorithm::Bicubic => "Bicubic",
+            UpscaleAlgorithm::Lanczos => "Lanczos",
+        })
+    }
+}
+
+impl UpscaleEffect {
+    /// Write the parameters to the uniforms in the order of the WGSL struct
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        // Comparison is handled by the comparison effect
+        uniforms.set_uniform("comparison_enabled", UniformValue::Uint(0));
+        uniforms.set_uniform("comparison_position", UniformValue::Float(0.0));
+        uniforms.set_uniform(
+            "color_threshold",
+            UniformValue::Float(self.state.color_threshold),
+        );
+        uniforms.set_uniform(
+            "color_blend_mode",
+            UniformValue::Uint(self.state.color_blend_mode as u32),
+        );
+        uniforms.set_uniform("algorithm", UniformValue::Uint(self.state.algorithm as u32));
+    }
+}
+
 impl Effect for UpscaleEffect {
     fn add(
         &mut self,
         device: &iced_wgpu::wgpu::Device,
         queue: &iced_wgpu::wgpu::Queue,
-    ) -> ShaderEffect {
+    ) -> anyhow::Result<ShaderEffect> {
         // Create uniform buffer with initial values
         let mut shader_uniforms = ShaderUniforms::new(device, 2);
-
-        shader_uniforms.set_uniform(
-            "color_threshold",
-            UniformValue::Float(self.state.color_threshold),
-        );
-        shader_uniforms.set_uniform(
-            "color_blend_mode",
-            UniformValue::Float(self.state.color_blend_mode),
-        );
+        self.set_uniforms(&mut shader_uniforms);
 
         // Update the buffer with initial values
         shader_uniforms.update_buffer(queue);
@@ -85,7 +131,7 @@ impl Effect for UpscaleEffect {
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
-                        min_binding_size: Some(NonZero::new(16).unwrap()), // size of ShaderUniforms (2 floats × 4 bytes each + padding = 16 bytes)
+                        min_binding_size: Some(NonZero::new(20).unwrap()), // size of ShaderUniforms (5 × 4 bytes)
                     },
                     count: None,
                 },
@@ -99,28 +145,29 @@ impl Effect for UpscaleEffect {
             .with_shader_source(shader_source.into())
             .with_bind_group_layout(bind_group_layout)
             .with_uniforms(shader_uniforms)
-            .build(device, queue, self.format);
+            .with_expected_layout(&[
+                ("comparison_enabled", 0, 4),
+                ("comparison_position", 4, 4),
+                ("color_threshold", 8, 4),
+                ("color_blend_mode", 12, 4),
+                ("algorithm", 16, 4),
+            ])
+            .build(device, queue, self.format)?;
 
         debug!("Shader effect created successfully");
-        shader_effect
+        Ok(shader_effect)
     }
 
     fn prepare(&mut self, effect: &mut ShaderEffect, queue: &iced_wgpu::wgpu::Queue) {
         // Update uniform values if they've changed since last frame
         if let Some(uniforms) = &mut effect.uniforms {
-            uniforms.set_uniform(
-                "color_threshold",
-                UniformValue::Float(self.state.color_threshold),
-            );
-            uniforms.set_uniform(
-                "color_blend_mode",
-                UniformValue::Float(self.state.color_blend_mode),
-            );
+            self.set_uniforms(uniforms);
 
             trace!(
-                "Updated uniforms - threshold: {}, blend mode: {}",
+                "Updated uniforms - threshold: {}, blend mode: {}, algorithm: {}",
                 self.state.color_threshold,
-                self.state.color_blend_mode
+                self.state.color_blend_mode,
+                self.state.algorithm
             );
 
             // Ensure all required uniforms are present
@@ -208,6 +255,31 @@ impl Effect for UpscaleEffect {
         Ok(bind_group)
     }
 
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("algorithm", UniformValue::Uint(index)) => {
+                match UpscaleAlgorithm::from_index(*index) {
+                    Some(algorithm) => self.state.algorithm = algorithm,
+                    None => warn!("Unknown upscale algorithm: {}", index),
+                }
+            }
+            _ => warn!("Unknown upscale parameter: {} = {:?}", name, value),
+        }
+    }
+
+    fn parameters(&self) -> Vec<EffectParam> {
+        vec![EffectParam {
+            name: "algorithm",
+            kind: EffectParamKind::Choice(
+                UpscaleAlgorithm::ALL
+                    .iter()
+                    .map(|a| a.to_string())
+                    .collect(),
+            ),
+            value: UniformValue::Uint(self.state.algorithm as u32),
+        }]
+    }
+
     fn update_comparison(&mut self, comparison_enabled: bool, comparison_position: f32) {
         // No-op for this effect
     }
@@ -215,4 +287,12 @@ impl Effect for UpscaleEffect {
     fn clone_box(&self) -> Box<dyn Effect> {
         Box::new(self.clone())
     }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
 }
@@ -1,5 +1,6 @@
 use super::Effect;
 use crate::video::{
+    color_space::BT709_CONFIG,
     pipeline::manager::{VideoEntry, VideoPipelineManager},
     shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
     ShaderEffect,
@@ -10,7 +11,11 @@ use tracing::{debug, error, info, warn};
 
 #[derive(Clone, Debug)]
 pub struct YuvToRgbEffect {
-    pub color_space: u32, // 0 for BT.709, 1 for BT.601
+    pub color_space: u32,   // 0 for BT.709, 1 for BT.601, 2 for BT.2020
+    pub region: [f32; 4],   // Normalized source region to sample [x, y, width, height]
+    pub y_range: [f32; 2],  // Min and max of Y
+    pub uv_range: [f32; 2], // Min and max of U and V
+    pub sample_scale: f32,  // Scales sampled values to 8-bit levels, see `video::sample_scale`
     pub format: wgpu::TextureFormat,
 }
 
@@ -19,13 +24,13 @@ impl Effect for YuvToRgbEffect {
         &mut self,
         device: &iced_wgpu::wgpu::Device,
         queue: &iced_wgpu::wgpu::Queue,
-    ) -> ShaderEffect {
+    ) -> anyhow::Result<ShaderEffect> {
         // Create uniform buffer with color space
         // Binding is 3 to match the shader layout
         let mut shader_uniforms = ShaderUniforms::new(device, 3);
 
-        // Set color space uniform
-        shader_uniforms.set_uniform("color_space", UniformValue::Uint(self.color_space));
+        // Set color space, region and range uniforms
+        self.set_uniforms(&mut shader_uniforms);
         shader_uniforms.update_buffer(queue);
 
         let shader_source = include_str!("../../../../../../assets/shaders/yuv_to_rgb.wgsl");
@@ -73,7 +78,18 @@ impl Effect for YuvToRgbEffect {
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
-                        min_binding_size: Some(NonZero::new(4).unwrap()),
+                        min_binding_size: Some(NonZero::new(64).unwrap()), // u32 + vec4<f32> + 2 vec2<f32> + f32, padded
+                    },
+                    count: None,
+                },
+                // Alpha texture (opaque for videos without alpha)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
                     },
                     count: None,
                 },
@@ -93,7 +109,14 @@ impl Effect for YuvToRgbEffect {
             .with_shader_source(shader_source.into())
             .with_bind_group_layout(bind_group_layout)
             .with_uniforms(shader_uniforms)
-            .build(device, queue, self.format);
+            .with_expected_layout(&[
+                ("color_space", 0, 4),
+                ("region", 16, 16),
+                ("y_range", 32, 8),
+                ("uv_range", 40, 8),
+                ("sample_scale", 48, 4),
+            ])
+            .build(device, queue, self.format)?;
 
         debug!(
             "Created shader effect with layout ID: {:?}",
@@ -107,7 +130,7 @@ impl Effect for YuvToRgbEffect {
             "Bind group layout ID changed during shader effect creation"
         );
 
-        shader_effect
+        Ok(shader_effect)
     }
 
     fn update_for_frame(
@@ -118,12 +141,11 @@ impl Effect for YuvToRgbEffect {
         texture_list: &[&Texture],
     ) -> anyhow::Result<()> {
         // Check if we have enough views
-        if texture_view_list.len() < 2 {
+        if texture_view_list.len() < 3 {
             return Err(anyhow::anyhow!("Not enough texture views"));
         }
 
-        // YUV conversion requires new bind groups for each frame
-        // as the texture views change with each video frame
+        // Bind the Y, UV and alpha planes of the video
         let bind_group = self.create_bind_group(device, effect, texture_view_list, texture_list)?;
 
         // Replace the old bind group entirely
@@ -142,10 +164,10 @@ impl Effect for YuvToRgbEffect {
     ) -> anyhow::Result<wgpu::BindGroup> {
         effect.debug_layout();
 
-        // Handling insufficient textures case (expected: Y and UV)
-        if texture_view_list.len() < 2 {
+        // Handling insufficient textures case (expected: Y, UV and alpha)
+        if texture_view_list.len() < 3 {
             warn!(
-                "YUV to RGB effect received only {} texture views (expected 2)",
+                "YUV to RGB effect received only {} texture views (expected 3)",
                 texture_view_list.len()
             );
             warn!("This might cause a pink screen if not handled properly");
@@ -160,7 +182,7 @@ impl Effect for YuvToRgbEffect {
                 if input_format == wgpu::TextureFormat::Bgra8UnormSrgb {
                     info!("Input is already in BGRA format - creating compatible bind group");
 
-                    // Use same texture for both Y and UV to satisfy binding requirements
+                    // Use same texture for Y, UV and alpha to satisfy binding requirements
                     let texture_view = &texture_view_list[0];
 
                     // Create a bind group that can handle RGB input
@@ -189,6 +211,10 @@ impl Effect for YuvToRgbEffect {
                                     .buffer()
                                     .as_entire_binding(),
                             },
+                            wgpu::BindGroupEntry {
+                                binding: 4,
+                                resource: wgpu::BindingResource::TextureView(texture_view), // Same view for alpha
+                            },
                         ],
                     });
 
@@ -202,9 +228,10 @@ impl Effect for YuvToRgbEffect {
             ));
         }
 
-        // Normal case: Create bind group with separate Y and UV textures
+        // Normal case: Create bind group with separate Y, UV and alpha textures
         let y_texture_view = &texture_view_list[0];
         let uv_texture_view = &texture_view_list[1];
+        let a_texture_view = &texture_view_list[2];
 
         // Log texture formats for debugging
         debug!("Y texture format: {:?}", texture_list[0].format());
@@ -238,6 +265,10 @@ impl Effect for YuvToRgbEffect {
                         .buffer()
                         .as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(a_texture_view),
+                },
             ],
         });
 
@@ -246,27 +277,66 @@ impl Effect for YuvToRgbEffect {
     }
 
     fn prepare(&mut self, effect: &mut ShaderEffect, queue: &iced_wgpu::wgpu::Queue) {
-        // Update color space uniform if needed
+        // Update color space and region uniforms if needed
         if let Some(uniforms) = &mut effect.uniforms {
-            uniforms.set_uniform("color_space", UniformValue::Uint(self.color_space));
+            self.set_uniforms(uniforms);
             uniforms.update_buffer(queue);
         }
     }
 
+    fn set_parameter(&mut self, name: &str, value: &UniformValue) {
+        match (name, value) {
+            ("region", UniformValue::Vec4(region)) => self.region = *region,
+            ("color_space", UniformValue::Uint(color_space)) => self.color_space = *color_space,
+            ("y_range", UniformValue::Vec2(range)) => self.y_range = *range,
+            ("uv_range", UniformValue::Vec2(range)) => self.uv_range = *range,
+            ("sample_scale", UniformValue::Float(scale)) => self.sample_scale = *scale,
+            _ => warn!("Unknown YUV to RGB parameter: {} = {:?}", name, value),
+        }
+    }
+
     fn update_comparison(&mut self, _: bool, _: f32) {
         // No comparison functionality needed for YUV to RGB conversion
     }
 
+    fn required_input_format(&self) -> Option<wgpu::TextureFormat> {
+        // Samples the Y plane of the video, the UV and alpha planes are bound alongside it
+        Some(wgpu::TextureFormat::R8Unorm)
+    }
+
     fn clone_box(&self) -> Box<dyn Effect> {
         Box::new(self.clone())
     }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
 }
 
 impl YuvToRgbEffect {
     pub fn new(color_space: u32, format: wgpu::TextureFormat) -> Self {
         Self {
             color_space,
+            region: [0.0, 0.0, 1.0, 1.0],
+            y_range: BT709_CONFIG.y_range,
+            uv_range: BT709_CONFIG.uv_range,
+            sample_scale: 1.0,
             format,
         }
     }
+
+    /// Write the color space, region and ranges to the uniforms
+    ///
+    /// The region starts at the 16-byte alignment of vec4 in WGSL uniforms.
+    fn set_uniforms(&self, uniforms: &mut ShaderUniforms) {
+        uniforms.set_uniform("color_space", UniformValue::Uint(self.color_space));
+        uniforms.set_uniform("region", UniformValue::Vec4(self.region));
+        uniforms.set_uniform("y_range", UniformValue::Vec2(self.y_range));
+        uniforms.set_uniform("uv_range", UniformValue::Vec2(self.uv_range));
+        uniforms.set_uniform("sample_scale", UniformValue::Float(self.sample_scale));
+    }
 }
@@ -1,12 +1,16 @@
 use iced_wgpu::wgpu::{self, TextureFormat, TextureView};
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     ops::Deref,
-    sync::{atomic::AtomicUsize, Arc},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
 };
 use tracing::{debug, error, info, trace, warn};
 
 use crate::video::{
+    color_space::{ColorRange, ColorSpace, BT709_CONFIG},
     pipeline::effects::yuv_to_rgb::YuvToRgbEffect,
     render_passes::RenderPasses,
     shader::{ShaderEffectBuilder, ShaderUniforms, UniformValue},
@@ -15,24 +19,155 @@ use crate::video::{
 };
 
 use super::{
-    effects::{Effect, EffectManager},
+    effects::{
+        custom::{CustomEffect, CustomEffectSource},
+        lut::{CubeLut, LutEffect},
+        Effect, EffectManager,
+    },
+    present::{self, PresentPass},
     state::PipelineState,
-    video::VideoPipeline,
+    video::{self, VideoPipeline},
+    PipelineConfig, SamplingMode,
 };
 
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Continue an FNV-1a hash with the given bytes
+///
+/// Used instead of `DefaultHasher` because recorded hashes must stay the same
+/// across Rust versions.
+fn hash_bytes(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Token held by the owner of a video, e.g. its `VideoStream`
+///
+/// Each pipeline keeps a weak reference for the videos it draws, see
+/// [`VideoPrimitive::with_owner`](crate::video::VideoPrimitive::with_owner),
+/// and frees their GPU resources once the owner has been dropped.
+#[derive(Debug, Default)]
+pub struct VideoOwner;
+
+/// Owners of the videos drawn by one pipeline
+#[derive(Debug, Default)]
+struct VideoOwners {
+    owners: BTreeMap<u64, Weak<VideoOwner>>,
+}
+
+impl VideoOwners {
+    /// Track the owner of a video, replacing an earlier owner of the same ID
+    fn track(&mut self, video_id: u64, owner: &Weak<VideoOwner>) {
+        match self.owners.get(&video_id) {
+            Some(tracked) if tracked.ptr_eq(owner) => {}
+            _ => {
+                self.owners.insert(video_id, owner.clone());
+            }
+        }
+    }
+
+    /// Stop tracking the videos whose owner has been dropped and return their IDs
+    fn take_dropped(&mut self) -> Vec<u64> {
+        let mut dropped = Vec::new();
+        self.owners.retain(|&video_id, owner| {
+            let alive = owner.strong_count() > 0;
+            if !alive {
+                dropped.push(video_id);
+            }
+            alive
+        });
+        dropped
+    }
+
+    fn len(&self) -> usize {
+        self.owners.len()
+    }
+}
+
+/// Copy pixel rows without the alignment padding of a texture readback
+fn unpad_rows(padded: &[u8], padded_bytes_per_row: usize, bytes_per_row: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(padded.len() / padded_bytes_per_row * bytes_per_row);
+    for row in padded.chunks(padded_bytes_per_row) {
+        data.extend_from_slice(&row[..bytes_per_row]);
+    }
+    data
+}
+
+/// Result of reading back a frame, the width, height and tightly packed RGBA pixels
+pub type FrameReadBack = anyhow::Result<(u32, u32, Vec<u8>)>;
+
+/// A frame copied to a buffer that is being mapped, see [`VideoPipelineManager::read_back_frame`]
+struct PendingReadBack {
+    buffer: wgpu::Buffer,
+    size: wgpu::Extent3d,
+    padded_bytes_per_row: u32,
+    bytes_per_row: u32,
+    swap_red_blue: bool,
+    mapped: Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>, // Set once mapping finishes
+    on_ready: Box<dyn FnOnce(FrameReadBack) + Send>,
+}
+
 /// Represents a single video entry with associated GPU resources
 pub struct VideoEntry {
     pub texture_y: wgpu::Texture,  // Y plane texture
     pub texture_uv: wgpu::Texture, // UV plane texture (chroma)
+    pub texture_a: wgpu::Texture,  // Alpha plane texture, a single opaque texel without alpha
+    pub has_alpha: bool,           // Whether the video has an alpha plane
     pub instances: wgpu::Buffer,   // Uniform buffer for rendering
     pub bg0: wgpu::BindGroup,      // Bind group connecting textures and uniforms
     pub alive: bool,               // Whether this video is still active
 
-    pub prepare_index: AtomicUsize,  // Current prepare buffer index
-    pub render_index: AtomicUsize,   // Current render buffer index
+    pub prepare_index: AtomicUsize, // Number of uniform slots prepared so far
+    pub render_index: AtomicUsize,  // Number of prepared uniform slots drawn so far
     pub aligned_uniform_size: usize, // Size of each uniform entry, aligned to GPU requirements
 }
 
+/// Supported conversions, listed in errors for unsupported ones
+const SUPPORTED_CONVERSIONS: &str =
+    "R8Unorm, R16Unorm or R16Float video planes to the intermediate format (YUV to RGB), \
+     between RGBA color formats (sampled directly)";
+
+/// How the input of an effect is converted to the format it requires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FormatConversion {
+    /// Convert the video planes to RGB with a `yuv_to_rgb` pass
+    YuvToRgb,
+    /// Both formats sample as normalized RGBA floats, so no pass is needed
+    Direct,
+}
+
+impl FormatConversion {
+    /// Find the conversion from `input` to `required`, if supported
+    fn find(
+        input: TextureFormat,
+        required: TextureFormat,
+        intermediate: TextureFormat,
+    ) -> Option<Self> {
+        match (input, required) {
+            _ if video::is_plane_format(input) && required == intermediate => Some(Self::YuvToRgb),
+            _ if is_rgba_color_format(input) && is_rgba_color_format(required) => {
+                Some(Self::Direct)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Check if a format holds filterable RGBA color data
+fn is_rgba_color_format(format: TextureFormat) -> bool {
+    matches!(
+        format,
+        TextureFormat::Rgba8Unorm
+            | TextureFormat::Rgba8UnormSrgb
+            | TextureFormat::Bgra8Unorm
+            | TextureFormat::Bgra8UnormSrgb
+            | TextureFormat::Rgb10a2Unorm
+            | TextureFormat::Rgba16Float
+    )
+}
+
 /// Main manager for video pipelines and effect chains
 pub struct VideoPipelineManager {
     state: PipelineState,
@@ -40,7 +175,14 @@ pub struct VideoPipelineManager {
     pub texture_manager: TextureManager,
     pub effect_manager: EffectManager,
     format: wgpu::TextureFormat,
+    intermediate_format: wgpu::TextureFormat,
+    present_pass: Option<PresentPass>, // Set when the surface can't be drawn to by effects
     videos: BTreeMap<u64, VideoEntry>,
+    owners: VideoOwners, // Videos freed once their owner is dropped
+    failed_custom_effects: HashSet<String>, // Custom effects whose shader was rejected
+    lut: Option<Arc<CubeLut>>, // LUT of the "lut" effect
+    bypass: bool,        // Draw without effects, see `set_bypass`
+    read_backs: Vec<PendingReadBack>, // Frames being read back, see `poll_read_backs`
     pub effects_added: bool,
 }
 
@@ -54,9 +196,32 @@ pub struct TextureInfo {
 impl VideoPipelineManager {
     /// Create a new video pipeline manager with the specified texture format
     pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        Self::with_config(device, format, PipelineConfig::default())
+    }
+
+    /// Create a new video pipeline manager with the specified texture format and video pipeline options
+    pub fn with_config(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        config: PipelineConfig,
+    ) -> Self {
         let state = PipelineState::default();
-        let video_pipeline = VideoPipeline::new(device, format);
-        let mut texture_manager = TextureManager::new(format);
+        let sampling = config.sampling;
+        // Effects render to linear or high precision intermediates, with a
+        // final pass onto surfaces of another format
+        let intermediate_format =
+            present::intermediate_format(format, config.high_precision_effects);
+        let bypass = config.bypass_effects;
+        let video_pipeline = VideoPipeline::with_config(device, format, config);
+
+        let present_pass =
+            (intermediate_format != format).then(|| PresentPass::new(device, format, sampling));
+        debug!(
+            "Surface format: {:?}, intermediate format: {:?}",
+            format, intermediate_format
+        );
+
+        let mut texture_manager = TextureManager::new(intermediate_format);
         let effect_manager = EffectManager::new();
 
         if !texture_manager.validate_formats() {
@@ -77,11 +242,28 @@ impl VideoPipelineManager {
             texture_manager,
             effect_manager,
             format,
+            intermediate_format,
+            present_pass,
             videos: BTreeMap::new(),
+            owners: VideoOwners::default(),
+            failed_custom_effects: HashSet::new(),
+            lut: None,
+            bypass,
+            read_backs: Vec::new(),
             effects_added: false,
         }
     }
 
+    /// Get the format of the surface the video is drawn to
+    pub fn surface_format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    /// Get the format effects render to, linear for sRGB surfaces or `Rgba16Float` for high precision
+    pub fn intermediate_format(&self) -> wgpu::TextureFormat {
+        self.intermediate_format
+    }
+
     /// Resize intermediate textures based on video dimensions
     pub fn resize_for_effects(&mut self, device: &wgpu::Device) {
         if let Some(video) = self.videos.values().next() {
@@ -190,12 +372,73 @@ impl VideoPipelineManager {
                     trace!("Destroying resources for video {}", id);
                     video.texture_y.destroy();
                     video.texture_uv.destroy();
+                    video.texture_a.destroy();
                     video.instances.destroy();
                 }
             }
         }
     }
 
+    /// Switch between smooth and pixel-exact filtering of the video
+    ///
+    /// Recreates the samplers of the video pipeline and the present pass, and
+    /// rebinds existing videos. Does nothing if the mode is already in use.
+    pub fn set_sampling(&mut self, device: &wgpu::Device, sampling: SamplingMode) {
+        if self.video_pipeline.sampling() == sampling {
+            return;
+        }
+        self.video_pipeline
+            .set_sampling(device, sampling, &mut self.videos);
+        if let Some(present_pass) = &mut self.present_pass {
+            present_pass.set_sampling(device, sampling);
+        }
+    }
+
+    /// Draw the decoded video directly, skipping the effect chain
+    ///
+    /// Registered effects are kept but neither prepared nor rendered, not even
+    /// the YUV to RGB conversion, so comparing against normal playback isolates
+    /// the cost of the effects from decoding.
+    pub fn set_bypass(&mut self, bypass: bool) {
+        if self.bypass != bypass {
+            debug!("Effect bypass: {}", bypass);
+            self.bypass = bypass;
+        }
+    }
+
+    /// Check if the effect chain is bypassed, see `set_bypass`
+    pub fn is_bypassed(&self) -> bool {
+        self.bypass
+    }
+
+    /// Free the GPU resources of a video right away
+    pub fn release_video(&mut self, video_id: u64) {
+        if let Some(video) = self.videos.get_mut(&video_id) {
+            debug!("Releasing video {}", video_id);
+            video.alive = false;
+            self.cleanup();
+        } else {
+            trace!("Video {} already released", video_id);
+        }
+    }
+
+    /// Free the GPU resources of a video once its owner is dropped
+    pub fn track_owner(&mut self, video_id: u64, owner: &Weak<VideoOwner>) {
+        self.owners.track(video_id, owner);
+    }
+
+    /// Free the GPU resources of the videos whose owner has been dropped
+    pub fn release_dropped_videos(&mut self) {
+        let tracked = self.owners.len();
+        let dropped = self.owners.take_dropped();
+        debug_assert_eq!(self.owners.len(), tracked - dropped.len());
+
+        for video_id in dropped {
+            self.release_video(video_id);
+            debug_assert!(!self.videos.contains_key(&video_id));
+        }
+    }
+
     /// Get a video entry by ID
     pub fn get_video(&self, video_id: u64) -> Option<&VideoEntry> {
         self.videos.get(&video_id)
@@ -206,6 +449,43 @@ impl VideoPipelineManager {
         !self.effect_manager.is_empty()
     }
 
+    /// Get the names of the effects in the chain, in processing order
+    pub fn effect_names(&self) -> Vec<String> {
+        self.effect_manager
+            .effects
+            .iter()
+            .map(|entry| entry.effect.name.clone())
+            .collect()
+    }
+
+    /// Get the current uniform values of an effect, in buffer order
+    ///
+    /// Padding uniforms (names starting with `_`) are skipped. Returns `None`
+    /// if no effect with that name is in the chain.
+    pub fn effect_params(&self, name: &str) -> Option<Vec<(String, UniformValue)>> {
+        let entry = self
+            .effect_manager
+            .effects
+            .iter()
+            .find(|entry| entry.effect.name == name)?;
+
+        let params = entry
+            .effect
+            .uniforms
+            .as_ref()
+            .map(|uniforms| {
+                uniforms
+                    .values
+                    .iter()
+                    .filter(|(name, _)| !name.starts_with('_'))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(params)
+    }
+
     /// Remove all effects from the pipeline
     pub fn clear_effects(&mut self) {
         debug!("Clearing all effects from pipeline");
@@ -292,7 +572,7 @@ impl VideoPipelineManager {
                 };
 
                 // Get the processed result (last effect's output)
-                let processed_index = i - 1;
+                let processed_index = self.effect_manager.input_index(i);
                 let processed_texture = match self.texture_manager.get_texture(processed_index) {
                     Some(texture) => texture,
                     None => {
@@ -314,12 +594,7 @@ impl VideoPipelineManager {
 
                 // Update the comparison effect with both textures
                 let effect_entry = &mut self.effect_manager.effects[i];
-                if let Err(e) = effect_entry.state.update_for_frame(
-                    device,
-                    &mut effect_entry.effect,
-                    &views,
-                    &textures,
-                ) {
+                if let Err(e) = effect_entry.update_for_frame(device, &views, &textures) {
                     error!("Failed to update comparison effect: {}", e);
                 } else {
                     trace!("Successfully updated comparison effect");
@@ -331,13 +606,17 @@ impl VideoPipelineManager {
     }
 
     /// Prepare the pipeline for rendering a frame
+    ///
+    /// Only the normalized `region` of the frame is shown, stretched to the bounds.
     pub fn prepare(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         video_id: u64,
         bounds: &iced::Rectangle,
-        color_space: ffmpeg_next::color::Space,
+        color_space: ColorSpace,
+        color_range: ColorRange,
+        region: &iced::Rectangle,
     ) {
         // Update video pipeline state
         self.video_pipeline.prepare(
@@ -346,27 +625,55 @@ impl VideoPipelineManager {
             video_id,
             bounds,
             color_space,
+            color_range,
+            region,
             &mut self.videos,
             &self.state,
         );
+        if self.bypass {
+            return;
+        }
+
+        // The YUV to RGB conversion samples the frame for the effect chain
+        let (y_range, uv_range) = color_space
+            .config()
+            .unwrap_or(&BT709_CONFIG)
+            .ranges(color_range);
+        let sample_scale = self
+            .videos
+            .get(&video_id)
+            .map_or(1.0, |video| video::sample_scale(video.texture_y.format()));
+        let parameters = [
+            (
+                "region",
+                UniformValue::Vec4([region.x, region.y, region.width, region.height]),
+            ),
+            ("color_space", UniformValue::Uint(color_space as u32)),
+            ("y_range", UniformValue::Vec2(y_range)),
+            ("uv_range", UniformValue::Vec2(uv_range)),
+            ("sample_scale", UniformValue::Float(sample_scale)),
+        ];
+        for effect_entry in &mut self.effect_manager.effects {
+            if effect_entry.effect.name == "yuv_to_rgb" {
+                for (name, value) in &parameters {
+                    effect_entry.state.set_parameter(name, value);
+                }
+            }
+        }
 
         if let Some(video) = self.videos.get(&video_id) {
             // Get video textures for the first effect (YUV to RGB)
             let y_view = video.texture_y.create_view(&Default::default());
             let uv_view = video.texture_uv.create_view(&Default::default());
+            let a_view = video.texture_a.create_view(&Default::default());
 
-            let textures = vec![&video.texture_y, &video.texture_uv];
-            let views = vec![y_view, uv_view];
+            let textures = vec![&video.texture_y, &video.texture_uv, &video.texture_a];
+            let views = vec![y_view, uv_view, a_view];
 
             // Update the first effect (YUV to RGB) with video textures
             if self.effect_manager.len() > 0 {
                 let effect_entry = &mut self.effect_manager.effects[0];
-                if let Err(e) = effect_entry.state.update_for_frame(
-                    device,
-                    &mut effect_entry.effect,
-                    &views,
-                    &textures,
-                ) {
+                if let Err(e) = effect_entry.update_for_frame(device, &views, &textures) {
                     error!("Failed to update first effect: {}", e);
                 }
             }
@@ -385,8 +692,15 @@ impl VideoPipelineManager {
 
             // For subsequent effects, use the output from previous effect
             for i in 1..self.effect_manager.len() {
-                // Get the output texture from the previous effect
-                let prev_output_index = i - 1;
+                // Comparison samples two textures, updated separately below
+                if !self.effect_manager.effects[i].enabled
+                    || self.effect_manager.effects[i].effect.name == "comparison"
+                {
+                    continue;
+                }
+
+                // Get the output texture from the previous enabled effect
+                let prev_output_index = self.effect_manager.input_index(i);
 
                 // Get the texture from the TextureManager
                 if let Some(input_texture) = self.texture_manager.get_texture(prev_output_index) {
@@ -399,12 +713,9 @@ impl VideoPipelineManager {
 
                     // Update this effect with the output from the previous effect
                     let effect_entry = &mut self.effect_manager.effects[i];
-                    if let Err(e) = effect_entry.state.update_for_frame(
-                        device,
-                        &mut effect_entry.effect,
-                        &current_views,
-                        &current_textures,
-                    ) {
+                    if let Err(e) =
+                        effect_entry.update_for_frame(device, &current_views, &current_textures)
+                    {
                         error!("Failed to update effect {}: {}", i, e);
                     } else {
                         trace!("Updated effect {} successfully", i);
@@ -419,6 +730,14 @@ impl VideoPipelineManager {
                 self.prepare_comparison_effect(device, queue);
             }
 
+            // The last effect writes to its intermediate texture when presenting separately
+            if let Some(present_pass) = &mut self.present_pass {
+                let last_index = self.effect_manager.output_index().unwrap_or(0);
+                if let Some(texture) = self.texture_manager.get_texture(last_index) {
+                    present_pass.set_source(device, &texture);
+                }
+            }
+
             // Update uniform values for all effects
             self.update_effect_uniforms(queue);
         }
@@ -428,6 +747,32 @@ impl VideoPipelineManager {
         self.texture_manager.debug_print_state();
     }
 
+    /// Prepare a picture-in-picture view showing a normalized region of the frame
+    ///
+    /// The view reuses the frame uploaded for the main view of the same video.
+    pub fn prepare_pip(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        video_id: u64,
+        bounds: &iced::Rectangle,
+        color_space: ColorSpace,
+        color_range: ColorRange,
+        region: &iced::Rectangle,
+    ) {
+        self.video_pipeline.prepare(
+            device,
+            queue,
+            video_id,
+            bounds,
+            color_space,
+            color_range,
+            region,
+            &mut self.videos,
+            &self.state,
+        );
+    }
+
     /// Check if an effect with the given name exists
     pub fn has_effect(&self, name: &str) -> bool {
         self.effect_manager
@@ -440,12 +785,93 @@ impl VideoPipelineManager {
     pub fn remove_effect(&mut self, name: &str) {
         if self.has_effect(name) {
             debug!("Removing effect: {}", name);
+            if name == "lut" {
+                self.lut = None;
+            }
             self.effect_manager
                 .effects
                 .retain(|e| e.effect.name != name);
         }
     }
 
+    /// Enable or disable an effect without removing it from the chain
+    ///
+    /// Disabled effects keep their pipeline and resources and pass their input
+    /// through, so toggling is cheap. The first effect, which reads the video,
+    /// can't be disabled. Returns whether an effect with the name exists.
+    pub fn set_effect_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let Some(index) = self
+            .effect_manager
+            .effects
+            .iter()
+            .position(|e| e.effect.name == name)
+        else {
+            return false;
+        };
+
+        if index == 0 && !enabled {
+            warn!("Can't disable the first effect '{}'", name);
+        } else if self.effect_manager.effects[index].enabled != enabled {
+            debug!(
+                "{} effect: {}",
+                if enabled { "Enabling" } else { "Disabling" },
+                name
+            );
+            self.effect_manager.effects[index].enabled = enabled;
+        }
+        true
+    }
+
+    /// Check if an effect is in the chain and enabled
+    pub fn is_effect_enabled(&self, name: &str) -> bool {
+        self.effect_manager
+            .effects
+            .iter()
+            .any(|e| e.effect.name == name && e.enabled)
+    }
+
+    /// Put the named effects in the given order
+    ///
+    /// The effects take the places the named effects had in the chain, so
+    /// effects that aren't named, such as the first YUV to RGB stage, stay where
+    /// they are. Bind groups are rebuilt on the next frame for the new inputs.
+    pub fn reorder_effects(&mut self, names: &[String]) -> anyhow::Result<()> {
+        let mut slots = Vec::with_capacity(names.len());
+        for name in names {
+            let index = self
+                .effect_manager
+                .effects
+                .iter()
+                .position(|e| &e.effect.name == name)
+                .ok_or_else(|| anyhow::anyhow!("No effect named '{}'", name))?;
+            if slots.contains(&index) {
+                anyhow::bail!("Effect '{}' is listed more than once", name);
+            }
+            slots.push(index);
+        }
+
+        // Move each effect into its slot, front to back, so placed effects stay put
+        let mut targets = slots.clone();
+        targets.sort_unstable();
+        for (i, &target) in targets.iter().enumerate() {
+            let current = slots[i];
+            if current == target {
+                continue;
+            }
+            // Swap through `move_effect` so unnamed effects in between don't shift
+            self.effect_manager.move_effect(current, target)?;
+            self.effect_manager.move_effect(target + 1, current)?;
+            for slot in &mut slots[i + 1..] {
+                if *slot == target {
+                    *slot = current;
+                }
+            }
+        }
+
+        self.debug_effect_chain();
+        Ok(())
+    }
+
     /// Log the current effect chain for debugging
     fn debug_effect_chain(&self) {
         debug!("Effect Chain: {} total effects", self.effect_manager.len());
@@ -511,7 +937,14 @@ impl VideoPipelineManager {
 
         if let Some(video) = self.videos.get(&video_id) {
             // For each effect in the chain, ensure the video textures are properly bound
-            if !self.effect_manager.is_empty() {
+            if !self.bypass && !self.effect_manager.is_empty() {
+                // The effect chain samples the video textures directly and never
+                // draws the prepared uniforms, so mark them as consumed
+                video.render_index.store(
+                    video.prepare_index.load(Ordering::Relaxed),
+                    Ordering::Relaxed,
+                );
+
                 // Get input texture dimensions
                 let texture_width = video.texture_y.size().width as f32;
                 let texture_height = video.texture_y.size().height as f32;
@@ -541,13 +974,354 @@ impl VideoPipelineManager {
                     texture_height,
                 );
             } else {
-                // Fallback to basic video rendering if no effects
+                // Fallback to basic video rendering if no effects or bypassed
                 trace!("No effects active, using basic video rendering");
                 self.video_pipeline.draw(target, encoder, clip, video);
             }
         }
     }
 
+    /// Render the current frame offscreen and hash the result
+    ///
+    /// The frame goes through the same effect chain as on screen, at the native
+    /// video size and in the surface format, so the hash only changes when the
+    /// output does. Meant for golden-image regression tests; stateful effects
+    /// such as temporal denoise see the capture as an extra rendered frame.
+    pub fn capture_frame_hash(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        video_id: u64,
+    ) -> anyhow::Result<u64> {
+        let (size, data) = self.render_offscreen(device, queue, video_id)?;
+        let hash = hash_bytes(FNV_OFFSET_BASIS, &data);
+
+        debug!(
+            "Captured frame hash for video {}: {:016x} ({}x{}, {:?})",
+            video_id, hash, size.width, size.height, self.format
+        );
+        Ok(hash)
+    }
+
+    /// Render the current frame offscreen and start reading it back as RGBA
+    ///
+    /// Like `capture_frame_hash` the frame goes through the effect chain at the
+    /// native video size. This doesn't wait for the GPU, `on_ready` is called
+    /// from a later `poll_read_backs` with the width, height and tightly packed
+    /// 8-bit RGBA pixels, e.g. to save a screenshot. Only 8-bit surface formats
+    /// (`Bgra8UnormSrgb` on most platforms) can be read back.
+    pub fn read_back_frame(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        video_id: u64,
+        on_ready: impl FnOnce(FrameReadBack) + Send + 'static,
+    ) -> anyhow::Result<()> {
+        let swap_red_blue = match self.format {
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            format => anyhow::bail!("Cannot read back {:?} frames as RGBA", format),
+        };
+
+        let (mut encoder, texture) = self.encode_offscreen(device, video_id)?;
+        let (buffer, padded_bytes_per_row, bytes_per_row) =
+            Self::copy_to_read_back_buffer(device, &mut encoder, &texture)?;
+        queue.submit(Some(encoder.finish()));
+
+        let mapped = Arc::new(Mutex::new(None));
+        let mapped_result = mapped.clone();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *mapped_result.lock().unwrap() = Some(result);
+            });
+
+        debug!(
+            "Reading back frame of video {} ({}x{}, {:?})",
+            video_id,
+            texture.width(),
+            texture.height(),
+            self.format
+        );
+        self.read_backs.push(PendingReadBack {
+            buffer,
+            size: texture.size(),
+            padded_bytes_per_row,
+            bytes_per_row,
+            swap_red_blue,
+            mapped,
+            on_ready: Box::new(on_ready),
+        });
+        Ok(())
+    }
+
+    /// Finish the frame read backs whose buffers have been mapped
+    ///
+    /// Polls the device without blocking, call this every frame while
+    /// `read_back_frame` requests are outstanding.
+    pub fn poll_read_backs(&mut self, device: &wgpu::Device) {
+        if self.read_backs.is_empty() {
+            return;
+        }
+        device.poll(wgpu::Maintain::Poll);
+
+        let (finished, pending): (Vec<_>, Vec<_>) = self
+            .read_backs
+            .drain(..)
+            .partition(|read_back| read_back.mapped.lock().unwrap().is_some());
+        self.read_backs = pending;
+
+        for read_back in finished {
+            let mapped = read_back.mapped.lock().unwrap().take();
+            let result = match mapped {
+                Some(Ok(())) => {
+                    let mut data = unpad_rows(
+                        &read_back.buffer.slice(..).get_mapped_range(),
+                        read_back.padded_bytes_per_row as usize,
+                        read_back.bytes_per_row as usize,
+                    );
+                    read_back.buffer.unmap();
+                    if read_back.swap_red_blue {
+                        for pixel in data.chunks_exact_mut(4) {
+                            pixel.swap(0, 2);
+                        }
+                    }
+                    Ok((read_back.size.width, read_back.size.height, data))
+                }
+                Some(Err(e)) => Err(e.into()),
+                None => unreachable!("Only mapped read backs are finished"),
+            };
+            (read_back.on_ready)(result);
+        }
+    }
+
+    /// Render the current frame at its native size and copy it to the CPU
+    ///
+    /// Returns the size and the pixel rows in the surface format, without
+    /// the padding `copy_texture_to_buffer` needs for row alignment.
+    fn render_offscreen(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        video_id: u64,
+    ) -> anyhow::Result<(wgpu::Extent3d, Vec<u8>)> {
+        let (encoder, texture) = self.encode_offscreen(device, video_id)?;
+        let data = Self::read_texture(device, queue, encoder, &texture)?;
+        Ok((texture.size(), data))
+    }
+
+    /// Encode drawing the current frame at its native size to a texture in the surface format
+    fn encode_offscreen(
+        &self,
+        device: &wgpu::Device,
+        video_id: u64,
+    ) -> anyhow::Result<(wgpu::CommandEncoder, wgpu::Texture)> {
+        let video = self
+            .videos
+            .get(&video_id)
+            .ok_or_else(|| anyhow::anyhow!("No video with id {}", video_id))?;
+
+        let size = video.texture_y.size();
+        let capture_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("frame_capture_texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let capture_view = capture_texture.create_view(&Default::default());
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("frame_capture_encoder"),
+        });
+
+        // Draw the frame the same way as on screen, filling the whole capture texture
+        let clip = iced::Rectangle {
+            x: 0,
+            y: 0,
+            width: size.width,
+            height: size.height,
+        };
+        if self.effect_manager.is_empty() {
+            self.video_pipeline
+                .draw(&capture_view, &mut encoder, &clip, video);
+        } else {
+            self.process_effect_chain(
+                &mut encoder,
+                &capture_view,
+                &clip,
+                video,
+                size.width as f32,
+                size.height as f32,
+                size.width as f32,
+                size.height as f32,
+            );
+        }
+
+        Ok((encoder, capture_texture))
+    }
+
+    /// Copy a texture to the CPU after the commands recorded in `encoder`
+    ///
+    /// Returns the pixel rows in the texture format, without the padding
+    /// `copy_texture_to_buffer` needs for row alignment. The texture needs
+    /// `COPY_SRC` usage.
+    fn read_texture(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mut encoder: wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> anyhow::Result<Vec<u8>> {
+        let (readback_buffer, padded_bytes_per_row, unpadded_bytes_per_row) =
+            Self::copy_to_read_back_buffer(device, &mut encoder, texture)?;
+        queue.submit(Some(encoder.finish()));
+
+        // Wait for the copy to finish and map the buffer
+        let slice = readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let data = unpad_rows(
+            &slice.get_mapped_range(),
+            padded_bytes_per_row as usize,
+            unpadded_bytes_per_row as usize,
+        );
+        readback_buffer.unmap();
+
+        Ok(data)
+    }
+
+    /// Encode copying a texture to a new mappable buffer
+    ///
+    /// Returns the buffer with the padded and unpadded bytes per row, rows are
+    /// padded to the alignment `copy_texture_to_buffer` needs.
+    fn copy_to_read_back_buffer(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> anyhow::Result<(wgpu::Buffer, u32, u32)> {
+        let size = texture.size();
+        let format = texture.format();
+        let bytes_per_pixel = format
+            .block_copy_size(None)
+            .ok_or_else(|| anyhow::anyhow!("Cannot read back format {:?}", format))?;
+        let unpadded_bytes_per_row = size.width * bytes_per_pixel;
+        let padded_bytes_per_row = unpadded_bytes_per_row
+            .div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("texture_readback"),
+            size: (padded_bytes_per_row * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+
+        Ok((
+            readback_buffer,
+            padded_bytes_per_row,
+            unpadded_bytes_per_row,
+        ))
+    }
+
+    /// Read back an intermediate texture of the effect chain as RGBA
+    ///
+    /// Texture 0 holds the output of the first effect, each effect renders to
+    /// the next one. Meant for diffing the stages when writing custom effects.
+    /// Returns tightly packed 8-bit RGBA pixels with the width and height, or
+    /// `None` if there is no texture at the index or its format can't be read.
+    pub fn debug_read_texture(
+        &self,
+        index: usize,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Option<(Vec<u8>, u32, u32)> {
+        let texture = self.texture_manager.get_texture(index)?;
+        let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("debug_readback_encoder"),
+        });
+        let data = match Self::read_texture(device, queue, encoder, &texture) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Failed to read back intermediate texture {}: {}", index, e);
+                return None;
+            }
+        };
+
+        let rgba = match texture.format() {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => data,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+                let mut data = data;
+                for pixel in data.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+                data
+            }
+            // Linear values, clamped to 0-1
+            TextureFormat::Rgba16Float => data
+                .chunks_exact(2)
+                .map(|value| {
+                    let value = half::f16::from_le_bytes([value[0], value[1]]).to_f32();
+                    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+                })
+                .collect(),
+            format => {
+                warn!("Cannot read back {:?} textures as RGBA", format);
+                return None;
+            }
+        };
+
+        let size = texture.size();
+        debug!(
+            "Read back intermediate texture {} ({}x{}, {:?})",
+            index,
+            size.width,
+            size.height,
+            texture.format()
+        );
+        Some((rgba, size.width, size.height))
+    }
+
+    /// Draw a picture-in-picture view of the current frame, without effects
+    pub fn draw_pip(
+        &self,
+        target: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+        clip: &iced::Rectangle<u32>,
+        video_id: u64,
+    ) {
+        trace!("Drawing picture-in-picture view for id={}", video_id);
+
+        if let Some(video) = self.videos.get(&video_id) {
+            self.video_pipeline.draw(target, encoder, clip, video);
+        } else {
+            warn!(
+                "Attempted to draw picture-in-picture of non-existent video: {}",
+                video_id
+            );
+        }
+    }
+
     /// Process the entire effect chain for rendering
     fn process_effect_chain(
         &self,
@@ -581,8 +1355,23 @@ impl VideoPipelineManager {
             }
         }
 
-        // For each effect in the chain
+        // The last enabled effect draws the output
+        let Some(last_index) = self.effect_manager.output_index() else {
+            warn!("No enabled effects to render");
+            return;
+        };
+
+        // For each enabled effect in the chain
         for i in 0..self.effect_manager.len() {
+            if !self.effect_manager.effects[i].enabled {
+                trace!(
+                    "Skipping disabled effect {} ({})",
+                    i,
+                    self.effect_manager.effects[i].effect.name
+                );
+                continue;
+            }
+
             let effect = &self.effect_manager.effects[i].effect;
             let bind_group = match effect.get_bind_group() {
                 Some(bg) => bg,
@@ -593,17 +1382,15 @@ impl VideoPipelineManager {
             };
 
             // Calculate input and output texture indices
-            let input_index = if i == 0 {
-                // First effect doesn't use intermediate textures as input
-                // (Input comes directly from video textures in bind_group)
-                0
-            } else {
-                // Other effects read from the output of the previous effect
-                i - 1
-            };
-
-            // Last effect writes directly to screen, others to their intermediate texture
-            let output_view = if i == self.effect_manager.len() - 1 {
+            // First effect doesn't use intermediate textures as input (input
+            // comes directly from video textures in bind_group), the others read
+            // from the output of the previous enabled effect
+            let input_index = self.effect_manager.input_index(i);
+
+            // Last effect writes directly to screen unless it has to be presented
+            // separately, others to their intermediate texture
+            let to_screen = i == last_index && self.present_pass.is_none();
+            let output_view = if to_screen {
                 target
             } else {
                 &views[i] // Effect i writes to texture i
@@ -614,7 +1401,7 @@ impl VideoPipelineManager {
                 i,
                 effect.name,
                 input_index,
-                if i == self.effect_manager.len() - 1 {
+                if to_screen {
                     "screen".to_string()
                 } else {
                     format!("texture {}", i)
@@ -630,8 +1417,13 @@ impl VideoPipelineManager {
                 }
             };
 
+            // Let multi-pass effects render their own passes first
+            self.effect_manager.effects[i]
+                .state
+                .before_render(effect, encoder);
+
             // When rendering to an intermediate texture
-            if i < self.effect_manager.len() - 1 {
+            if !to_screen {
                 // Use the intermediate texture dimensions
                 let intermediate_width = input_texture.size().width as f32;
                 let intermediate_height = input_texture.size().height as f32;
@@ -665,6 +1457,19 @@ impl VideoPipelineManager {
                     input_texture.size().height as f32,
                 );
             }
+
+            // Let stateful effects record their per-frame work
+            self.effect_manager.effects[i]
+                .state
+                .after_render(effect, encoder);
+        }
+
+        if let Some(present_pass) = &self.present_pass {
+            trace!(
+                "Presenting effect chain output to {:?} surface",
+                self.format
+            );
+            present_pass.draw(encoder, target, clip);
         }
     }
 
@@ -705,6 +1510,80 @@ impl VideoPipelineManager {
         Ok(())
     }
 
+    /// Compile a custom WGSL effect and add it to the end of the effect chain
+    ///
+    /// The shader is validated before it's added, see [`CustomEffect`] for the
+    /// bindings and entry points it needs. Uniforms are given with their initial
+    /// values in the order of the WGSL struct and can be changed like the
+    /// parameters of built-in effects.
+    pub fn add_custom_effect(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        name: &str,
+        wgsl_source: &str,
+        uniforms: Vec<(String, UniformValue)>,
+    ) -> anyhow::Result<()> {
+        if self.has_effect(name) {
+            anyhow::bail!("An effect named '{}' already exists", name);
+        }
+
+        let mut effect = CustomEffect::new(
+            CustomEffectSource {
+                name: name.to_string(),
+                wgsl_source: wgsl_source.to_string(),
+                uniforms,
+            },
+            self.intermediate_format,
+        );
+        let result = effect.add(device, queue).and_then(|shader_effect| {
+            self.add_effect(false, device, queue, shader_effect, Box::new(effect))
+        });
+
+        if result.is_err() {
+            self.failed_custom_effects.insert(name.to_string());
+        } else {
+            self.failed_custom_effects.remove(name);
+        }
+        result
+    }
+
+    /// Check if a custom effect with the given name failed to compile
+    ///
+    /// Used to avoid recompiling a broken shader on every frame.
+    pub fn custom_effect_failed(&self, name: &str) -> bool {
+        self.failed_custom_effects.contains(name)
+    }
+
+    /// Set the LUT used for color grading, or remove it with `None`
+    ///
+    /// The "lut" effect is added to the end of the effect chain, so it should be
+    /// set after the other effects. Setting the same LUT again does nothing.
+    pub fn set_lut(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        lut: Option<Arc<CubeLut>>,
+    ) -> anyhow::Result<()> {
+        let unchanged = match (&self.lut, &lut) {
+            (Some(current), Some(new)) => Arc::ptr_eq(current, new),
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return Ok(());
+        }
+
+        self.remove_effect("lut");
+        self.lut = lut.clone();
+        if let Some(lut) = lut {
+            let mut effect = LutEffect::new(lut, self.intermediate_format);
+            let shader_effect = effect.add(device, queue)?;
+            self.add_effect(false, device, queue, shader_effect, Box::new(effect))?;
+        }
+        Ok(())
+    }
+
     /// Update all existing effects with current textures
     fn update_existing_effects(
         &mut self,
@@ -724,7 +1603,7 @@ impl VideoPipelineManager {
             );
 
             // Get input texture and view
-            let input_index = if i == 0 { 0 } else { i - 1 };
+            let input_index = self.effect_manager.input_index(i);
             trace!("Using input index: {}", input_index);
 
             // For the YUV to RGB effect (first effect), we need to handle video textures
@@ -734,11 +1613,12 @@ impl VideoPipelineManager {
 
                 let y_view = video.texture_y.create_view(&Default::default());
                 let uv_view = video.texture_uv.create_view(&Default::default());
+                let a_view = video.texture_a.create_view(&Default::default());
 
                 // References to the actual textures
                 (
-                    vec![y_view, uv_view],
-                    vec![&video.texture_y, &video.texture_uv],
+                    vec![y_view, uv_view, a_view],
+                    vec![&video.texture_y, &video.texture_uv, &video.texture_a],
                 )
             } else {
                 // Handle intermediate textures (stored as Arc)
@@ -774,12 +1654,7 @@ impl VideoPipelineManager {
             );
 
             let effect_entry = &mut self.effect_manager.effects[i];
-            match effect_entry.state.update_for_frame(
-                device,
-                &mut effect_entry.effect,
-                &texture_views,
-                &textures,
-            ) {
+            match effect_entry.update_for_frame(device, &texture_views, &textures) {
                 Ok(_) => trace!("Updated effect bind group successfully"),
                 Err(e) => {
                     error!("Failed to update effect bind group: {}", e);
@@ -833,13 +1708,15 @@ impl VideoPipelineManager {
         };
 
         // Check if format conversion is needed between effects
-        let required_format = shader_effect.get_format().to_owned();
+        let required_format = shader_effect_type
+            .required_input_format()
+            .unwrap_or(self.intermediate_format);
         debug!(
             "Effect requires format: {:?}, input format is: {:?}",
             required_format, input_format
         );
 
-        if input_format != required_format {
+        let converted = if input_format != required_format {
             debug!(
                 "Format conversion needed: {:?} -> {:?}",
                 input_format, required_format
@@ -854,11 +1731,11 @@ impl VideoPipelineManager {
                 required_format,
                 texture_size,
                 effect_count,
-            )?;
-            debug!("Format conversion effect added successfully");
+            )?
         } else {
             debug!("No format conversion needed");
-        }
+            false
+        };
 
         // Resize textures for the new effect
         debug!("Resizing intermediate textures for new effect");
@@ -869,7 +1746,7 @@ impl VideoPipelineManager {
         );
 
         // Get the input for the current effect (which might be after conversion)
-        let input_index = if input_format != required_format {
+        let input_index = if converted {
             // If we added a conversion effect, use its output
             debug!("Using conversion effect output at index {}", effect_count);
             effect_count
@@ -954,6 +1831,7 @@ impl VideoPipelineManager {
         let format = video.texture_y.format();
         let y_view = video.texture_y.create_view(&Default::default());
         let uv_view = video.texture_uv.create_view(&Default::default());
+        let a_view = video.texture_a.create_view(&Default::default());
 
         trace!(
             "Retrieved video textures: format={:?}, size={}x{}",
@@ -962,7 +1840,7 @@ impl VideoPipelineManager {
             size.height
         );
 
-        Ok((vec![y_view, uv_view], format, size))
+        Ok((vec![y_view, uv_view, a_view], format, size))
     }
 
     /// Get texture from a previous effect's output
@@ -1003,6 +1881,8 @@ impl VideoPipelineManager {
     }
 
     /// Add a format conversion effect between incompatible effect formats
+    ///
+    /// Returns whether a conversion pass was added in front of the effect.
     fn add_format_conversion(
         &mut self,
         device: &wgpu::Device,
@@ -1012,7 +1892,7 @@ impl VideoPipelineManager {
         required_format: TextureFormat,
         texture_size: wgpu::Extent3d,
         output_index: usize,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<bool> {
         info!(
             "Adding format conversion: {:?} → {:?}",
             input_format, required_format
@@ -1026,14 +1906,15 @@ impl VideoPipelineManager {
         );
 
         // Handle supported format conversions
-        match (input_format, required_format) {
+        match FormatConversion::find(input_format, required_format, self.intermediate_format) {
             // YUV to RGB conversion (common for video processing)
-            (wgpu::TextureFormat::R8Unorm, wgpu::TextureFormat::Bgra8UnormSrgb) => {
+            Some(FormatConversion::YuvToRgb) => {
                 debug!("Creating YUV to RGB converter");
 
                 // Create the YUV to RGB effect
-                let mut yuv_effect = YuvToRgbEffect::new(0, wgpu::TextureFormat::Bgra8UnormSrgb);
-                let mut yuv_shader = yuv_effect.add(device, queue);
+                let mut yuv_effect = YuvToRgbEffect::new(0, self.intermediate_format);
+                yuv_effect.sample_scale = video::sample_scale(input_format);
+                let mut yuv_shader = yuv_effect.add(device, queue)?;
                 debug!(
                     "Created YUV shader effect with bind group layout ID: {:?}",
                     yuv_shader.bind_group_layout.global_id()
@@ -1091,20 +1972,116 @@ impl VideoPipelineManager {
                     .add_effect(yuv_shader, Box::new(yuv_effect));
 
                 info!("YUV to RGB conversion effect added successfully");
-                Ok(())
+                Ok(true)
+            }
+            // The effect samples the input as-is
+            Some(FormatConversion::Direct) => {
+                debug!(
+                    "Sampling {:?} input directly as {:?}",
+                    input_format, required_format
+                );
+                Ok(false)
             }
             // Add other format conversions here as needed
-            _ => {
+            None => {
                 error!(
                     "Unsupported format conversion: {:?} to {:?}",
                     input_format, required_format
                 );
                 Err(anyhow::anyhow!(
-                    "Unsupported format conversion: {:?} to {:?}",
+                    "Unsupported format conversion: {:?} to {:?} (supported: {})",
                     input_format,
-                    required_format
+                    required_format,
+                    SUPPORTED_CONVERSIONS
                 ))
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_hashes_are_fnv_1a() {
+        // Reference values of the FNV-1a 64 bit test suite
+        assert_eq!(hash_bytes(FNV_OFFSET_BASIS, b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash_bytes(FNV_OFFSET_BASIS, b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(
+            hash_bytes(FNV_OFFSET_BASIS, b"foobar"),
+            0x8594_4171_f739_67e8
+        );
+        // Hashing in parts continues the same hash
+        assert_eq!(
+            hash_bytes(hash_bytes(FNV_OFFSET_BASIS, b"foo"), b"bar"),
+            hash_bytes(FNV_OFFSET_BASIS, b"foobar")
+        );
+    }
+
+    #[test]
+    fn readback_padding_is_not_hashed() {
+        // A 3x2 RGBA frame, rows padded to 256 bytes by the copy
+        let pixels: Vec<u8> = (0..24).collect();
+        let mut padded = vec![0xff; 512];
+        padded[..12].copy_from_slice(&pixels[..12]);
+        padded[256..268].copy_from_slice(&pixels[12..]);
+
+        let data = unpad_rows(&padded, 256, 12);
+        assert_eq!(data, pixels);
+        assert_eq!(
+            hash_bytes(FNV_OFFSET_BASIS, &data),
+            hash_bytes(FNV_OFFSET_BASIS, &pixels)
+        );
+    }
+
+    #[test]
+    fn videos_are_released_once_their_owner_is_dropped() {
+        let mut owners = VideoOwners::default();
+        let first = Arc::new(VideoOwner);
+        let second = Arc::new(VideoOwner);
+        owners.track(0, &Arc::downgrade(&first));
+        owners.track(1, &Arc::downgrade(&second));
+        // Tracking again on the next frame changes nothing
+        owners.track(0, &Arc::downgrade(&first));
+        assert!(owners.take_dropped().is_empty());
+        assert_eq!(owners.len(), 2);
+
+        drop(second);
+        assert_eq!(owners.take_dropped(), vec![1]);
+        assert_eq!(owners.len(), 1);
+        assert!(owners.take_dropped().is_empty());
+    }
+
+    #[test]
+    fn effect_inputs_are_converted_to_their_required_format() {
+        let intermediate = TextureFormat::Bgra8UnormSrgb;
+        let find = |input, required| FormatConversion::find(input, required, intermediate);
+
+        for plane_format in [
+            TextureFormat::R8Unorm,
+            TextureFormat::R16Unorm,
+            TextureFormat::R16Float,
+        ] {
+            assert_eq!(
+                find(plane_format, intermediate),
+                Some(FormatConversion::YuvToRgb)
+            );
+        }
+        assert_eq!(
+            find(TextureFormat::Rgba16Float, TextureFormat::Bgra8UnormSrgb),
+            Some(FormatConversion::Direct)
+        );
+        assert_eq!(
+            find(TextureFormat::Bgra8UnormSrgb, TextureFormat::Rgba16Float),
+            Some(FormatConversion::Direct)
+        );
+
+        // Planes only convert to the intermediate format, depth never converts
+        assert_eq!(
+            find(TextureFormat::R8Unorm, TextureFormat::Rgba16Float),
+            None
+        );
+        assert_eq!(find(TextureFormat::Depth32Float, intermediate), None);
+    }
+}
@@ -3,15 +3,46 @@ use std::collections::HashMap;
 
 pub mod effects;
 pub mod manager;
-pub mod render;
+pub mod mipmap;
+pub mod present;
 pub mod state;
 pub mod video;
 
+#[derive(Clone, Debug)]
 pub struct PipelineConfig {
     pub format: wgpu::TextureFormat,
     pub sample_count: u32,
     pub blend_state: Option<wgpu::BlendState>,
     pub primitive_state: wgpu::PrimitiveState,
+    /// Create the video textures with a full mip chain, regenerated on every upload
+    ///
+    /// Improves quality when the video is displayed much smaller than its native
+    /// size, at the cost of about a third more texture memory and a render pass
+    /// per mip level for every frame.
+    pub mipmaps: bool,
+    /// Maximum anisotropy of the video sampler (1 disables anisotropic filtering)
+    ///
+    /// Values above 1 make the sampler use linear mip filtering and take more
+    /// texture bandwidth per sample.
+    pub anisotropy_clamp: u16,
+    /// Size of the intermediate textures the effect chain renders into
+    ///
+    /// Expensive effects can run below the display resolution on high-DPI
+    /// screens, the result is stretched to the widget by the last pass.
+    pub render_size: RenderSize,
+    /// Render the effect chain into `Rgba16Float` textures instead of 8-bit ones
+    ///
+    /// Reduces banding when effects are stacked, e.g. tone mapping followed by
+    /// blends, at twice the memory and bandwidth of the intermediate textures.
+    /// A final pass converts the result to the surface format.
+    pub high_precision_effects: bool,
+    /// How the video is filtered when scaled, can be changed after creation
+    pub sampling: SamplingMode,
+    /// Draw the decoded video without any effects, can be changed after creation
+    ///
+    /// Effects stay registered but are neither prepared nor rendered, e.g. to
+    /// measure their cost against plain playback.
+    pub bypass_effects: bool,
 }
 impl Default for PipelineConfig {
     fn default() -> Self {
@@ -20,6 +51,80 @@ impl Default for PipelineConfig {
             sample_count: 1,
             blend_state: None,
             primitive_state: wgpu::PrimitiveState::default(),
+            mipmaps: false,
+            anisotropy_clamp: 1,
+            render_size: RenderSize::default(),
+            high_precision_effects: false,
+            sampling: SamplingMode::default(),
+            bypass_effects: false,
+        }
+    }
+}
+
+/// Filtering of the video when it is scaled to the widget
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SamplingMode {
+    /// Interpolate between pixels, for smooth playback
+    #[default]
+    Linear,
+    /// Show each pixel as a sharp square, e.g. to inspect pixel art or upscalers
+    Nearest,
+}
+
+impl SamplingMode {
+    /// Filter mode of the samplers for this mode
+    pub fn filter_mode(self) -> wgpu::FilterMode {
+        match self {
+            SamplingMode::Linear => wgpu::FilterMode::Linear,
+            SamplingMode::Nearest => wgpu::FilterMode::Nearest,
+        }
+    }
+}
+
+/// How the effect chain intermediate textures are sized
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderSize {
+    /// Physical viewport size multiplied by a scale factor (e.g. 0.5 for half)
+    Viewport(f32),
+    /// Native size of the video, independent of the widget size
+    Source,
+}
+
+impl Default for RenderSize {
+    fn default() -> Self {
+        RenderSize::Viewport(1.0)
+    }
+}
+
+impl RenderSize {
+    /// Intermediate texture size for the given viewport and video sizes
+    ///
+    /// The result is at least 1x1 and at most `max_dimension` on each side.
+    pub fn resolve(
+        self,
+        viewport: (u32, u32),
+        source: (u32, u32),
+        max_dimension: u32,
+    ) -> wgpu::Extent3d {
+        let (width, height) = match self {
+            RenderSize::Viewport(scale) => {
+                let scale = if scale.is_finite() && scale > 0.0 {
+                    scale
+                } else {
+                    1.0
+                };
+                (
+                    (viewport.0 as f32 * scale).round() as u32,
+                    (viewport.1 as f32 * scale).round() as u32,
+                )
+            }
+            RenderSize::Source => source,
+        };
+
+        wgpu::Extent3d {
+            width: width.clamp(1, max_dimension),
+            height: height.clamp(1, max_dimension),
+            depth_or_array_layers: 1,
         }
     }
 }
@@ -0,0 +1,205 @@
+use iced_wgpu::wgpu;
+use tracing::{debug, trace};
+
+use super::SamplingMode;
+
+/// Format of the intermediate textures for a surface format
+///
+/// Effects read and write linear `Rgba8Unorm` textures when the surface is
+/// sRGB, so values aren't encoded and decoded again between every effect.
+/// Other surface formats are used directly, unless `high_precision` asks for
+/// `Rgba16Float` textures on any surface.
+pub fn intermediate_format(
+    surface_format: wgpu::TextureFormat,
+    high_precision: bool,
+) -> wgpu::TextureFormat {
+    if high_precision {
+        wgpu::TextureFormat::Rgba16Float
+    } else if surface_format.is_srgb() {
+        wgpu::TextureFormat::Rgba8Unorm
+    } else {
+        surface_format
+    }
+}
+
+/// Draws the output of the effect chain onto a surface of a different format
+pub struct PresentPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+impl PresentPass {
+    /// Create a present pass targeting the given surface format
+    pub fn new(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        sampling: SamplingMode,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("present_shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                include_str!("../../../../../assets/shaders/present.wgsl").into(),
+            ),
+        });
+
+        // Create bind group layout with:
+        // 1. Output of the last effect (binding 0)
+        // 2. Sampler (binding 1)
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("present_bind_group_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("present_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // Blend the same way as effects drawing directly to the surface
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("present_pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        let sampler = Self::create_sampler(device, sampling);
+
+        debug!(
+            "Created present pass for surface format {:?}",
+            surface_format
+        );
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            bind_group: None,
+        }
+    }
+
+    fn create_sampler(device: &wgpu::Device, sampling: SamplingMode) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("present_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: sampling.filter_mode(),
+            min_filter: sampling.filter_mode(),
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        })
+    }
+
+    /// Switch the filtering of the source, used from the next `set_source`
+    pub fn set_sampling(&mut self, device: &wgpu::Device, sampling: SamplingMode) {
+        self.sampler = Self::create_sampler(device, sampling);
+    }
+
+    /// Bind the texture to present on the next draw
+    pub fn set_source(&mut self, device: &wgpu::Device, source: &wgpu::Texture) {
+        let view = source.create_view(&Default::default());
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("present_bind_group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        }));
+    }
+
+    /// Record a pass drawing the source texture into the clip area of the target
+    pub fn draw(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        clip: &iced::Rectangle<u32>,
+    ) {
+        let Some(bind_group) = &self.bind_group else {
+            trace!("No source texture for present pass, skipping");
+            return;
+        };
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("present render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        pass.set_viewport(
+            clip.x as f32,
+            clip.y as f32,
+            clip.width as f32,
+            clip.height as f32,
+            0.0,
+            1.0,
+        );
+        pass.set_scissor_rect(clip.x, clip.y, clip.width, clip.height);
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.draw(0..6, 0..1);
+    }
+}
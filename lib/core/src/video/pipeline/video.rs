@@ -1,14 +1,24 @@
 use iced_wgpu::wgpu;
 use std::{
+    borrow::Cow,
     collections::{btree_map::Entry, BTreeMap},
     num::NonZero,
     sync::atomic::Ordering,
 };
 use tracing::{debug, info, trace, warn};
 
-use crate::video::{color_space::BT709_CONFIG, render_passes::RenderPasses};
+use crate::video::{
+    color_space::{ColorRange, ColorSpace, BT709_CONFIG},
+    render_passes::RenderPasses,
+    stream::chroma_size,
+};
 
-use super::{manager::VideoEntry, state::PipelineState, PipelineConfig};
+use super::{
+    manager::VideoEntry,
+    mipmap::{mip_level_count, MipmapGenerator},
+    state::PipelineState,
+    PipelineConfig, SamplingMode,
+};
 
 /// Uniform buffer for video pipeline shader
 /// This structure provides all necessary parameters for video rendering
@@ -18,24 +28,137 @@ use super::{manager::VideoEntry, state::PipelineState, PipelineConfig};
 pub struct Uniforms {
     pub rect: [f32; 4],        // Rectangle dimensions [x, y, width, height]
     pub color_space: [u32; 1], // Color space identifier
+    pub _pad0: [u32; 3],       // Aligns region to 16 bytes
+    pub region: [f32; 4],      // Normalized source region to sample [x, y, width, height]
     pub y_range: [f32; 2],     // min, max for Y
     pub uv_range: [f32; 2],    // min, max for UV
+    pub sample_scale: f32,     // Scales sampled values to 8-bit levels, see `sample_scale`
     pub matrix: [[f32; 3]; 3], // Color conversion matrix
-    pub _pad: [u8; 188],       // Padding to maintain alignment
+    pub _pad: [u8; 156],       // Padding to maintain alignment
+}
+
+/// Clamp a normalized region so that it lies within the video frame
+///
+/// Width and height are kept at 1% of the frame or more.
+pub fn clamp_region(region: iced::Rectangle) -> iced::Rectangle {
+    let x = region.x.clamp(0.0, 0.99);
+    let y = region.y.clamp(0.0, 0.99);
+    iced::Rectangle {
+        x,
+        y,
+        width: region.width.clamp(0.01, 1.0 - x),
+        height: region.height.clamp(0.01, 1.0 - y),
+    }
+}
+
+/// Region covering the whole video frame
+pub const FULL_REGION: iced::Rectangle = iced::Rectangle {
+    x: 0.0,
+    y: 0.0,
+    width: 1.0,
+    height: 1.0,
+};
+
+/// Texture formats of the Y and interleaved UV planes of a video
+///
+/// 16-bit samples use `R16Unorm` if the device supports it, and `R16Float`
+/// otherwise, as the normalized formats are an optional feature.
+pub fn plane_formats(
+    device: &wgpu::Device,
+    high_bit_depth: bool,
+) -> (wgpu::TextureFormat, wgpu::TextureFormat) {
+    if !high_bit_depth {
+        (wgpu::TextureFormat::R8Unorm, wgpu::TextureFormat::Rg8Unorm)
+    } else if device
+        .features()
+        .contains(wgpu::Features::TEXTURE_FORMAT_16BIT_NORM)
+    {
+        (
+            wgpu::TextureFormat::R16Unorm,
+            wgpu::TextureFormat::Rg16Unorm,
+        )
+    } else {
+        (
+            wgpu::TextureFormat::R16Float,
+            wgpu::TextureFormat::Rg16Float,
+        )
+    }
+}
+
+/// Check if a format holds the samples of a video plane
+pub fn is_plane_format(format: wgpu::TextureFormat) -> bool {
+    matches!(
+        format,
+        wgpu::TextureFormat::R8Unorm
+            | wgpu::TextureFormat::R16Unorm
+            | wgpu::TextureFormat::R16Float
+    )
+}
+
+/// Scale from values sampled from a plane of the format to 8-bit levels
+///
+/// The color space ranges and the chroma midpoint are 8-bit levels over 255.
+/// 16-bit samples have 8-bit level n at n * 256 over 65535, slightly below.
+pub fn sample_scale(format: wgpu::TextureFormat) -> f32 {
+    match format {
+        wgpu::TextureFormat::R8Unorm => 1.0,
+        _ => 65535.0 / 65280.0,
+    }
+}
+
+/// Plane data in the layout of the plane texture format
+///
+/// 16-bit samples are converted to half floats for the `R16Float` fallback.
+fn plane_data(data: &[u8], format: wgpu::TextureFormat) -> Cow<'_, [u8]> {
+    match format {
+        wgpu::TextureFormat::R16Float | wgpu::TextureFormat::Rg16Float => Cow::Owned(
+            data.chunks_exact(2)
+                .flat_map(|sample| {
+                    let value = u16::from_le_bytes([sample[0], sample[1]]);
+                    half::f16::from_f32(value as f32 / 65535.0).to_le_bytes()
+                })
+                .collect(),
+        ),
+        _ => Cow::Borrowed(data),
+    }
+}
+
+/// Number of uniform slots in each video's ring buffer
+///
+/// This is the maximum number of prepares that can be queued between draws;
+/// further prepares are dropped until a draw consumes a slot.
+pub const UNIFORM_RING_SIZE: usize = 256;
+
+/// Slot of the ring buffer for the next prepare, `None` while every slot waits for a draw
+///
+/// The indices count prepares and draws and wrap around at `usize::MAX`.
+fn uniform_slot(prepare_index: usize, render_index: usize) -> Option<usize> {
+    let pending = prepare_index.wrapping_sub(render_index);
+    (pending < UNIFORM_RING_SIZE).then_some(prepare_index % UNIFORM_RING_SIZE)
 }
 
 /// Main pipeline for video rendering
-/// Handles YUV textures and performs color space conversion
+/// Handles YUV(A) textures and performs color space conversion
 pub struct VideoPipeline {
     pipeline: wgpu::RenderPipeline,
     bg0_layout: wgpu::BindGroupLayout,
     config: PipelineConfig,
     sampler: wgpu::Sampler,
+    mipmap_generator: Option<MipmapGenerator>, // Only created when mipmaps are enabled
 }
 
 impl VideoPipeline {
     /// Create a new video pipeline with the given texture format
     pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        Self::with_config(device, format, PipelineConfig::default())
+    }
+
+    /// Create a new video pipeline with the given texture format and filtering options
+    pub fn with_config(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        config: PipelineConfig,
+    ) -> Self {
         // Load shader from embedded assets
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("video_shader"),
@@ -49,6 +172,7 @@ impl VideoPipeline {
         // 2. UV texture plane (binding 1)
         // 3. Texture sampler (binding 2)
         // 4. Uniforms buffer (binding 3)
+        // 5. Alpha texture plane (binding 4)
         let bg0_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("video_bind_group_layout"),
             entries: &[
@@ -88,6 +212,16 @@ impl VideoPipeline {
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -117,37 +251,160 @@ impl VideoPipeline {
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
                 entry_point: "fs_main",
+                // Composite videos with alpha over the container background,
+                // blending the same way as effects drawing to the surface
                 targets: &[Some(wgpu::ColorTargetState {
                     format,
-                    blend: None,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
             multiview: None,
         });
 
-        // Create texture sampler
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        let sampler = Self::create_sampler(device, &config);
+
+        let mipmap_generator = config.mipmaps.then(|| {
+            let (luma_16, chroma_16) = plane_formats(device, true);
+            MipmapGenerator::new(
+                device,
+                &[
+                    wgpu::TextureFormat::R8Unorm,
+                    wgpu::TextureFormat::Rg8Unorm,
+                    luma_16,
+                    chroma_16,
+                ],
+            )
+        });
+
+        debug!(
+            "Created video pipeline: mipmaps={}, anisotropy_clamp={}, sampling={:?}",
+            config.mipmaps, config.anisotropy_clamp, config.sampling
+        );
+
+        Self {
+            pipeline,
+            bg0_layout,
+            config,
+            sampler,
+            mipmap_generator,
+        }
+    }
+
+    /// Create the sampler of the video textures for the filtering options
+    fn create_sampler(device: &wgpu::Device, config: &PipelineConfig) -> wgpu::Sampler {
+        // Anisotropic filtering requires linear filtering, so it's off for nearest sampling
+        let filter = config.sampling.filter_mode();
+        let anisotropy_clamp = match config.sampling {
+            SamplingMode::Linear => config.anisotropy_clamp.clamp(1, 16),
+            SamplingMode::Nearest => 1,
+        };
+        let mipmap_filter = if config.mipmaps || anisotropy_clamp > 1 {
+            wgpu::FilterMode::Linear
+        } else {
+            wgpu::FilterMode::Nearest
+        };
+
+        device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("video_sampler"),
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter,
             lod_min_clamp: 0.0,
-            lod_max_clamp: 1.0,
+            lod_max_clamp: if config.mipmaps { 32.0 } else { 1.0 },
             compare: None,
-            anisotropy_clamp: 1,
+            anisotropy_clamp,
             border_color: None,
-        });
+        })
+    }
 
-        Self {
-            pipeline,
-            bg0_layout,
-            config: PipelineConfig::default(),
-            sampler,
+    /// Get the current filtering of the video
+    pub fn sampling(&self) -> SamplingMode {
+        self.config.sampling
+    }
+
+    /// Switch the filtering of the video, rebinding the sampler of existing videos
+    pub fn set_sampling(
+        &mut self,
+        device: &wgpu::Device,
+        sampling: SamplingMode,
+        videos: &mut BTreeMap<u64, VideoEntry>,
+    ) {
+        if self.config.sampling == sampling {
+            return;
         }
+        debug!("Switching video sampling to {:?}", sampling);
+        self.config.sampling = sampling;
+        self.sampler = Self::create_sampler(device, &self.config);
+
+        for video in videos.values_mut() {
+            video.bg0 = self.create_bind_group(
+                device,
+                &video.texture_y,
+                &video.texture_uv,
+                &video.texture_a,
+                &video.instances,
+            );
+        }
+    }
+
+    /// Bind the planes of a video, the sampler and its uniform buffer
+    fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        texture_y: &wgpu::Texture,
+        texture_uv: &wgpu::Texture,
+        texture_a: &wgpu::Texture,
+        instances: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        let view_y = texture_y.create_view(&Default::default());
+        let view_uv = texture_uv.create_view(&Default::default());
+        let view_a = texture_a.create_view(&Default::default());
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("video_bind_group"),
+            layout: &self.bg0_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view_y),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view_uv),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: instances,
+                        offset: 0,
+                        size: Some(NonZero::new(std::mem::size_of::<Uniforms>() as _).unwrap()),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&view_a),
+                },
+            ],
+        })
     }
 
     /// Draw video frame to the target texture with existing content
@@ -195,7 +452,9 @@ impl VideoPipeline {
 
     /// Upload video frame data to GPU textures
     ///
-    /// Creates new video entry if needed and uploads Y and UV plane data
+    /// Creates new video entry if needed and uploads Y and UV plane data, and
+    /// the alpha plane for frames that carry one after the UV plane. Frames
+    /// twice the size of 8-bit ones carry 16-bit samples, e.g. for 10-bit HDR.
     pub fn upload(
         &mut self,
         device: &wgpu::Device,
@@ -206,12 +465,37 @@ impl VideoPipeline {
         frame: &[u8],
         videos: &mut BTreeMap<u64, VideoEntry>,
     ) {
+        let (uv_width, uv_height) = chroma_size(width, height);
+        let y_samples = (width * height) as usize;
+        let uv_samples = (uv_width * uv_height * 2) as usize;
+        // 16-bit frames are larger than 8-bit ones with alpha
+        let high_bit_depth = frame.len() >= (y_samples + uv_samples) * 2;
+        let sample_size = if high_bit_depth { 2 } else { 1 };
+        let y_size = y_samples * sample_size;
+        let uv_size = uv_samples * sample_size;
+        let has_alpha = frame.len() >= y_size + uv_size + y_size;
+        let (luma_format, chroma_format) = plane_formats(device, high_bit_depth);
+
         // Calculate uniform buffer alignment requirements
         let uniform_alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
         let uniform_size = std::mem::size_of::<Uniforms>();
         let aligned_uniform_size =
             (uniform_size + uniform_alignment - 1) & !(uniform_alignment - 1);
 
+        // Textures are rendered into when generating their mip levels
+        let mut texture_usage =
+            wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING;
+        if self.config.mipmaps {
+            texture_usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
+        let mip_levels = |width, height| {
+            if self.config.mipmaps {
+                mip_level_count(width, height)
+            } else {
+                1
+            }
+        };
+
         // Create new video entry if needed
         if let Entry::Vacant(entry) = videos.entry(video_id) {
             debug!(
@@ -227,11 +511,11 @@ impl VideoPipeline {
                     height,
                     depth_or_array_layers: 1,
                 },
-                mip_level_count: 1,
+                mip_level_count: mip_levels(width, height),
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::R8Unorm,
-                usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+                format: luma_format,
+                usage: texture_usage,
                 view_formats: &[],
             });
 
@@ -239,61 +523,70 @@ impl VideoPipeline {
             let texture_uv = device.create_texture(&wgpu::TextureDescriptor {
                 label: Some("video_texture_uv"),
                 size: wgpu::Extent3d {
-                    width: width / 2,
-                    height: height / 2,
+                    width: uv_width,
+                    height: uv_height,
                     depth_or_array_layers: 1,
                 },
-                mip_level_count: 1,
+                mip_level_count: mip_levels(uv_width, uv_height),
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rg8Unorm,
-                usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+                format: chroma_format,
+                usage: texture_usage,
                 view_formats: &[],
             });
 
-            let view_y = texture_y.create_view(&Default::default());
-            let view_uv = texture_uv.create_view(&Default::default());
+            // Create alpha plane texture (full resolution), a single opaque
+            // texel for videos without alpha
+            let (a_width, a_height) = if has_alpha { (width, height) } else { (1, 1) };
+            let texture_a = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("video_texture_a"),
+                size: wgpu::Extent3d {
+                    width: a_width,
+                    height: a_height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: mip_levels(a_width, a_height),
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: if has_alpha {
+                    luma_format
+                } else {
+                    wgpu::TextureFormat::R8Unorm
+                },
+                usage: texture_usage,
+                view_formats: &[],
+            });
+            if !has_alpha {
+                queue.write_texture(
+                    texture_a.as_image_copy(),
+                    &[u8::MAX],
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(1),
+                        rows_per_image: Some(1),
+                    },
+                    texture_a.size(),
+                );
+            }
 
             // Create uniform buffer with space for multiple frames
             let instances = device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some("video_uniform_buffer"),
-                size: (256 * aligned_uniform_size) as u64,
+                size: (UNIFORM_RING_SIZE * aligned_uniform_size) as u64,
                 usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
                 mapped_at_creation: false,
             });
 
             // Create bind group connecting textures, sampler and uniforms
-            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("video_bind_group"),
-                layout: &self.bg0_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&view_y),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&view_uv),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: wgpu::BindingResource::Sampler(&self.sampler),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 3,
-                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                            buffer: &instances,
-                            offset: 0,
-                            size: Some(NonZero::new(std::mem::size_of::<Uniforms>() as _).unwrap()),
-                        }),
-                    },
-                ],
-            });
+            let bind_group =
+                self.create_bind_group(device, &texture_y, &texture_uv, &texture_a, &instances);
 
             // Insert new video entry
             entry.insert(VideoEntry {
                 texture_y,
                 texture_uv,
+                texture_a,
+                has_alpha,
                 instances,
                 bg0: bind_group,
                 alive,
@@ -313,10 +606,10 @@ impl VideoPipeline {
                     origin: wgpu::Origin3d::ZERO,
                     aspect: wgpu::TextureAspect::All,
                 },
-                &frame[..(width * height) as usize],
+                &plane_data(&frame[..y_size], luma_format),
                 wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some(width),
+                    bytes_per_row: Some(width * sample_size as u32),
                     rows_per_image: Some(height),
                 },
                 wgpu::Extent3d {
@@ -327,7 +620,7 @@ impl VideoPipeline {
             );
 
             // Upload UV plane data
-            let uv_data = &frame[(width * height) as usize..];
+            let uv_data = plane_data(&frame[y_size..y_size + uv_size], chroma_format);
             queue.write_texture(
                 wgpu::ImageCopyTexture {
                     texture: &video.texture_uv,
@@ -335,45 +628,97 @@ impl VideoPipeline {
                     origin: wgpu::Origin3d::ZERO,
                     aspect: wgpu::TextureAspect::All,
                 },
-                uv_data,
+                &uv_data,
                 wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some(width / 2 * 2),
-                    rows_per_image: Some(height / 2),
+                    bytes_per_row: Some(uv_width * 2 * sample_size as u32), // Interleaved U and V samples
+                    rows_per_image: Some(uv_height),
                 },
                 wgpu::Extent3d {
-                    width: width / 2,
-                    height: height / 2,
+                    width: uv_width,
+                    height: uv_height,
                     depth_or_array_layers: 1,
                 },
             );
 
+            // Upload alpha plane data if the video has alpha
+            if video.has_alpha && has_alpha {
+                queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        texture: &video.texture_a,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d::ZERO,
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    &plane_data(&frame[y_size + uv_size..y_size * 2 + uv_size], luma_format),
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(width * sample_size as u32),
+                        rows_per_image: Some(height),
+                    },
+                    wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+
+            // Regenerate the lower mip levels from the new frame
+            if let Some(mipmap_generator) = &self.mipmap_generator {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("video_mipmap_encoder"),
+                });
+                mipmap_generator.generate(device, &mut encoder, &video.texture_y);
+                mipmap_generator.generate(device, &mut encoder, &video.texture_uv);
+                if video.has_alpha {
+                    mipmap_generator.generate(device, &mut encoder, &video.texture_a);
+                }
+                queue.submit(Some(encoder.finish()));
+            }
+
             trace!(
-                "Uploaded frame data for video {}: Y size={}x{}, UV size={}x{}",
+                "Uploaded frame data for video {}: Y size={}x{}, UV size={}x{}, alpha={}",
                 video_id,
                 width,
                 height,
-                width / 2,
-                height / 2
+                uv_width,
+                uv_height,
+                video.has_alpha
             );
         }
     }
 
     /// Prepare video for rendering by updating uniform buffer
     ///
-    /// Sets up color space conversion parameters and frame dimensions
+    /// Sets up color space conversion parameters, frame dimensions and the
+    /// normalized region of the frame to sample
     pub fn prepare(
         &self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         video_id: u64,
         bounds: &iced::Rectangle,
-        color_space: ffmpeg_next::color::Space,
+        color_space: ColorSpace,
+        color_range: ColorRange,
+        region: &iced::Rectangle,
         videos: &mut BTreeMap<u64, VideoEntry>,
         state: &PipelineState,
     ) {
         if let Some(video) = videos.get_mut(&video_id) {
             let prepare_index = video.prepare_index.load(Ordering::Relaxed);
+            let render_index = video.render_index.load(Ordering::Relaxed);
+
+            // Don't overwrite slots that were prepared but haven't been drawn yet
+            let Some(slot) = uniform_slot(prepare_index, render_index) else {
+                warn!(
+                    "Uniform ring full for video {}: {} prepares pending without a draw, skipping",
+                    video_id,
+                    prepare_index.wrapping_sub(render_index)
+                );
+                return;
+            };
+
             trace!(
                 "Preparing video {}: frame={}, bounds={:?}",
                 video_id,
@@ -382,40 +727,72 @@ impl VideoPipeline {
             );
 
             // Get color space configuration (defaulting to BT.709 if not recognized)
-            let config = match color_space {
-                ffmpeg_next::color::Space::BT709 => BT709_CONFIG,
-                _ => {
-                    debug!(
-                        "Using default BT709 config for unsupported color space: {:?}",
-                        color_space
-                    );
-                    BT709_CONFIG
-                }
-            };
+            let config = color_space.config().unwrap_or_else(|| {
+                debug!(
+                    "Using default BT709 config for unsupported color space: {:?}",
+                    color_space
+                );
+                &BT709_CONFIG
+            });
+            let (y_range, uv_range) = config.ranges(color_range);
 
             // Create uniform buffer with video parameters
             let uniforms = Uniforms {
                 rect: [bounds.x, bounds.y, bounds.width, bounds.height],
                 color_space: [color_space as u32],
-                y_range: config.y_range,
-                uv_range: config.uv_range,
+                _pad0: [0; 3],
+                region: [region.x, region.y, region.width, region.height],
+                y_range,
+                uv_range,
+                sample_scale: sample_scale(video.texture_y.format()),
                 matrix: config.matrix,
-                _pad: [0; 188],
+                _pad: [0; 156],
             };
 
             // Calculate offset in uniform buffer ring and write new data
-            let offset = prepare_index * video.aligned_uniform_size;
+            let offset = slot * video.aligned_uniform_size;
             queue.write_buffer(
                 &video.instances,
                 offset as u64,
                 bytemuck::cast_slice(&[uniforms]),
             );
 
-            // Update prepare index for next frame (wrapping at 256)
-            let next_index = (prepare_index + 1) % 256;
-            video.prepare_index.store(next_index, Ordering::Relaxed);
+            // Advance prepare index for next frame (the slot wraps at UNIFORM_RING_SIZE)
+            video
+                .prepare_index
+                .store(prepare_index.wrapping_add(1), Ordering::Relaxed);
         } else {
             warn!("Attempted to prepare non-existent video: {}", video_id);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniform_ring_stops_before_overwriting_pending_slots() {
+        // Prepare without drawing, the 257th prepare would overwrite the first slot
+        let render_index = 0;
+        for prepare_index in 0..UNIFORM_RING_SIZE {
+            assert_eq!(
+                uniform_slot(prepare_index, render_index),
+                Some(prepare_index)
+            );
+        }
+        assert_eq!(uniform_slot(256, render_index), None);
+        assert_eq!(uniform_slot(257, render_index), None);
+
+        // A draw frees the oldest slot, the next prepare wraps around to it
+        assert_eq!(uniform_slot(256, 1), Some(0));
+        assert_eq!(uniform_slot(257, 2), Some(1));
+    }
+
+    #[test]
+    fn uniform_ring_indices_wrap_around() {
+        assert_eq!(uniform_slot(usize::MAX, usize::MAX - 1), Some(255));
+        assert_eq!(uniform_slot(0, usize::MAX), Some(0));
+        assert_eq!(uniform_slot(255, usize::MAX), None);
+    }
+}
@@ -1,20 +1,73 @@
-use ffmpeg_next::color::{self, Space};
 use iced_wgpu::primitive::Primitive;
 use iced_wgpu::wgpu;
 use std::{
     collections::HashMap,
-    sync::atomic::{AtomicUsize, Ordering},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
 };
-use tracing::{debug, info, trace, warn};
+use tracing::{debug, error, info, trace, warn};
 
+use crate::video::color_space::{ColorRange, ColorSpace, ColorTransfer};
 use crate::video::pipeline::effects::{
-    comparison::ComparisonEffect,
+    bloom::{BloomEffect, BloomEffectState},
+    blur::{BlurEffect, BlurEffectState},
+    chroma_key::{ChromaKeyEffect, GREEN_SCREEN},
+    color_balance::{ColorBalanceEffect, ColorBalanceEffectState},
+    comparison::{ComparisonEffect, ComparisonOrientation, DEFAULT_LINE_COLOR, DEFAULT_LINE_WIDTH},
+    custom::CustomEffectSource,
+    lut::CubeLut,
+    posterize::{PosterizeEffect, PosterizeEffectState},
+    sharpen::{SharpenEffect, SharpenEffectState},
+    simple_color::{SimpleColorEffect, SimpleColorEffectState, SimpleColorMode},
+    stereo::{StereoEffect, StereoEffectState, StereoLayout},
+    temporal_denoise::{TemporalDenoiseEffect, TemporalDenoiseEffectState},
+    tone_map::{HdrTransfer, ToneMapEffect},
     upscale::{UpscaleEffect, UpscaleEffectState},
     yuv_to_rgb::YuvToRgbEffect,
-    Effect,
+    Effect, EffectParameters,
 };
+use crate::video::shader::{self, UniformValue};
 
-use super::pipeline::manager::VideoPipelineManager;
+use super::pipeline::{
+    manager::{FrameReadBack, VideoOwner, VideoPipelineManager},
+    video::{clamp_region, FULL_REGION},
+    PipelineConfig,
+};
+
+/// A video drawn in one cell of a grid, see [`VideoPrimitive::with_grid`]
+#[derive(Debug, Clone)]
+pub struct GridVideo {
+    pub video_id: u64,
+    pub frame: Option<Arc<Vec<u8>>>, // New frame to upload, `None` keeps the last uploaded one
+    pub size: (u32, u32),
+    pub color_space: ColorSpace,
+    pub color_range: ColorRange,
+    pub owner: Option<Weak<VideoOwner>>, // See `VideoPrimitive::with_owner`
+}
+
+/// Split `bounds` into equal columns, one per video, and fit each video into its column
+fn grid_cells<'a>(
+    bounds: &iced::Rectangle,
+    videos: &'a [GridVideo],
+) -> impl Iterator<Item = (iced::Rectangle, &'a GridVideo)> {
+    let cell_width = bounds.width / videos.len().max(1) as f32;
+    let cell_height = bounds.height;
+    let (x, y) = (bounds.x, bounds.y);
+
+    videos.iter().enumerate().map(move |(i, video)| {
+        let (width, height) = (video.size.0.max(1) as f32, video.size.1.max(1) as f32);
+        let scale = (cell_width / width).min(cell_height / height);
+        let size = iced::Size::new(width * scale, height * scale);
+        let position = iced::Point::new(
+            x + cell_width * i as f32 + (cell_width - size.width) / 2.0,
+            y + (cell_height - size.height) / 2.0,
+        );
+        (iced::Rectangle::new(position, size), video)
+    })
+}
 
 /// A primitive for rendering video content in the iced UI framework
 ///
@@ -23,14 +76,29 @@ use super::pipeline::manager::VideoPipelineManager;
 #[derive(Debug, Clone)]
 pub struct VideoPrimitive {
     video_id: u64,
+    owner: Option<Weak<VideoOwner>>,
     alive: bool,
-    frame: Vec<u8>,
+    frame: Arc<Vec<u8>>, // Shared so cloning the primitive for each draw doesn't copy the frame
     size: (u32, u32),
     upload_frame: bool,
-    color_space: Space,
+    color_space: ColorSpace,
+    color_range: ColorRange,
+    color_transfer: ColorTransfer,
     comparison_enabled: bool,
     comparison_position: f32,
+    comparison_line_color: [f32; 4],
+    comparison_line_width: f32,
+    comparison_orientation: ComparisonOrientation,
     shader_selections: HashMap<String, bool>,
+    shader_parameters: HashMap<String, EffectParameters>,
+    custom_effects: Arc<Vec<CustomEffectSource>>,
+    lut: Option<Arc<CubeLut>>,
+    stereo_layout: Option<StereoLayout>,
+    pip_region: Option<iced::Rectangle>,
+    grid: Vec<GridVideo>,
+    crop: iced::Rectangle,
+    pipeline_config: PipelineConfig,
+    screenshot: Option<PathBuf>, // Save this frame as a PNG, see `with_screenshot`
 }
 
 impl VideoPrimitive {
@@ -38,37 +106,160 @@ impl VideoPrimitive {
     pub fn new(
         video_id: u64,
         alive: bool,
-        frame: Vec<u8>,
+        frame: Arc<Vec<u8>>,
         size: (u32, u32),
         upload_frame: bool,
-        color_space: Space,
+        color_space: ColorSpace,
     ) -> Self {
         let shader_selections = HashMap::new();
         VideoPrimitive {
             video_id,
+            owner: None,
             alive,
             frame,
             size,
             upload_frame,
             color_space,
+            color_range: ColorRange::default(),
+            color_transfer: ColorTransfer::default(),
             comparison_enabled: false,
             comparison_position: 0.5,
+            comparison_line_color: DEFAULT_LINE_COLOR,
+            comparison_line_width: DEFAULT_LINE_WIDTH,
+            comparison_orientation: ComparisonOrientation::default(),
             shader_selections,
+            shader_parameters: HashMap::new(),
+            custom_effects: Arc::default(),
+            lut: None,
+            stereo_layout: None,
+            pip_region: None,
+            grid: Vec::new(),
+            crop: FULL_REGION,
+            pipeline_config: PipelineConfig::default(),
+            screenshot: None,
         }
     }
 
+    /// Set the range of the YUV values, limited unless the video is full range
+    pub fn with_color_range(mut self, range: ColorRange) -> Self {
+        self.color_range = range;
+        self
+    }
+
+    /// Set the transfer characteristic, HDR video is tone mapped to SDR
+    pub fn with_color_transfer(mut self, transfer: ColorTransfer) -> Self {
+        self.color_transfer = transfer;
+        self
+    }
+
     /// Set which shader effects should be active
     pub fn with_shader_selections(mut self, selections: HashMap<String, bool>) -> Self {
         self.shader_selections = selections;
         self
     }
 
+    /// Set parameter values for shader effects, keyed by effect name
+    pub fn with_shader_parameters(mut self, parameters: HashMap<String, EffectParameters>) -> Self {
+        self.shader_parameters = parameters;
+        self
+    }
+
+    /// Set effects compiled from custom WGSL shaders
+    ///
+    /// They are enabled unless their name is disabled in the shader selections.
+    pub fn with_custom_effects(mut self, effects: Arc<Vec<CustomEffectSource>>) -> Self {
+        self.custom_effects = effects;
+        self
+    }
+
+    /// Grade the video with a 3D LUT at the end of the effect chain
+    ///
+    /// Its intensity is the "intensity" parameter of the "lut" effect.
+    pub fn with_lut(mut self, lut: Option<Arc<CubeLut>>) -> Self {
+        self.lut = lut;
+        self
+    }
+
+    /// Show stereoscopic video with the given layout, `None` for regular video
+    pub fn with_stereo_layout(mut self, layout: Option<StereoLayout>) -> Self {
+        self.stereo_layout = layout;
+        self
+    }
+
+    /// Draw this primitive as a picture-in-picture view of a video
+    ///
+    /// Only the given normalized region (0.0-1.0) of the frame uploaded by the
+    /// main view is drawn, without shader effects.
+    pub fn with_pip_region(mut self, region: iced::Rectangle) -> Self {
+        self.pip_region = Some(clamp_region(region));
+        self
+    }
+
+    /// Draw several videos side by side instead of this primitive's frame
+    ///
+    /// The bounds are split into equal columns and each video is fitted into
+    /// its own, e.g. to compare two source files. Each video keeps its own GPU
+    /// resources and uploads its own frames, so the streams are timed
+    /// independently. Like picture-in-picture views, the videos are drawn
+    /// without shader effects, comparing the sources as decoded.
+    pub fn with_grid(mut self, videos: Vec<GridVideo>) -> Self {
+        self.grid = videos;
+        self
+    }
+
+    /// Free the GPU resources of the video once `owner` is dropped
+    ///
+    /// Pass [`VideoStream::owner`](crate::video::stream::VideoStream::owner).
+    /// The resources are freed the next time a video is prepared by the same
+    /// pipeline. Without an owner they are kept until the pipeline is dropped.
+    pub fn with_owner(mut self, owner: Weak<VideoOwner>) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Save this frame as a PNG, with its shader effects applied
+    ///
+    /// The frame is read back at the native video size without waiting for the
+    /// GPU, and the file is written on a background thread a few frames later.
+    /// Set this on one primitive only, each prepared primitive saves a file.
+    pub fn with_screenshot(mut self, path: PathBuf) -> Self {
+        self.screenshot = Some(path);
+        self
+    }
+
+    /// Show only a normalized region (0.0-1.0) of the video, stretched to the bounds
+    pub fn with_crop(mut self, region: iced::Rectangle) -> Self {
+        self.crop = clamp_region(region);
+        self
+    }
+
+    /// Set the video pipeline options, such as mipmaps and anisotropic filtering
+    ///
+    /// The options are applied when the pipeline is first created.
+    pub fn with_pipeline_config(mut self, config: PipelineConfig) -> Self {
+        self.pipeline_config = config;
+        self
+    }
+
     /// Enable or disable comparison mode
     pub fn with_comparison(mut self, enabled: bool) -> Self {
         self.comparison_enabled = enabled;
         self
     }
 
+    /// Set the color (alpha is the opacity) and width in physical pixels of the comparison line
+    pub fn with_comparison_line(mut self, color: iced::Color, width: f32) -> Self {
+        self.comparison_line_color = [color.r, color.g, color.b, color.a];
+        self.comparison_line_width = width.max(0.0);
+        self
+    }
+
+    /// Split the comparison left/right or top/bottom
+    pub fn with_comparison_orientation(mut self, orientation: ComparisonOrientation) -> Self {
+        self.comparison_orientation = orientation;
+        self
+    }
+
     /// Set the position of the comparison slider (0.0-1.0)
     pub fn with_comparison_position(mut self, position: f32) -> Self {
         self.comparison_position = position.clamp(0.0, 1.0);
@@ -79,6 +270,55 @@ impl VideoPrimitive {
 // Global counter to track prepare calls for debugging
 static FRAME_COUNT: AtomicUsize = AtomicUsize::new(0);
 
+/// Write a read back frame to a PNG file on a background thread
+fn save_screenshot(path: PathBuf, frame: FrameReadBack) {
+    match frame {
+        Ok((width, height, rgba)) => {
+            std::thread::spawn(move || match save_png(&path, width, height, &rgba) {
+                Ok(()) => info!("Saved screenshot to {:?}", path),
+                Err(e) => error!("Failed to save screenshot to {:?}: {}", path, e),
+            });
+        }
+        Err(e) => error!("Failed to read back frame for screenshot: {}", e),
+    }
+}
+
+/// Encode tightly packed RGBA pixels as a PNG file
+fn save_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> anyhow::Result<()> {
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(rgba)?;
+    Ok(())
+}
+
+impl VideoPrimitive {
+    /// Convert the comparison line width from physical pixels to render texture pixels
+    ///
+    /// The comparison is drawn into the render texture, which is then stretched over the
+    /// bounds, so the width is scaled by how many texels cover one physical pixel across the
+    /// split. This keeps the line the same on screen regardless of the render size and crop.
+    fn comparison_line_texels(
+        &self,
+        render_size: wgpu::Extent3d,
+        bounds: &iced::Rectangle,
+        scale_factor: f64,
+    ) -> f32 {
+        let (texels, region, logical) = match self.comparison_orientation {
+            ComparisonOrientation::Horizontal => (render_size.width, self.crop.width, bounds.width),
+            ComparisonOrientation::Vertical => {
+                (render_size.height, self.crop.height, bounds.height)
+            }
+        };
+        let physical = logical * scale_factor as f32;
+        if physical <= 0.0 {
+            return self.comparison_line_width;
+        }
+        self.comparison_line_width * texels as f32 * region / physical
+    }
+}
+
 impl Primitive for VideoPrimitive {
     /// Prepare the video for rendering
     ///
@@ -110,11 +350,80 @@ impl Primitive for VideoPrimitive {
 
         if !has_manager {
             debug!("Creating new video pipeline manager");
-            let pipeline_manager = VideoPipelineManager::new(device, format);
+            let pipeline_manager =
+                VideoPipelineManager::with_config(device, format, self.pipeline_config.clone());
             storage.store(pipeline_manager);
         }
 
         let pipeline_manager = storage.get_mut::<VideoPipelineManager>().unwrap();
+        pipeline_manager.set_sampling(device, self.pipeline_config.sampling);
+        pipeline_manager.set_bypass(self.pipeline_config.bypass_effects);
+
+        // Free videos whose owners have been dropped
+        let owners = std::iter::once((self.video_id, &self.owner))
+            .chain(self.grid.iter().map(|video| (video.video_id, &video.owner)));
+        for (video_id, owner) in owners {
+            if let Some(owner) = owner {
+                pipeline_manager.track_owner(video_id, owner);
+            }
+        }
+        pipeline_manager.release_dropped_videos();
+
+        // Finish screenshots of earlier frames
+        pipeline_manager.poll_read_backs(device);
+
+        // Grid cells upload their own frames and are drawn like picture-in-picture views
+        if !self.grid.is_empty() {
+            let physical_size = viewport.physical_size();
+            let transformation = iced::Transformation::orthographic(
+                physical_size.width as _,
+                physical_size.height as _,
+            );
+            for (cell, video) in grid_cells(bounds, &self.grid) {
+                if let Some(frame) = &video.frame {
+                    pipeline_manager.upload_frame(
+                        device,
+                        queue,
+                        video.video_id,
+                        video.size.0,
+                        video.size.1,
+                        frame.as_slice(),
+                        self.alive,
+                    );
+                }
+                if pipeline_manager.get_video(video.video_id).is_some() {
+                    pipeline_manager.prepare_pip(
+                        device,
+                        queue,
+                        video.video_id,
+                        &(cell * transformation),
+                        video.color_space,
+                        video.color_range,
+                        &FULL_REGION,
+                    );
+                }
+            }
+            return;
+        }
+
+        // Picture-in-picture views only sample the frame of the main view
+        if let Some(region) = &self.pip_region {
+            let physical_size = viewport.physical_size();
+            pipeline_manager.prepare_pip(
+                device,
+                queue,
+                self.video_id,
+                &(*bounds
+                    * iced::Transformation::orthographic(
+                        physical_size.width as _,
+                        physical_size.height as _,
+                    )),
+                self.color_space,
+                self.color_range,
+                region,
+            );
+            return;
+        }
 
         // Upload new frame data if requested
         if self.upload_frame {
@@ -137,8 +446,50 @@ impl Primitive for VideoPrimitive {
             );
         }
 
+        // Intermediate textures use the configured render size
+        let physical_size = viewport.physical_size();
+        let render_size = self.pipeline_config.render_size.resolve(
+            (physical_size.width, physical_size.height),
+            self.size,
+            device.limits().max_texture_dimension_2d,
+        );
+        let line_width = self.comparison_line_texels(render_size, bounds, viewport.scale_factor());
+
         // Create a list of effects that should be active based on current settings
         let mut desired_effects = Vec::new();
+        let effect_format = pipeline_manager.intermediate_format();
+
+        // Tone map HDR video first, right after the YUV to RGB conversion
+        let hdr_transfer = HdrTransfer::from_color_transfer(self.color_transfer);
+        match hdr_transfer {
+            Some(transfer) if !pipeline_manager.has_effect("tone_map") => {
+                debug!("Adding tone map effect for {:?} video", transfer);
+                desired_effects.push((
+                    "tone_map",
+                    Box::new(ToneMapEffect::new(transfer, effect_format))
+                        as Box<dyn Effect + Send + Sync>,
+                ));
+            }
+            None if pipeline_manager.has_effect("tone_map") => {
+                debug!("Removing tone map effect for SDR video");
+                pipeline_manager.remove_effect("tone_map");
+            }
+            _ => {}
+        }
+
+        // Add stereo effect if a stereo layout is set and not already active
+        if let Some(layout) = self.stereo_layout {
+            if !pipeline_manager.has_effect("stereo") {
+                debug!("Adding stereo effect (layout set but not yet active)");
+                desired_effects.push((
+                    "stereo",
+                    Box::new(StereoEffect {
+                        state: StereoEffectState { layout },
+                        format: effect_format,
+                    }) as Box<dyn Effect + Send + Sync>,
+                ));
+            }
+        }
 
         // Add upscale effect if selected and not already active
         if *self.shader_selections.get("upscale").unwrap_or(&false)
@@ -151,32 +502,211 @@ impl Primitive for VideoPrimitive {
                     state: UpscaleEffectState {
                         color_threshold: 1.0,
                         color_blend_mode: 0.5,
+                        ..Default::default()
                     },
-                    format,
+                    format: effect_format,
                 }) as Box<dyn Effect + Send + Sync>,
             ));
         }
 
-        // Add comparison effect if needed
-        if self.comparison_enabled && !pipeline_manager.has_effect("comparison") {
-            debug!("Adding comparison effect (enabled but not yet active)");
+        // Add sharpen effect after the upscale if selected and not already active
+        if *self.shader_selections.get("sharpen").unwrap_or(&false)
+            && !pipeline_manager.has_effect("sharpen")
+        {
+            debug!("Adding sharpen effect (selected but not yet active)");
             desired_effects.push((
-                "comparison",
-                Box::new(ComparisonEffect {
-                    line_position: self.comparison_position,
-                    format,
+                "sharpen",
+                Box::new(SharpenEffect {
+                    state: SharpenEffectState::default(),
+                    format: effect_format,
+                }) as Box<dyn Effect + Send + Sync>,
+            ));
+        }
+
+        // Add color balance effect if selected and not already active
+        if *self
+            .shader_selections
+            .get("color_balance")
+            .unwrap_or(&false)
+            && !pipeline_manager.has_effect("color_balance")
+        {
+            debug!("Adding color balance effect (selected but not yet active)");
+            desired_effects.push((
+                "color_balance",
+                Box::new(ColorBalanceEffect {
+                    state: ColorBalanceEffectState::default(),
+                    format: effect_format,
                 }) as Box<dyn Effect + Send + Sync>,
             ));
         }
 
+        // Add temporal denoise effect if selected and not already active
+        if *self
+            .shader_selections
+            .get("temporal_denoise")
+            .unwrap_or(&false)
+            && !pipeline_manager.has_effect("temporal_denoise")
+        {
+            debug!("Adding temporal denoise effect (selected but not yet active)");
+            desired_effects.push((
+                "temporal_denoise",
+                Box::new(TemporalDenoiseEffect::new(
+                    TemporalDenoiseEffectState::default(),
+                    effect_format,
+                )) as Box<dyn Effect + Send + Sync>,
+            ));
+        }
+
+        // Add posterize effect if selected and not already active
+        if *self.shader_selections.get("posterize").unwrap_or(&false)
+            && !pipeline_manager.has_effect("posterize")
+        {
+            debug!("Adding posterize effect (selected but not yet active)");
+            desired_effects.push((
+                "posterize",
+                Box::new(PosterizeEffect {
+                    state: PosterizeEffectState::default(),
+                    format: effect_format,
+                }) as Box<dyn Effect + Send + Sync>,
+            ));
+        }
+
+        // Add bloom effect if selected and not already active
+        if *self.shader_selections.get("bloom").unwrap_or(&false)
+            && !pipeline_manager.has_effect("bloom")
+        {
+            debug!("Adding bloom effect (selected but not yet active)");
+            desired_effects.push((
+                "bloom",
+                Box::new(BloomEffect::new(BloomEffectState::default(), effect_format))
+                    as Box<dyn Effect + Send + Sync>,
+            ));
+        }
+
+        // Add simple color effect if any of its modes is selected and not already active
+        let simple_color_mode = SimpleColorMode::from_selections(&self.shader_selections);
+        if simple_color_mode != SimpleColorMode::None
+            && !pipeline_manager.has_effect("simple_color")
+        {
+            debug!(
+                "Adding simple color effect ({} selected)",
+                simple_color_mode
+            );
+            desired_effects.push((
+                "simple_color",
+                Box::new(SimpleColorEffect {
+                    state: SimpleColorEffectState {
+                        mode: simple_color_mode,
+                    },
+                    format: effect_format,
+                }) as Box<dyn Effect + Send + Sync>,
+            ));
+        }
+
+        // Add blur effect if selected and not already active
+        if *self.shader_selections.get("blur").unwrap_or(&false)
+            && !pipeline_manager.has_effect("blur")
+        {
+            debug!("Adding blur effect (selected but not yet active)");
+            desired_effects.push((
+                "blur",
+                Box::new(BlurEffect::new(BlurEffectState::default(), effect_format))
+                    as Box<dyn Effect + Send + Sync>,
+            ));
+        }
+
+        // Add chroma key effect after the other built-in effects so none overwrites its alpha
+        if *self.shader_selections.get("chroma_key").unwrap_or(&false)
+            && !pipeline_manager.has_effect("chroma_key")
+        {
+            debug!("Adding chroma key effect (selected but not yet active)");
+            desired_effects.push((
+                "chroma_key",
+                Box::new(ChromaKeyEffect::new(GREEN_SCREEN, effect_format))
+                    as Box<dyn Effect + Send + Sync>,
+            ));
+        }
+
         // Add all desired effects to the pipeline
         for (name, mut effect) in desired_effects {
             debug!("Initializing effect: {}", name);
-            let shader_effect = effect.add(device, queue);
+            let result = effect.add(device, queue).and_then(|shader_effect| {
+                pipeline_manager.add_effect(false, device, queue, shader_effect, effect)
+            });
+            if let Err(e) = result {
+                warn!("Failed to add effect {}: {}", name, e);
+            }
+        }
+
+        // Effects kept from an SDR video can be ahead of the tone mapping
+        let tone_map_index = pipeline_manager
+            .effect_manager
+            .effects
+            .iter()
+            .position(|e| e.effect.name == "tone_map");
+        if let Some(index) = tone_map_index.filter(|&index| index > 1) {
+            debug!("Moving tone map effect after the YUV to RGB conversion");
+            if let Err(e) = pipeline_manager.effect_manager.move_effect(index, 1) {
+                warn!("Failed to move tone map effect: {}", e);
+            }
+        }
 
-            if let Err(e) = pipeline_manager.add_effect(false, device, queue, shader_effect, effect)
+        // Add custom effects, skipping ones whose shader was already rejected
+        for source in self.custom_effects.iter() {
+            if *self.shader_selections.get(&source.name).unwrap_or(&true)
+                && !pipeline_manager.has_effect(&source.name)
+                && !pipeline_manager.custom_effect_failed(&source.name)
             {
-                warn!("Failed to add effect {}: {}", name, e);
+                debug!("Adding custom effect: {}", source.name);
+                if let Err(e) = pipeline_manager.add_custom_effect(
+                    device,
+                    queue,
+                    &source.name,
+                    &source.wgsl_source,
+                    source.uniforms.clone(),
+                ) {
+                    warn!("Failed to add custom effect {}: {}", source.name, e);
+                }
+            }
+        }
+
+        // Grade with the LUT after the other effects
+        if let Err(e) = pipeline_manager.set_lut(device, queue, self.lut.clone()) {
+            warn!("Failed to set LUT: {}", e);
+        }
+
+        // Keep the comparison last, so it splits the fully processed frame
+        if self.comparison_enabled {
+            let comparison_last = pipeline_manager
+                .effect_manager
+                .effects
+                .last()
+                .is_some_and(|e| e.effect.name == "comparison");
+            if pipeline_manager.has_effect("comparison") && !comparison_last {
+                debug!("Moving comparison effect to the end of the chain");
+                pipeline_manager.remove_effect("comparison");
+            }
+            if !pipeline_manager.has_effect("comparison") {
+                debug!("Adding comparison effect (enabled but not yet active)");
+                let mut effect = ComparisonEffect {
+                    line_position: self.comparison_position,
+                    line_color: self.comparison_line_color,
+                    line_width,
+                    orientation: self.comparison_orientation,
+                    format: effect_format,
+                };
+                let result = effect.add(device, queue).and_then(|shader_effect| {
+                    pipeline_manager.add_effect(
+                        false,
+                        device,
+                        queue,
+                        shader_effect,
+                        Box::new(effect),
+                    )
+                });
+                if let Err(e) = result {
+                    warn!("Failed to add effect comparison: {}", e);
+                }
             }
         }
 
@@ -186,17 +716,29 @@ impl Primitive for VideoPrimitive {
             pipeline_manager.remove_effect("comparison");
         }
 
-        // Handle shader toggles
+        if self.stereo_layout.is_none() && pipeline_manager.has_effect("stereo") {
+            debug!("Removing stereo effect (no longer enabled)");
+            pipeline_manager.remove_effect("stereo");
+        }
+
+        // Handle shader toggles, keeping toggled off effects in the chain so
+        // turning them back on doesn't rebuild their pipelines
         for (name, enabled) in &self.shader_selections {
-            // If the shader is disabled in selections but exists in the pipeline, remove it
-            if !enabled && pipeline_manager.has_effect(name) {
-                debug!("Removing effect: {} (disabled in shader selections)", name);
-                pipeline_manager.remove_effect(name);
-            }
+            pipeline_manager.set_effect_enabled(name, *enabled);
         }
+        // The simple color modes share one effect
+        pipeline_manager
+            .set_effect_enabled("simple_color", simple_color_mode != SimpleColorMode::None);
 
         // Update parameters for active effects
         for effect in &mut pipeline_manager.effect_manager.effects {
+            // Apply parameters set from the UI
+            if let Some(parameters) = self.shader_parameters.get(&effect.effect.name) {
+                for (name, value) in parameters {
+                    effect.state.set_parameter(name, value);
+                }
+            }
+
             match effect.effect.name.as_str() {
                 "comparison" => {
                     trace!(
@@ -207,30 +749,57 @@ impl Primitive for VideoPrimitive {
                         .state
                         .as_mut()
                         .update_comparison(true, self.comparison_position);
+                    effect.state.set_parameter(
+                        "line_color",
+                        &UniformValue::Vec4(self.comparison_line_color),
+                    );
+                    effect
+                        .state
+                        .set_parameter("line_width", &UniformValue::Float(line_width));
+                    effect.state.set_parameter(
+                        "orientation",
+                        &UniformValue::Uint(self.comparison_orientation as u32),
+                    );
+                }
+                "tone_map" => {
+                    if let Some(transfer) = hdr_transfer {
+                        effect
+                            .state
+                            .set_parameter("transfer", &UniformValue::Uint(transfer as u32));
+                    }
+                }
+                "stereo" => {
+                    if let Some(layout) = self.stereo_layout {
+                        effect
+                            .state
+                            .set_parameter("layout", &UniformValue::Uint(layout as u32));
+                    }
+                }
+                "simple_color" => {
+                    effect
+                        .state
+                        .set_parameter("mode", &UniformValue::Uint(simple_color_mode as u32));
                 }
                 // Can add other effect parameter updates here
                 _ => {}
             }
         }
 
-        // Resize intermediate textures to match viewport size
-        let physical_size = viewport.physical_size();
-        let size = wgpu::Extent3d {
-            width: physical_size.width,
-            height: physical_size.height,
-            depth_or_array_layers: 1,
-        };
-
+        // Resize intermediate textures to the configured render size
         trace!(
             "Resizing intermediate textures: {}x{}, count={}",
-            size.width,
-            size.height,
+            render_size.width,
+            render_size.height,
             pipeline_manager.effect_manager.len() + 1
         );
 
         pipeline_manager
             .texture_manager
-            .resize_intermediate_textures(device, size, pipeline_manager.effect_manager.len() + 1);
+            .resize_intermediate_textures(
+                device,
+                render_size,
+                pipeline_manager.effect_manager.len() + 1,
+            );
 
         // Prepare the pipeline for rendering with current parameters
         pipeline_manager.prepare(
@@ -243,15 +812,30 @@ impl Primitive for VideoPrimitive {
                     physical_size.height as _,
                 )),
             self.color_space,
+            self.color_range,
+            &self.crop,
         );
 
         pipeline_manager.effects_added = true;
 
+        // Start reading back the frame, it's saved once the GPU is done
+        if let Some(path) = &self.screenshot {
+            let path = path.clone();
+            debug!("Taking screenshot of video {} to {:?}", self.video_id, path);
+            let result = pipeline_manager.read_back_frame(device, queue, self.video_id, |frame| {
+                save_screenshot(path, frame)
+            });
+            if let Err(e) = result {
+                error!("Failed to read back frame for screenshot: {}", e);
+            }
+        }
+
         trace!(
-            "Video prepared: effects={}, textures={}, comparison={}",
+            "Video prepared: effects={}, textures={}, comparison={}, bind group updates={}",
             pipeline_manager.effect_manager.len(),
             pipeline_manager.texture_manager.len(),
-            self.comparison_enabled
+            self.comparison_enabled,
+            shader::bind_group_updates()
         );
     }
 
@@ -268,7 +852,17 @@ impl Primitive for VideoPrimitive {
         trace!("Rendering video {}: clip={:?}", self.video_id, clip_bounds);
 
         if let Some(pipeline_manager) = storage.get::<VideoPipelineManager>() {
-            pipeline_manager.draw(target, encoder, clip_bounds, self.video_id);
+            if !self.grid.is_empty() {
+                for video in &self.grid {
+                    if pipeline_manager.get_video(video.video_id).is_some() {
+                        pipeline_manager.draw_pip(target, encoder, clip_bounds, video.video_id);
+                    }
+                }
+            } else if self.pip_region.is_some() {
+                pipeline_manager.draw_pip(target, encoder, clip_bounds, self.video_id);
+            } else {
+                pipeline_manager.draw(target, encoder, clip_bounds, self.video_id);
+            }
         } else {
             warn!("Attempted to render without pipeline manager");
         }
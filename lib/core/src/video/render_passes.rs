@@ -13,7 +13,11 @@ use wgpu::{
     TextureView,
 };
 
-use super::{color_space::BT709_CONFIG, pipeline::manager::VideoEntry, ShaderEffect};
+use super::{
+    color_space::BT709_CONFIG,
+    pipeline::{manager::VideoEntry, video::UNIFORM_RING_SIZE},
+    ShaderEffect,
+};
 
 /// Utility struct for creating various render passes in the video pipeline
 pub(crate) struct RenderPasses;
@@ -59,8 +63,17 @@ impl RenderPasses {
         // Set the pipeline for this render pass
         pass.set_pipeline(&pipeline);
 
-        // Calculate uniform buffer offset for this frame
-        let offset = video.render_index.load(Ordering::Relaxed) * video.aligned_uniform_size;
+        // Calculate uniform buffer offset for this frame, reusing the last
+        // prepared slot if nothing new has been prepared since the previous draw
+        let prepare_index = video.prepare_index.load(Ordering::Relaxed);
+        let render_index = video.render_index.load(Ordering::Relaxed);
+        let has_pending = render_index != prepare_index;
+        let slot = if has_pending {
+            render_index
+        } else {
+            render_index.wrapping_sub(1)
+        };
+        let offset = (slot % UNIFORM_RING_SIZE) * video.aligned_uniform_size;
         pass.set_bind_group(0, &video.bg0, &[offset as u32]);
 
         // Set appropriate scissor rectangle based on render mode
@@ -75,6 +88,14 @@ impl RenderPasses {
             );
         } else {
             // For final render to target, use UI coordinates from clip rectangle
+            pass.set_viewport(
+                clip.x as f32,
+                clip.y as f32,
+                clip.width as f32,
+                clip.height as f32,
+                0.0,
+                1.0,
+            );
             pass.set_scissor_rect(clip.x, clip.y, clip.width, clip.height);
             trace!("Using clip bounds for scissor: {:?}", clip);
         }
@@ -82,8 +103,10 @@ impl RenderPasses {
         // Draw a full-screen quad (2 triangles, 6 vertices)
         pass.draw(0..6, 0..1);
 
-        // Increment render index for next frame
-        video.render_index.fetch_add(1, Ordering::Relaxed);
+        // Consume the prepared slot
+        if has_pending {
+            video.render_index.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// Apply a shader effect to a texture
@@ -1,6 +1,11 @@
 use iced_wgpu::wgpu;
 use indexmap::IndexMap;
-use std::{collections::HashMap, num::NonZero, ops::Index};
+use std::{
+    collections::HashMap,
+    num::NonZero,
+    ops::Index,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 use tracing::{debug, info, trace, warn};
 
 /// Represents different types of uniform values that can be used in shaders
@@ -52,38 +57,128 @@ impl ShaderUniforms {
         }
     }
 
+    /// Get the name, offset and size of each uniform as written by `update_buffer`
+    ///
+    /// Follows the WGSL uniform layout rules, each value starts at a multiple
+    /// of its alignment in the order the uniforms were first set.
+    pub fn layout(&self) -> Vec<(&str, usize, usize)> {
+        uniform_layout(
+            self.values
+                .iter()
+                .map(|(name, value)| (name.as_str(), value)),
+        )
+    }
+
     /// Validate the memory layout of uniform values
     pub fn validate_layout(&self) {
-        let mut offset = 0;
-        let sorted_values: Vec<_> = self.values.iter().collect();
+        let layout = self.layout();
 
+        // Offsets are aligned by `layout`, mismatches with the shader are found by `check_layout`
         trace!("Uniform layout validation:");
-        for (name, value) in &sorted_values {
-            // Ensure alignment
-            while offset % 4 != 0 {
-                offset += 1;
-            }
-            trace!("  {} at offset {}, size {}", name, offset, value.size());
-            offset += value.size();
+        for (name, offset, size) in &layout {
+            let alignment = self.values[*name].alignment();
+            trace!(
+                "  {} at offset {}, size {}, alignment {}",
+                name,
+                offset,
+                size,
+                alignment
+            );
         }
-        trace!("Total size (before alignment): {}", offset);
-        trace!("Aligned size: {}", (offset + 15) & !15);
+        let total = layout.last().map_or(0, |(_, offset, size)| offset + size);
+        trace!("Total size (before alignment): {}", total);
+        trace!("Aligned size: {}", (total + 15) & !15);
+    }
+
+    /// Check the uniform layout against the fields of the WGSL uniform struct
+    ///
+    /// `expected` lists each field as (name, offset, size) in bytes. Padding
+    /// uniforms (names starting with `_`) only need to be present on the Rust
+    /// side, since they show up as offsets of the fields after them.
+    pub fn check_layout(&self, expected: &[(String, usize, usize)]) -> anyhow::Result<()> {
+        check_uniform_layout(&self.layout(), expected)
+    }
+}
+
+/// Check a uniform layout against the (name, offset, size) fields of a WGSL struct
+///
+/// See [`ShaderUniforms::check_layout`].
+pub fn check_uniform_layout(
+    layout: &[(&str, usize, usize)],
+    expected: &[(String, usize, usize)],
+) -> anyhow::Result<()> {
+    for (name, offset, size) in expected {
+        match layout.iter().find(|(field, _, _)| field == name) {
+            Some((_, actual_offset, actual_size))
+                if actual_offset == offset && actual_size == size => {}
+            Some((_, actual_offset, actual_size)) => anyhow::bail!(
+                "Uniform '{}' is at offset {} with size {}, but the shader expects offset {} with size {}",
+                name,
+                actual_offset,
+                actual_size,
+                offset,
+                size
+            ),
+            None => anyhow::bail!("Uniform '{}' is missing", name),
+        }
+    }
+
+    if let Some((name, _, _)) = layout.iter().find(|(field, _, _)| {
+        !field.starts_with('_') && !expected.iter().any(|(name, _, _)| name == field)
+    }) {
+        anyhow::bail!("Uniform '{}' is not part of the shader layout", name);
     }
+
+    Ok(())
+}
+
+/// Lay out uniforms in order following the WGSL uniform layout rules
+///
+/// Returns the name, offset and size of each value, see [`ShaderUniforms::layout`].
+pub fn uniform_layout<'a>(
+    values: impl IntoIterator<Item = (&'a str, &'a UniformValue)>,
+) -> Vec<(&'a str, usize, usize)> {
+    let mut offset: usize = 0;
+    let mut layout = Vec::new();
+
+    for (name, value) in values {
+        offset = offset.next_multiple_of(value.alignment());
+        layout.push((name, offset, value.size()));
+        offset += value.size();
+    }
+
+    layout
 }
 
 impl UniformValue {
-    /// Get the size in bytes of this uniform value
+    /// Get the size in bytes of this uniform value in a WGSL uniform struct
+    ///
+    /// A mat3x3 is stored as three columns padded to 16 bytes each.
     pub fn size(&self) -> usize {
         match self {
             UniformValue::Float(_) | UniformValue::Int(_) | UniformValue::Uint(_) => 4,
             UniformValue::Vec2(_) => 8,
             UniformValue::Vec3(_) => 12,
             UniformValue::Vec4(_) => 16,
-            UniformValue::Mat3(_) => 36,
+            UniformValue::Mat3(_) => 48,
             UniformValue::Mat4(_) => 64,
         }
     }
 
+    /// Get the alignment in bytes of this uniform value in a WGSL uniform struct
+    ///
+    /// A vec3 aligns like a vec4, so a scalar can follow in its last 4 bytes.
+    pub fn alignment(&self) -> usize {
+        match self {
+            UniformValue::Float(_) | UniformValue::Int(_) | UniformValue::Uint(_) => 4,
+            UniformValue::Vec2(_) => 8,
+            UniformValue::Vec3(_)
+            | UniformValue::Vec4(_)
+            | UniformValue::Mat3(_)
+            | UniformValue::Mat4(_) => 16,
+        }
+    }
+
     /// Convert the uniform value to a byte representation
     pub fn as_bytes(&self) -> Vec<u8> {
         match self {
@@ -93,7 +188,12 @@ impl UniformValue {
             UniformValue::Vec2(v) => bytemuck::cast_slice(v).to_vec(),
             UniformValue::Vec3(v) => bytemuck::cast_slice(v).to_vec(),
             UniformValue::Vec4(v) => bytemuck::cast_slice(v).to_vec(),
-            UniformValue::Mat3(v) => bytemuck::cast_slice(v).to_vec(),
+            UniformValue::Mat3(v) => v
+                .iter()
+                .flat_map(|column| {
+                    bytemuck::cast_slice(&[column[0], column[1], column[2], 0.0]).to_vec()
+                })
+                .collect(),
             UniformValue::Mat4(v) => bytemuck::cast_slice(v).to_vec(),
         }
     }
@@ -130,26 +230,14 @@ impl ShaderUniforms {
     /// Update the GPU buffer with the current uniform values
     pub fn update_buffer(&self, queue: &wgpu::Queue) {
         let mut data = Vec::new();
-        let mut offset = 0;
-
-        // Process values in insertion order
-        for (name, value) in &self.values {
-            // Ensure 4-byte alignment for each value
-            while offset % 4 != 0 {
-                data.push(0);
-                offset += 1;
-            }
 
-            let value_data = value.as_bytes();
-            data.extend_from_slice(&value_data);
-            offset += value_data.len();
+        // Process values in insertion order, padded to their alignment
+        for (name, offset, _) in self.layout() {
+            let value = &self.values[name];
+            data.resize(offset, 0);
+            data.extend_from_slice(&value.as_bytes());
 
-            trace!(
-                "Added uniform {} at offset {}: {:?}",
-                name,
-                offset - value_data.len(),
-                value
-            );
+            trace!("Added uniform {} at offset {}: {:?}", name, offset, value);
         }
 
         // Ensure 16-byte alignment for the overall buffer
@@ -181,6 +269,7 @@ pub struct ShaderEffectBuilder {
     texture_bindings: Vec<wgpu::BindGroupLayoutEntry>,
     bind_group_layout: Option<wgpu::BindGroupLayout>,
     sampler_bindings: Vec<wgpu::BindGroupLayoutEntry>,
+    expected_layout: Option<Vec<(String, usize, usize)>>,
 }
 
 impl ShaderEffectBuilder {
@@ -194,6 +283,7 @@ impl ShaderEffectBuilder {
             pending_uniforms: HashMap::new(),
             texture_bindings: Vec::new(),
             sampler_bindings: Vec::new(),
+            expected_layout: None,
         }
     }
 
@@ -221,6 +311,20 @@ impl ShaderEffectBuilder {
         self
     }
 
+    /// Set the uniform layout of the WGSL struct as (name, offset, size) in bytes
+    ///
+    /// The uniforms are checked against it when the effect is built, see
+    /// [`ShaderUniforms::check_layout`].
+    pub fn with_expected_layout(mut self, layout: &[(&str, usize, usize)]) -> Self {
+        self.expected_layout = Some(
+            layout
+                .iter()
+                .map(|(name, offset, size)| (name.to_string(), *offset, *size))
+                .collect(),
+        );
+        self
+    }
+
     /// Add a texture binding at the specified binding point
     pub fn with_texture_binding(mut self, binding: u32) -> Self {
         self.texture_bindings.push(wgpu::BindGroupLayoutEntry {
@@ -236,13 +340,17 @@ impl ShaderEffectBuilder {
         self
     }
 
-    /// Build the shader effect with all configured options
+    /// Build the shader effect, failing if the uniforms don't match the expected layout
     pub fn build(
         self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         format: wgpu::TextureFormat,
-    ) -> ShaderEffect {
+    ) -> anyhow::Result<ShaderEffect> {
+        if let (Some(expected), Some(uniforms)) = (&self.expected_layout, &self.uniforms) {
+            uniforms.check_layout(expected)?;
+        }
+
         let bind_layout_group = self
             .bind_group_layout
             .expect("Bind group layout must be provided");
@@ -336,6 +444,7 @@ impl ShaderEffectBuilder {
             uniforms,
             sampler,
             current_bind_group: None,
+            last_input_ids: Vec::new(),
         };
 
         // Ensure layout ID hasn't changed during the process
@@ -347,7 +456,7 @@ impl ShaderEffectBuilder {
             );
         }
 
-        effect
+        Ok(effect)
     }
 }
 
@@ -360,6 +469,18 @@ pub struct ShaderEffect {
     pub sampler: wgpu::Sampler,
     pub format: wgpu::TextureFormat,
     pub current_bind_group: Option<wgpu::BindGroup>,
+    pub last_input_ids: Vec<wgpu::Id<wgpu::Texture>>, // Input textures of the current bind group
+}
+
+// Number of bind groups set on effects, to check that they are reused
+static BIND_GROUP_UPDATES: AtomicUsize = AtomicUsize::new(0);
+
+/// Get the number of bind groups set on effects since startup
+///
+/// Stays the same while paused, as bind groups are only rebuilt when the
+/// input textures of an effect change.
+pub fn bind_group_updates() -> usize {
+    BIND_GROUP_UPDATES.load(Ordering::Relaxed)
 }
 
 impl ShaderEffect {
@@ -376,6 +497,7 @@ impl ShaderEffect {
     /// Update the bind group used by this effect
     pub fn update_bind_group(&mut self, bind_group: wgpu::BindGroup) {
         trace!("Updating bind group for effect '{}'", self.name);
+        BIND_GROUP_UPDATES.fetch_add(1, Ordering::Relaxed);
         self.current_bind_group = Some(bind_group);
     }
 
@@ -422,3 +544,60 @@ impl ShaderEffect {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_follows_wgsl_alignment() {
+        let values = [
+            ("a", UniformValue::Float(1.0)),
+            ("b", UniformValue::Vec3([0.0; 3])),
+            ("c", UniformValue::Float(2.0)),
+            ("d", UniformValue::Vec2([0.0; 2])),
+            ("e", UniformValue::Mat3([[0.0; 3]; 3])),
+        ];
+        let layout = uniform_layout(values.iter().map(|(name, value)| (*name, value)));
+
+        assert_eq!(
+            layout,
+            vec![
+                ("a", 0, 4),
+                ("b", 16, 12),
+                ("c", 28, 4),
+                ("d", 32, 8),
+                ("e", 48, 48),
+            ]
+        );
+    }
+
+    #[test]
+    fn mat3_columns_are_padded() {
+        let mat3 = UniformValue::Mat3([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        assert_eq!(mat3.size(), 48);
+        assert_eq!(mat3.as_bytes().len(), 48);
+
+        let columns: [f32; 12] = [1.0, 2.0, 3.0, 0.0, 4.0, 5.0, 6.0, 0.0, 7.0, 8.0, 9.0, 0.0];
+        assert_eq!(mat3.as_bytes(), bytemuck::cast_slice::<f32, u8>(&columns));
+    }
+
+    #[test]
+    fn layout_mismatches_are_rejected() {
+        let expected = vec![("a".to_string(), 0, 4), ("b".to_string(), 16, 12)];
+        let check = |layout: &[(&str, usize, usize)]| {
+            check_uniform_layout(layout, &expected).map_err(|e| e.to_string())
+        };
+
+        assert!(check(&[("a", 0, 4), ("_pad", 4, 12), ("b", 16, 12)]).is_ok());
+        assert_eq!(check(&[("a", 0, 4)]).unwrap_err(), "Uniform 'b' is missing");
+        assert_eq!(
+            check(&[("a", 0, 4), ("b", 4, 12)]).unwrap_err(),
+            "Uniform 'b' is at offset 4 with size 12, but the shader expects offset 16 with size 12"
+        );
+        assert_eq!(
+            check(&[("a", 0, 4), ("b", 16, 12), ("c", 28, 4)]).unwrap_err(),
+            "Uniform 'c' is not part of the shader layout"
+        );
+    }
+}
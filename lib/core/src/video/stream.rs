@@ -1,57 +1,613 @@
 use ffmpeg_next::{
     self as ffmpeg,
-    color::Space,
-    error::EAGAIN,
     ffi::{
-        av_seek_frame, AVColorPrimaries, AVColorRange, AVColorSpace, AVColorTransferCharacteristic,
-        AVMediaType, AVSEEK_FLAG_ANY, AVSEEK_FLAG_FRAME, AV_TIME_BASE,
+        av_hwdevice_ctx_create, av_seek_frame, AVColorPrimaries, AVColorRange, AVColorSpace,
+        AVColorTransferCharacteristic, AVHWDeviceType, AVMediaType, AVSEEK_FLAG_ANY,
+        AVSEEK_FLAG_FRAME, AV_PIX_FMT_FLAG_ALPHA, AV_TIME_BASE,
     },
 };
 
+use iced::futures::channel::oneshot;
 use orbit_video_player_common::VideoError;
+
+use super::{
+    audio::{AudioOutput, AudioTrack},
+    color_space::{ColorInfo, ColorPrimaries, ColorRange, ColorSpace, ColorTransfer},
+    decoder::{DecodeThread, Decoded, FrameDecoder},
+    pipeline::manager::VideoOwner,
+    timecode::{format_timecode, parse_timecode, TimecodeRate},
+};
 use std::{
     borrow::Borrow,
     collections::VecDeque,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Weak,
+    },
     time::{Duration, Instant},
 };
 use tracing::{debug, error, info, trace, warn};
 
 /// A decoded video frame ready for display
+///
+/// Samples are 16-bit little endian for high bit depth videos, see
+/// `VideoStream::is_high_bit_depth`.
 pub struct QueuedFrame {
-    pub data: Vec<u8>,     // YUV data in planar format
+    pub data: Vec<u8>, // YUV data in planar format, followed by alpha if the video has it
     pub frame_number: u64, // Sequential frame number
 }
 
-/// Video stream decoder that handles reading, buffering, and playback control
+/// Playback state of a video stream, e.g. for showing a spinner while buffering
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaybackState {
+    Opening,   // No frame has been presented yet
+    Buffering, // The presentation queue ran dry and is being refilled
+    Playing,
+    Paused,
+    Ended,
+    Error, // Decoding failed, cleared by seeking
+}
+
+/// Something that happened during playback, reported by the video widget
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackEvent {
+    FramePresented { frame: u64, pts: Duration }, // A new frame was shown, `pts` as `current_time`
+    Ended,                                        // The end frame was reached without looping
+    Looped,                                       // Playback started over from the start frame
+    SeekCompleted,                                // A seek finished and its target frame is shown
+    BufferUnderrun, // The frame queue ran dry, `PlaybackState::Buffering` follows
+}
+
+/// How precisely `VideoStream::seek_to_time` lands on the requested time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SeekMode {
+    /// Show the keyframe at or before the time, e.g. while scrubbing
+    ///
+    /// Only the keyframe is decoded, so this is fast even for long GOPs, but
+    /// the position can be off by up to the keyframe interval.
+    Fast,
+    /// Decode forward from the keyframe to the first frame at the time
+    #[default]
+    Accurate,
+}
+
+/// Video stream that handles buffering and playback control
+///
+/// Frames are read, decoded and converted on a dedicated thread ahead of
+/// playback, so a slow decode doesn't stall the caller. See `DecodeThread`.
+///
+/// The ffmpeg decoder is owned by that thread and no longer exposed as a
+/// field. Its details are available from `snapshot_metadata`, `color_info`,
+/// `width` and `height`.
 pub struct VideoStream {
-    pub decoder: ffmpeg::decoder::Video,
-    format_context: ffmpeg::format::context::Input,
+    id: u64,                // Unique ID used for the GPU resources of this stream
+    owner: Arc<VideoOwner>, // Keeps the GPU resources of this stream alive
+    decode_thread: DecodeThread,
     video_stream_index: usize,
-    current_frame: u64,
+    width: u32,
+    height: u32,
+    timing: FrameTiming,
+    duration: i64,      // Duration of the video stream in its time base
+    bit_rate: i64,      // Bitrate of the file, 0 if unknown
+    decoded_frame: u64, // Decode cursor, number of the next frame to queue
     start_frame: u64,
     end_frame: Option<u64>,
+    duration_frames: u64, // Total frames, computed once when opening
+    end_of_file: bool,    // Whether the decode thread has drained the decoder
     looping: bool,
     presentation_queue: VecDeque<QueuedFrame>,
-    max_queue_size: usize,
+    queue_size: usize,     // Configured number of frames to decode ahead
+    max_queue_size: usize, // Current target, raised above `queue_size` when adaptive
+    adaptive_queue_size: bool,
+    decode_latency: Duration, // Recent peak time to decode a frame, decays over time
     frame_timer: Instant,
+    playback_speed: f32, // Multiplier of the source frame rate
     pub is_playing: bool,
-    pub color_space: Space,
-    frame_buffer: Vec<u8>,           // Buffer for processing frames
-    yuv_frame: ffmpeg::frame::Video, // Reusable frame object
-    scaler: ffmpeg::software::scaling::Context,
+    buffering: bool,                   // Refilling the queue after an underrun
+    previewing: bool,                  // A preview frame is being decoded, see `preview_at`
+    pending_preview: Option<f64>,      // Time to preview once the current preview arrives
+    underruns: u64,                    // Number of times the queue ran dry during playback
+    seeks: u64,                        // Number of completed seeks, excluding loops
+    loops: u64,                        // Number of times playback started over
+    presented: bool,                   // Whether a frame has been returned for display
+    last_error: Option<(u64, String)>, // Frame and error of a failed update, cleared by seeking
+    reported_state: PlaybackState,     // Last state returned by take_state_change
+    logged_frame: Option<u64>,         // Last frame traced by get_current_frame
+    video_streams: Vec<StreamInfo>,    // Playable video streams of the file
+    has_alpha: bool,                   // Whether frames carry an alpha plane
+    high_bit_depth: bool,              // Whether frames carry 16-bit samples, e.g. for 10-bit HDR
+    color_info: ColorInfo,
+    hw_accel: Option<HwAccelType>, // Backend frames are decoded with, `None` for software
+    audio: Option<AudioOutput>,    // Playback side of the audio, decoded on the decode thread
+    metadata: VideoMetadata,
 }
 
 /// Options for creating a new video stream
-pub struct VideoStreamOptions<'a> {
-    pub video_path: &'a str,
+///
+/// Owned, so they can be moved to the thread of `VideoStream::new_async`.
+#[derive(Clone, Debug)]
+pub struct VideoStreamOptions {
+    pub video_path: String,
+    /// First frame played, frames are numbered from 1
     pub start_frame: u64,
+    /// Last frame played, `None` plays to the end of the video
+    ///
+    /// The range is inclusive, so `start_frame + 30` plays 31 frames with the
+    /// end frame as the last one shown, the same as `set_frame_range`. Ends
+    /// past the last frame of the video are clamped to it.
     pub end_frame: Option<u64>,
+    /// Treat the video as BT.709 limited range regardless of its metadata
+    ///
+    /// Only meant for sources with missing or incorrect color metadata.
+    pub assume_bt709: bool,
+    /// Frame rate for opening `video_path` as an image sequence
+    ///
+    /// The path is a printf pattern (`frame_%04d.png`) or a glob (`frame_*.png`).
+    /// Image sequences have no timing of their own, so frame `n` of the sequence
+    /// is shown at `n / fps` seconds.
+    pub image_sequence_fps: Option<f64>,
+    /// Number of times a packet read is retried after a transient error
+    ///
+    /// Useful for files that are still being written and for pipes. Retries
+    /// back off exponentially and block the decoding thread, so keep this small.
+    pub max_read_retries: u32,
+    /// Container index of the video stream to play, e.g. to pick a camera angle
+    ///
+    /// `None` picks the best video stream. See `VideoStream::available_video_streams`.
+    pub stream_index: Option<usize>,
+    /// Number of decoder threads, `None` lets ffmpeg pick one per core
+    ///
+    /// Decoding uses frame threading, which spreads high resolution video such
+    /// as 4K across cores but delays output by about one frame per thread. The
+    /// delay adds to the time a seek takes before the new frame is shown, so
+    /// use `Some(1)` where seek latency matters more than throughput.
+    pub decode_threads: Option<usize>,
+    /// Number of corrupt packets in a row that are skipped before decoding fails
+    ///
+    /// Damaged captures and live streams can contain packets the decoder
+    /// rejects. These are skipped and counted in `VideoStream::corrupt_frames`;
+    /// `0` fails on the first corrupt packet.
+    pub max_corrupt_packets: u32,
+    /// Show the previous good frame in place of each skipped corrupt packet
+    ///
+    /// Keeps the frame numbers and timing of the rest of the video intact,
+    /// at the cost of a visible stutter.
+    pub repeat_frame_on_corrupt: bool,
+    /// Pixel format the scaler converts decoded frames to
+    ///
+    /// Both are queued as the same Y plus interleaved UV layout, see `OutputPixelFormat`.
+    pub output_pixel_format: OutputPixelFormat,
+    /// Decode with a hardware decoder, falling back to software if it isn't available
+    ///
+    /// See `HwAccelType` for the backend of each platform. `None` always decodes in software.
+    pub hw_accel: Option<HwAccelType>,
+    /// Number of decoded frames kept ahead of the one shown, at least 1
+    ///
+    /// Larger queues ride out slow reads and decodes but hold more frames in
+    /// memory, about 12 MB each for 4K. See `DEFAULT_QUEUE_SIZE`.
+    pub queue_size: usize,
+    /// Grow the queue beyond `queue_size` while decoding is slow
+    ///
+    /// Enough frames are added to cover the slowest recent decode, e.g. a stall
+    /// reading from a slow disk, up to `MAX_ADAPTIVE_QUEUE_SIZE` frames.
+    pub adaptive_queue_size: bool,
+    /// Play the best audio stream of the file on the default output device
+    ///
+    /// The video is synced to the audio while it plays at normal speed. Files
+    /// without audio, or systems without an output device, play silently.
+    pub audio: bool,
+    /// Size the scaler converts frames to instead of the native size, e.g. for previews
+    ///
+    /// Smaller frames are cheaper to convert, queue and upload. `width` and
+    /// `height` report the scaled size, see `target_scale` for the aspect ratio.
+    pub target_size: Option<(u32, u32)>,
+    /// How frames are scaled to `target_size`
+    pub target_scale: TargetScale,
+}
+
+/// Pixel format decoded frames are converted to before queueing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputPixelFormat {
+    /// Planar YUV 4:2:0, U and V are interleaved row by row when copying
+    #[default]
+    Yuv420p,
+    /// Y plane plus interleaved UV plane, copied as is without a per-pixel loop
+    ///
+    /// Cheaper for large frames. Videos with alpha or high bit depth use
+    /// `Yuv420p` regardless, the latter with 16-bit samples.
+    Nv12,
+}
+
+/// How decoded frames are scaled to `VideoStreamOptions::target_size`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TargetScale {
+    /// Keep the aspect ratio and fit inside the target size, never upscaling
+    #[default]
+    Fit,
+    /// Scale to exactly the target size, ignoring the aspect ratio
+    Stretch,
+}
+
+impl TargetScale {
+    /// Size frames of the native size are scaled to for a target size
+    pub fn output_size(self, width: u32, height: u32, target: Option<(u32, u32)>) -> (u32, u32) {
+        let Some((target_width, target_height)) = target else {
+            return (width, height);
+        };
+        match self {
+            Self::Fit => {
+                let scale = (target_width as f64 / width.max(1) as f64)
+                    .min(target_height as f64 / height.max(1) as f64)
+                    .min(1.0);
+                (
+                    ((width as f64 * scale).round() as u32).max(1),
+                    ((height as f64 * scale).round() as u32).max(1),
+                )
+            }
+            Self::Stretch => (target_width.max(1), target_height.max(1)),
+        }
+    }
+}
+
+/// Hardware decoding backend
+///
+/// Decoded frames are copied back to system memory and converted like software
+/// decoded ones, which still saves most of the CPU time for 4K video. Codecs
+/// the hardware can't decode fall back to software.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HwAccelType {
+    /// VA-API on Linux, with Intel and AMD GPUs
+    Vaapi,
+    /// VideoToolbox on macOS
+    VideoToolbox,
+    /// Direct3D 11 on Windows
+    D3d11va,
+}
+
+impl HwAccelType {
+    /// Backend of the current platform, if there is one
+    pub fn platform_default() -> Option<Self> {
+        if cfg!(target_os = "linux") {
+            Some(HwAccelType::Vaapi)
+        } else if cfg!(target_os = "macos") {
+            Some(HwAccelType::VideoToolbox)
+        } else if cfg!(target_os = "windows") {
+            Some(HwAccelType::D3d11va)
+        } else {
+            None
+        }
+    }
+
+    fn device_type(self) -> AVHWDeviceType {
+        match self {
+            HwAccelType::Vaapi => AVHWDeviceType::AV_HWDEVICE_TYPE_VAAPI,
+            HwAccelType::VideoToolbox => AVHWDeviceType::AV_HWDEVICE_TYPE_VIDEOTOOLBOX,
+            HwAccelType::D3d11va => AVHWDeviceType::AV_HWDEVICE_TYPE_D3D11VA,
+        }
+    }
+}
+
+/// Description of a video stream in an opened file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamInfo {
+    pub index: usize, // Index of the stream in the container
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Codec and format details of an opened video, e.g. for an info panel
+///
+/// Describes the file as signaled by it, the color properties are the ones
+/// before `assume_bt709` or the scaler override them for display.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoMetadata {
+    pub container: String, // Short name of the container format, e.g. "matroska,webm"
+    pub codec: String,     // Short name of the codec, e.g. "h264"
+    pub codec_long: String, // Descriptive name of the decoder, e.g. "H.264 / AVC / MPEG-4 AVC"
+    pub width: u32,        // Native size, before scaling to `VideoStreamOptions::target_size`
+    pub height: u32,
+    pub pixel_format: String, // Pixel format of the decoded frames, e.g. "yuv420p"
+    pub color_info: ColorInfo,
+    pub frame_rate: (u32, u32),     // Numerator and denominator
+    pub duration: Option<Duration>, // Duration of the video stream, if the container has it
+    pub bit_rate: Option<u64>,      // Bits per second of the whole file, if known
+}
+
+impl VideoMetadata {
+    /// Get the frames per second as a single number, e.g. 23.976
+    pub fn fps(&self) -> f64 {
+        self.frame_rate.0 as f64 / self.frame_rate.1.max(1) as f64
+    }
 }
 
 // Constants
-const DEFAULT_FPS: i32 = 30;
-const DEFAULT_QUEUE_SIZE: usize = 10;
-const MAX_PACKETS_PER_FRAME: usize = 100;
+const DEFAULT_FPS: u32 = 30;
+pub const DEFAULT_QUEUE_SIZE: usize = 10;
+pub const MAX_ADAPTIVE_QUEUE_SIZE: usize = 30;
+const DECODE_LATENCY_DECAY: f64 = 0.98; // Per decoded frame
+const FRAME_POSITION_TOLERANCE: f64 = 1e-3;
+const MIN_PLAYBACK_SPEED: f32 = 0.1;
+const MAX_PLAYBACK_SPEED: f32 = 8.0;
+
+// Source of unique stream IDs
+static NEXT_VIDEO_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Check if a stream is a video that can be played, not an attached picture such as cover art
+fn is_playable_video(stream: &ffmpeg::Stream) -> bool {
+    stream.parameters().medium() == ffmpeg::media::Type::Video
+        && !stream
+            .disposition()
+            .contains(ffmpeg::format::stream::Disposition::ATTACHED_PIC)
+}
+
+/// Find the video stream to play, skipping attached pictures such as cover art
+///
+/// Files without one fail with `VideoError::NoVideoStream`, which tells
+/// audio-only files apart from other containers.
+fn find_video_stream(
+    format_context: &ffmpeg::format::context::Input,
+) -> Result<ffmpeg::Stream<'_>, VideoError> {
+    let best = format_context
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .filter(is_playable_video);
+    if let Some(stream) = best.or_else(|| format_context.streams().find(is_playable_video)) {
+        return Ok(stream);
+    }
+
+    let format = format_context.format().name().to_owned();
+    let audio_only = format_context
+        .streams()
+        .any(|stream| stream.parameters().medium() == ffmpeg::media::Type::Audio);
+    warn!(
+        "No video stream in {} container (audio only: {})",
+        format, audio_only
+    );
+    Err(VideoError::NoVideoStream { format, audio_only })
+}
+
+/// Get the requested video stream, or the best one if none was requested
+fn select_video_stream(
+    format_context: &ffmpeg::format::context::Input,
+    stream_index: Option<usize>,
+) -> Result<ffmpeg::Stream<'_>, VideoError> {
+    match stream_index {
+        Some(index) => format_context
+            .stream(index)
+            .filter(is_playable_video)
+            .ok_or(VideoError::InvalidStreamIndex(index)),
+        None => find_video_stream(format_context),
+    }
+}
+
+/// Describe all playable video streams of a file
+fn video_stream_infos(format_context: &ffmpeg::format::context::Input) -> Vec<StreamInfo> {
+    format_context
+        .streams()
+        .filter(is_playable_video)
+        .map(|stream| {
+            let parameters = stream.parameters();
+            let (width, height) = unsafe {
+                let parameters = &*parameters.as_ptr();
+                (
+                    parameters.width.max(0) as u32,
+                    parameters.height.max(0) as u32,
+                )
+            };
+            StreamInfo {
+                index: stream.index(),
+                codec: parameters.id().name().to_owned(),
+                width,
+                height,
+            }
+        })
+        .collect()
+}
+
+/// Check if a path is a glob pattern rather than a printf image sequence pattern
+fn is_glob_pattern(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Check that frames of a pixel format can be converted for display
+///
+/// Frames are converted with swscale, which reads most formats. The rest, e.g.
+/// hardware surface formats or ones unknown to the linked FFmpeg, fail with
+/// `VideoError::UnsupportedPixelFormat` listing the formats it reads.
+fn validate_pixel_format(format: ffmpeg::format::Pixel) -> Result<(), VideoError> {
+    if ffmpeg::software::scaling::support::input(format) {
+        return Ok(());
+    }
+
+    let mut supported = Vec::new();
+    let mut descriptor = std::ptr::null();
+    loop {
+        descriptor = unsafe { ffmpeg::sys::av_pix_fmt_desc_next(descriptor) };
+        if descriptor.is_null() {
+            break;
+        }
+        let pixel =
+            ffmpeg::format::Pixel::from(unsafe { ffmpeg::sys::av_pix_fmt_desc_get_id(descriptor) });
+        if ffmpeg::software::scaling::support::input(pixel) {
+            supported.extend(pixel.descriptor().map(|descriptor| descriptor.name()));
+        }
+    }
+
+    error!("Unsupported pixel format {:?}", format);
+    Err(VideoError::UnsupportedPixelFormat {
+        format,
+        supported: supported.join(", "),
+    })
+}
+
+/// Check if a pixel format has an alpha channel, e.g. the YUVA formats of ProRes 4444
+fn has_alpha_channel(format: ffmpeg::format::Pixel) -> bool {
+    format.descriptor().is_some_and(|descriptor| unsafe {
+        (*descriptor.as_ptr()).flags & AV_PIX_FMT_FLAG_ALPHA as u64 != 0
+    })
+}
+
+/// Check if a pixel format has more than 8 bits per sample, e.g. P010 or yuv420p10le
+fn is_high_bit_depth(format: ffmpeg::format::Pixel) -> bool {
+    format
+        .descriptor()
+        .is_some_and(|descriptor| unsafe { (*descriptor.as_ptr()).comp[0].depth > 8 })
+}
+
+/// Check if the frame numbered `frame` comes after the inclusive `end_frame`
+pub(crate) fn past_end_frame(frame: u64, end_frame: Option<u64>) -> bool {
+    end_frame.is_some_and(|end_frame| frame > end_frame)
+}
+
+/// Check the end frame of a range and clamp it to the frames of the video
+///
+/// Frames are numbered from 1 and the range is inclusive. Ends past the last
+/// frame are clamped to it, unless `total` is 0 because the container doesn't
+/// know the duration. Fails for a start of 0 or an end before the start.
+fn clamp_end_frame(start: u64, end: Option<u64>, total: u64) -> Result<Option<u64>, VideoError> {
+    if start == 0 || end.is_some_and(|end| end < start) {
+        warn!("Invalid frame range {}-{:?} ({} frames)", start, end, total);
+        return Err(VideoError::InvalidFrameRange {
+            start,
+            end: end.unwrap_or(total),
+            total,
+        });
+    }
+
+    Ok(end.map(|end| {
+        if total > 0 && end > total {
+            debug!("Clamping end frame {} to the last frame {}", end, total);
+            total
+        } else {
+            end
+        }
+    }))
+}
+
+/// Size of the chroma planes of a YUV420 frame
+///
+/// Odd dimensions are rounded up so the last column and row keep their chroma.
+pub fn chroma_size(width: u32, height: u32) -> (u32, u32) {
+    (width.div_ceil(2), height.div_ceil(2))
+}
+
+/// Scale from luma to chroma texture coordinates, 1.0 for even sizes
+///
+/// The same as `chroma_scale` in the video shaders, which compute it from the
+/// texture sizes.
+pub fn chroma_scale(width: u32, height: u32) -> [f32; 2] {
+    let (uv_width, uv_height) = chroma_size(width, height);
+    [
+        width as f32 / (2 * uv_width.max(1)) as f32,
+        height as f32 / (2 * uv_height.max(1)) as f32,
+    ]
+}
+
+/// Clamp a seek time to the range from `start_time` to `end_time` in seconds
+///
+/// Times before the start land on the start, times at or past the end on the
+/// end, which is the start of the last frame. Fails for NaN and infinite times.
+fn clamp_time(time_s: f64, start_time: f64, end_time: f64) -> Result<f64, VideoError> {
+    if !time_s.is_finite() {
+        warn!(
+            "Invalid seek time: {} (range: {}-{})",
+            time_s, start_time, end_time
+        );
+        return Err(VideoError::InvalidTimestamp);
+    }
+
+    let clamped = time_s.clamp(start_time, end_time.max(start_time));
+    if clamped != time_s {
+        debug!(
+            "Clamping seek time {} to {} (range: {}-{})",
+            time_s, clamped, start_time, end_time
+        );
+    }
+    Ok(clamped)
+}
+
+/// Coefficients of the ffmpeg scaler for a YUV to RGB matrix
+///
+/// Spaces without a matrix fall back to BT.709, like in the video shaders.
+fn sws_color_space(space: ColorSpace) -> i32 {
+    let coefficients = match space {
+        ColorSpace::Bt601 => ffmpeg::sys::SWS_CS_ITU601,
+        ColorSpace::Bt2020 => ffmpeg::sys::SWS_CS_BT2020,
+        ColorSpace::Bt709 | ColorSpace::Rgb | ColorSpace::Unspecified => ffmpeg::sys::SWS_CS_ITU709,
+    };
+    coefficients as i32
+}
+
+/// Queue size covering the frames shown while waiting on a slow decode
+///
+/// Never below the configured size, and at most `MAX_ADAPTIVE_QUEUE_SIZE`
+/// unless configured larger.
+fn adaptive_queue_size(
+    queue_size: usize,
+    decode_latency: Duration,
+    frame_duration: Duration,
+) -> usize {
+    let stalled_frames = decode_latency.div_duration_f64(frame_duration).floor() as usize;
+    (queue_size + stalled_frames)
+        .min(MAX_ADAPTIVE_QUEUE_SIZE)
+        .max(queue_size)
+}
+
+/// Frame rate and time base of a video stream
+///
+/// Converts between frame numbers, seconds and timestamps, both on the
+/// decode thread and for playback.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FrameTiming {
+    pub frame_rate: (u32, u32), // Exact rational, e.g. (24000, 1001)
+    pub time_base: ffmpeg::Rational,
+}
+
+impl FrameTiming {
+    /// Convert a number of frames to seconds, frame n starts at `frames_to_secs(n - 1)`
+    pub fn frames_to_secs(&self, frames: u64) -> f64 {
+        let (numerator, denominator) = self.frame_rate;
+        (frames as u128 * denominator as u128) as f64 / numerator as f64
+    }
+
+    /// Number of the first frame at or after a time
+    ///
+    /// Allows for rounding errors so a time computed from a frame number lands
+    /// on that frame. Frames are numbered from 1 as when opening the stream,
+    /// frame n starts at (n - 1) / fps.
+    pub fn frame_at_time(&self, time_s: f64) -> u64 {
+        let (numerator, denominator) = self.frame_rate;
+        let frame_position = time_s * numerator as f64 / denominator as f64;
+        (frame_position - FRAME_POSITION_TOLERANCE).ceil().max(0.0) as u64 + 1
+    }
+
+    /// Count the frames of a video from the stream duration in the time base
+    ///
+    /// Uses integer math, so NTSC rates such as 24000/1001 don't round up to
+    /// an extra frame. A partial last frame counts.
+    pub fn count_frames(&self, duration: i64) -> u64 {
+        let (numerator, denominator) = self.frame_rate;
+
+        // duration * time base * frame rate, rounded up
+        let ticks =
+            duration.max(0) as i128 * self.time_base.numerator().max(0) as i128 * numerator as i128;
+        let ticks_per_frame = (self.time_base.denominator().max(1) as i128) * denominator as i128;
+        ((ticks + ticks_per_frame - 1) / ticks_per_frame) as u64
+    }
+
+    /// Convert a time in seconds to a timestamp in the video stream's time base
+    pub fn time_to_ts(&self, time_s: f64) -> i64 {
+        (time_s * self.time_base.denominator() as f64 / self.time_base.numerator().max(1) as f64)
+            as i64
+    }
+
+    /// Convert a timestamp back to seconds, the inverse of `time_to_ts`
+    pub fn ts_to_time(&self, ts: i64) -> f64 {
+        ts as f64 * self.time_base.numerator() as f64 / self.time_base.denominator().max(1) as f64
+    }
+}
 
 impl VideoStream {
     /// Create a new video stream from the specified path and options
@@ -60,13 +616,19 @@ impl VideoStream {
         ffmpeg::init()?;
 
         info!("Loading video from: {}", options.video_path);
-        let mut format_context = ffmpeg::format::input(&options.video_path)?;
+        let format_context = match options.image_sequence_fps {
+            Some(fps) => Self::open_image_sequence(&options.video_path, fps)?,
+            None => ffmpeg::format::input(&options.video_path)?,
+        };
 
-        // Find the best video stream
-        let video_stream = format_context
-            .streams()
-            .best(ffmpeg::media::Type::Video)
-            .ok_or(VideoError::StreamNotFound("Video stream not found"))?;
+        // Find the requested or best video stream
+        let video_streams = video_stream_infos(&format_context);
+        let video_stream = select_video_stream(&format_context, options.stream_index)?;
+        debug!(
+            "Playing video stream {} of {}",
+            video_stream.index(),
+            video_streams.len()
+        );
 
         // Extract frame rate information
         let frame_rate = video_stream.rate();
@@ -75,26 +637,73 @@ impl VideoStream {
         let frame_duration = std::time::Duration::from_secs_f64(1.0 / fps);
         debug!("Frame duration: {:?}", frame_duration);
 
+        // Audio times are relative to the start of the video
+        let video_start = match video_stream.start_time() {
+            ffmpeg::ffi::AV_NOPTS_VALUE => 0.0,
+            start_time => start_time as f64 * f64::from(video_stream.time_base()),
+        };
+
         // Get stream details
         let video_stream_index = video_stream.index();
         let parameters = video_stream.parameters();
-
-        // Seek to start frame
-        let time_s = ((options.start_frame - 1) as f64 / fps) as f64;
-        let timestamp = (time_s * AV_TIME_BASE as f64) as i64;
+        let timing = FrameTiming {
+            // Falls back to 30 fps if the stream has no valid rate
+            frame_rate: if frame_rate.numerator() > 0 && frame_rate.denominator() > 0 {
+                (
+                    frame_rate.numerator() as u32,
+                    frame_rate.denominator() as u32,
+                )
+            } else {
+                (DEFAULT_FPS, 1)
+            },
+            time_base: video_stream.time_base(),
+        };
+        let duration = video_stream.duration();
+        let duration_frames = timing.count_frames(duration);
+        debug!("Video has {} frames", duration_frames);
+        let end_frame = clamp_end_frame(options.start_frame, options.end_frame, duration_frames)?;
+
+        // VP9 stores alpha as side data that only the libvpx decoder reads
+        let metadata = video_stream.metadata();
+        let vp9_alpha =
+            parameters.id() == ffmpeg::codec::Id::VP9 && metadata.get("alpha_mode") == Some("1");
+
+        // Set up decoder, a thread count of 0 is ffmpeg's automatic choice
+        let mut context = ffmpeg::codec::Context::from_parameters(parameters)?;
+        let mut threading = ffmpeg::threading::Config::kind(ffmpeg::threading::Type::Frame);
+        threading.count = options.decode_threads.unwrap_or(0);
+        context.set_threading(threading);
         debug!(
-            "Seeking to timestamp: {:?} (start frame: {})",
-            timestamp, options.start_frame
+            "Decoder threads: {:?} (None is automatic)",
+            options.decode_threads
         );
-        format_context.seek(timestamp, timestamp..)?;
-
-        // Set up decoder
-        let context = ffmpeg::codec::Context::from_parameters(parameters)?;
-        let mut decoder = context.decoder().video()?;
+        let hw_accel = options
+            .hw_accel
+            .filter(|&hw_accel| Self::attach_hw_device(&mut context, hw_accel));
+        let alpha_decoder = vp9_alpha
+            .then(|| ffmpeg::decoder::find_by_name("libvpx-vp9"))
+            .flatten();
+        let mut decoder = match alpha_decoder {
+            Some(codec) => {
+                debug!("Decoding VP9 with alpha using libvpx-vp9");
+                context.decoder().open_as(codec)?.video()?
+            }
+            None => {
+                if vp9_alpha {
+                    warn!("VP9 video has alpha but libvpx-vp9 is not available, decoding opaque");
+                }
+                context.decoder().video()?
+            }
+        };
 
-        // Get color space information
-        let color_space = decoder.color_space();
-        debug!("Detected color space: {:?}", color_space);
+        // Get color information before overriding it on the decoder
+        let mut color_info = ColorInfo {
+            space: decoder.color_space().into(),
+            primaries: decoder.color_primaries().into(),
+            transfer: decoder.color_transfer_characteristic().into(),
+            range: decoder.color_range().into(),
+        };
+        debug!("Detected color info: {:?}", color_info);
 
         // Log detailed input format information
         info!(
@@ -109,170 +718,463 @@ impl VideoStream {
         // Initialize timing and frame objects
         let now = Instant::now();
         let yuv_frame = ffmpeg::frame::Video::empty();
+        let bit_rate = format_context.bit_rate();
+
+        let video_metadata = VideoMetadata {
+            container: format_context.format().name().to_owned(),
+            codec: decoder.id().name().to_owned(),
+            codec_long: decoder
+                .codec()
+                .map(|codec| codec.description().to_owned())
+                .unwrap_or_default(),
+            width: decoder.width(),
+            height: decoder.height(),
+            pixel_format: decoder
+                .format()
+                .descriptor()
+                .map(|descriptor| descriptor.name().to_owned())
+                .unwrap_or_else(|| format!("{:?}", decoder.format())),
+            color_info,
+            frame_rate: timing.frame_rate,
+            duration: (duration > 0)
+                .then(|| Duration::from_secs_f64(duration as f64 * f64::from(timing.time_base))),
+            bit_rate: (bit_rate > 0).then_some(bit_rate as u64),
+        };
+        debug!("Video metadata: {:?}", video_metadata);
 
-        // Create scaler for pixel format conversion
-        let scaler = ffmpeg::software::scaling::Context::get(
-            decoder.format(),
+        // Fail early for formats the scaler can't convert, rather than on the first frame
+        validate_pixel_format(decoder.format())?;
+
+        // Keep the alpha channel of formats that have one
+        let has_alpha = has_alpha_channel(decoder.format());
+        // Keep the precision of 10-bit and higher sources, e.g. HDR
+        let high_bit_depth = is_high_bit_depth(decoder.format());
+        let output_format = if has_alpha || high_bit_depth {
+            OutputPixelFormat::Yuv420p
+        } else {
+            options.output_pixel_format
+        };
+        let scaler_format = match output_format {
+            OutputPixelFormat::Yuv420p if high_bit_depth && has_alpha => {
+                ffmpeg::format::Pixel::YUVA420P16LE
+            }
+            OutputPixelFormat::Yuv420p if high_bit_depth => ffmpeg::format::Pixel::YUV420P16LE,
+            OutputPixelFormat::Yuv420p if has_alpha => ffmpeg::format::Pixel::YUVA420P,
+            OutputPixelFormat::Yuv420p => ffmpeg::format::Pixel::YUV420P,
+            OutputPixelFormat::Nv12 => ffmpeg::format::Pixel::NV12,
+        };
+        debug!(
+            "Has alpha: {}, high bit depth: {}, output format: {:?}",
+            has_alpha, high_bit_depth, scaler_format
+        );
+
+        // Create scaler for pixel format conversion, and downscaling to the target size
+        let (width, height) = options.target_scale.output_size(
             decoder.width(),
             decoder.height(),
-            ffmpeg::format::Pixel::YUV420P,
+            options.target_size,
+        );
+        if (width, height) != (decoder.width(), decoder.height()) {
+            debug!(
+                "Scaling frames from {}x{} to {}x{}",
+                decoder.width(),
+                decoder.height(),
+                width,
+                height
+            );
+        }
+        let scaler = ffmpeg::software::scaling::Context::get(
+            decoder.format(),
             decoder.width(),
             decoder.height(),
+            scaler_format,
+            width,
+            height,
             ffmpeg::software::scaling::Flags::BITEXACT |    // Ensure exact conversion
             ffmpeg::software::scaling::Flags::ACCURATE_RND, // Use accurate rounding
         )?;
 
-        // Set color space properties for accurate color reproduction
-        unsafe {
-            (*decoder.as_mut_ptr()).colorspace = AVColorSpace::AVCOL_SPC_BT709;
-            (*decoder.as_mut_ptr()).color_primaries = AVColorPrimaries::AVCOL_PRI_BT709;
-            (*decoder.as_mut_ptr()).color_trc = AVColorTransferCharacteristic::AVCOL_TRC_BT709;
-            (*decoder.as_mut_ptr()).color_range = AVColorRange::AVCOL_RANGE_MPEG;
+        // Untagged video gets the matrix of its size, as in other players
+        color_info.space = color_info.space.or_default_for_height(decoder.height());
+
+        // The scaler converts JPEG range formats to limited range, other formats keep their range
+        if matches!(
+            decoder.format(),
+            ffmpeg::format::Pixel::YUVJ420P
+                | ffmpeg::format::Pixel::YUVJ422P
+                | ffmpeg::format::Pixel::YUVJ444P
+        ) {
+            color_info.range = ColorRange::Limited;
+        }
+
+        // Override the color properties for sources with unreliable metadata
+        if options.assume_bt709 {
+            debug!("Assuming BT.709 limited range instead of detected color info");
+            unsafe {
+                (*decoder.as_mut_ptr()).colorspace = AVColorSpace::AVCOL_SPC_BT709;
+                (*decoder.as_mut_ptr()).color_primaries = AVColorPrimaries::AVCOL_PRI_BT709;
+                (*decoder.as_mut_ptr()).color_trc = AVColorTransferCharacteristic::AVCOL_TRC_BT709;
+                (*decoder.as_mut_ptr()).color_range = AVColorRange::AVCOL_RANGE_MPEG;
+            }
+            color_info = ColorInfo {
+                space: ColorSpace::Bt709,
+                primaries: ColorPrimaries::Bt709,
+                transfer: ColorTransfer::Bt709,
+                range: ColorRange::Limited,
+            };
         }
 
         // Create output buffer with appropriate capacity
-        let frame_buffer = Vec::with_capacity(Self::calculate_buffer_size(&decoder));
+        let frame_buffer = Vec::with_capacity(Self::calculate_buffer_size(
+            width,
+            height,
+            has_alpha,
+            high_bit_depth,
+        ));
+
+        // Audio is optional, play the video silently if it can't be opened
+        let audio = if options.audio {
+            AudioTrack::open(&format_context, video_start).unwrap_or_else(|e| {
+                warn!("Playing without audio: {}", e);
+                None
+            })
+        } else {
+            None
+        };
+        let queue_size = options.queue_size.max(1);
 
-        // Initialize the video stream object
-        let mut decoder = Self {
+        // Decode from the start frame on a dedicated thread
+        let audio_output = audio.as_ref().map(|audio| audio.output().clone());
+        let decode_thread = DecodeThread::spawn(FrameDecoder {
             decoder,
             format_context,
             video_stream_index,
-            current_frame: options.start_frame,
+            timing,
+            next_frame: options.start_frame,
+            end_frame,
+            end_of_file: false,
+            max_read_retries: options.max_read_retries,
+            max_corrupt_packets: options.max_corrupt_packets,
+            repeat_frame_on_corrupt: options.repeat_frame_on_corrupt,
+            consecutive_corrupt: 0,
+            has_alpha,
+            high_bit_depth,
+            output_format,
+            color_range: color_info.range,
+            frame_buffer,
+            yuv_frame,
+            scaler,
+            audio,
+            pending_packets: VecDeque::new(),
+            resumed_frame: None,
+            stats: Default::default(),
+        })?;
+
+        // Initialize the video stream object
+        let mut stream = Self {
+            id: NEXT_VIDEO_ID.fetch_add(1, Ordering::Relaxed),
+            owner: Arc::default(),
+            decode_thread,
+            video_stream_index,
+            width,
+            height,
+            timing,
+            duration,
+            bit_rate,
+            decoded_frame: options.start_frame,
             frame_timer: now,
+            playback_speed: 1.0,
             start_frame: options.start_frame,
-            end_frame: options.end_frame,
+            end_frame,
+            duration_frames,
+            end_of_file: false,
             looping: false,
             presentation_queue: VecDeque::new(),
-            max_queue_size: DEFAULT_QUEUE_SIZE,
+            queue_size,
+            max_queue_size: queue_size,
+            adaptive_queue_size: options.adaptive_queue_size,
+            decode_latency: Duration::ZERO,
             is_playing: true,
-            color_space,
-            yuv_frame,
-            scaler,
-            frame_buffer,
+            buffering: false,
+            previewing: false,
+            pending_preview: None,
+            underruns: 0,
+            seeks: 0,
+            loops: 0,
+            presented: false,
+            last_error: None,
+            reported_state: PlaybackState::Opening,
+            logged_frame: None,
+            video_streams,
+            has_alpha,
+            high_bit_depth,
+            color_info,
+            hw_accel,
+            audio: audio_output,
+            metadata: video_metadata,
         };
 
-        // Pre-buffer frames to fill the queue
-        decoder.pre_buffer_with_seek(None)?;
+        // Wait for the first frame, the rest of the queue is filled in the background.
+        // Later start frames are decoded from the keyframe before them, so the
+        // first frame shown is the start frame itself.
+        if stream.start_frame > 1 {
+            debug!("Seeking to start frame {}", stream.start_frame);
+            stream.seek(stream.start_time().as_secs_f64(), SeekMode::Accurate)?;
+        } else {
+            stream.receive_frame()?;
+            stream.buffering = !stream.reached_end();
+        }
         info!("Video stream initialized successfully");
 
-        Ok(decoder)
+        Ok(stream)
     }
 
-    /// Calculate the required buffer size for a frame in YUV420P format
-    fn calculate_buffer_size(decoder: &ffmpeg::decoder::Video) -> usize {
-        let width = decoder.width() as usize;
-        let height = decoder.height() as usize;
-
-        // For YUV420P:
-        // Y plane: width * height
-        // U and V planes: (width/2) * (height/2) each, interleaved
-        let y_size = width * height;
-        let uv_size = width * height / 2; // This accounts for both U and V interleaved
+    /// Create a new video stream without blocking the calling thread
+    ///
+    /// Opening, seeking and pre-buffering run on a dedicated thread, so the
+    /// returned future can be handed to an `iced::Task` while the UI shows a
+    /// loading state:
+    ///
+    /// ```ignore
+    /// Task::perform(VideoStream::new_async(options), Message::VideoLoaded)
+    /// ```
+    pub fn new_async(
+        options: VideoStreamOptions,
+    ) -> impl Future<Output = Result<Self, VideoError>> + Send + 'static {
+        let (sender, receiver) = oneshot::channel();
+
+        std::thread::spawn(move || {
+            let _ = sender.send(Self::new(options));
+        });
 
-        y_size + uv_size // Total size needed
+        async move {
+            receiver
+                .await
+                .map_err(|_| VideoError::Load("Video loading thread terminated".into()))?
+        }
     }
 
-    /// Get the video stream from the format context
-    fn get_video_stream(&self) -> Result<ffmpeg::Stream, VideoError> {
-        self.format_context
-            .streams()
-            .best(ffmpeg::media::Type::Video)
-            .ok_or(VideoError::StreamNotFound("Video stream not found"))
+    /// Create a hardware device for the decoder, returns whether it succeeded
+    ///
+    /// The decoder picks the hardware pixel format and creates the frames
+    /// context itself once a device is attached.
+    fn attach_hw_device(context: &mut ffmpeg::codec::Context, hw_accel: HwAccelType) -> bool {
+        let mut device = std::ptr::null_mut();
+        let result = unsafe {
+            av_hwdevice_ctx_create(
+                &mut device,
+                hw_accel.device_type(),
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+        if result < 0 {
+            warn!(
+                "Failed to create {:?} device, decoding in software: {}",
+                hw_accel,
+                ffmpeg::Error::from(result)
+            );
+            return false;
+        }
+
+        // The codec context takes ownership of the device reference
+        unsafe { (*context.as_mut_ptr()).hw_device_ctx = device };
+        info!("Decoding with {:?}", hw_accel);
+        true
     }
 
-    /// Process a decoded frame into planar YUV420 format
-    fn process_video_frame(&mut self, frame: &ffmpeg::frame::Video) -> Result<Vec<u8>, VideoError> {
-        self.frame_buffer.clear();
+    /// Get the hardware decoding backend in use, `None` when decoding in software
+    pub fn hw_accel(&self) -> Option<HwAccelType> {
+        self.hw_accel
+    }
 
-        // Preserve color properties in output frame
-        unsafe {
-            (*self.yuv_frame.as_mut_ptr()).colorspace = AVColorSpace::AVCOL_SPC_BT709;
-            (*self.yuv_frame.as_mut_ptr()).color_primaries = AVColorPrimaries::AVCOL_PRI_BT709;
-            (*self.yuv_frame.as_mut_ptr()).color_trc =
-                AVColorTransferCharacteristic::AVCOL_TRC_BT709;
-            (*self.yuv_frame.as_mut_ptr()).color_range = AVColorRange::AVCOL_RANGE_MPEG;
+    /// Open an image sequence with the `image2` demuxer at a fixed frame rate
+    fn open_image_sequence(
+        pattern: &str,
+        fps: f64,
+    ) -> Result<ffmpeg::format::context::Input, VideoError> {
+        if !fps.is_finite() || fps <= 0.0 {
+            return Err(VideoError::Load(format!(
+                "Invalid image sequence frame rate: {}",
+                fps
+            )));
         }
 
-        // Convert frame format if needed
-        self.scaler.run(frame, &mut self.yuv_frame)?;
+        let pattern_type = if is_glob_pattern(pattern) {
+            "glob"
+        } else {
+            "sequence"
+        };
+        info!(
+            "Opening image sequence: {} ({} pattern at {} fps)",
+            pattern, pattern_type, fps
+        );
 
-        // Copy Y plane directly (full resolution)
-        let y_plane = self.yuv_frame.data(0);
-        self.frame_buffer.extend_from_slice(y_plane);
+        let mut options = ffmpeg::Dictionary::new();
+        options.set("framerate", &fps.to_string());
+        options.set("pattern_type", pattern_type);
 
-        // Interleave U and V planes (half resolution)
-        let width = self.decoder.width() as usize;
-        let height = self.decoder.height() as usize;
-        let uv_width = width / 2;
-        let uv_height = height / 2;
+        Ok(ffmpeg::format::input_with_dictionary(&pattern, options)?)
+    }
 
-        for y in 0..uv_height {
-            let u_line = &self.yuv_frame.data(1)[y * uv_width..(y + 1) * uv_width];
-            let v_line = &self.yuv_frame.data(2)[y * uv_width..(y + 1) * uv_width];
+    /// Calculate the required buffer size for a queued frame
+    ///
+    /// Every `OutputPixelFormat` is queued as the same Y plus interleaved UV layout.
+    fn calculate_buffer_size(
+        width: u32,
+        height: u32,
+        has_alpha: bool,
+        high_bit_depth: bool,
+    ) -> usize {
+        let (uv_width, uv_height) = chroma_size(width, height);
 
-            for x in 0..uv_width {
-                self.frame_buffer.push(u_line[x]);
-                self.frame_buffer.push(v_line[x]);
-            }
-        }
+        // For YUV420P:
+        // Y plane: width * height
+        // U and V planes: ceil(width/2) * ceil(height/2) each, interleaved
+        // Alpha plane: width * height, only for videos with alpha
+        // Each sample is 2 bytes for high bit depth videos
+        let y_size = width as usize * height as usize;
+        let uv_size = uv_width as usize * uv_height as usize * 2;
+        let alpha_size = if has_alpha { y_size } else { 0 };
+        let sample_size = if high_bit_depth { 2 } else { 1 };
+
+        (y_size + uv_size + alpha_size) * sample_size // Total size needed
+    }
 
-        trace!(
-            "Processed frame with size: {} bytes",
-            self.frame_buffer.len()
-        );
-        Ok(self.frame_buffer.clone())
+    /// Get the playable video streams of the file, e.g. camera angles
+    ///
+    /// Pass an `index` as `VideoStreamOptions::stream_index` to play another stream.
+    pub fn available_video_streams(&self) -> &[StreamInfo] {
+        &self.video_streams
     }
 
-    /// Add a decoded frame to the presentation queue
-    fn add_frame_to_queue(&mut self, frame: ffmpeg::frame::Video) -> Result<(), VideoError> {
-        let combined = self.process_video_frame(&frame)?;
+    /// Get the container index of the video stream being played
+    pub fn stream_index(&self) -> usize {
+        self.video_stream_index
+    }
 
-        debug!(
-            "Adding frame {} to queue (queue size: {}/{})",
-            self.current_frame,
-            self.presentation_queue.len(),
-            self.max_queue_size
-        );
+    /// Check if the video has an alpha channel, e.g. ProRes 4444 or VP9 with alpha
+    ///
+    /// Frames of such videos carry a full resolution alpha plane after the UV
+    /// plane, and transparent areas show the background behind the player.
+    pub fn has_alpha(&self) -> bool {
+        self.has_alpha
+    }
 
-        self.presentation_queue.push_back(QueuedFrame {
-            data: combined,
-            frame_number: self.current_frame,
-        });
+    /// Check if the video has more than 8 bits per sample, e.g. 10-bit HDR
+    ///
+    /// Frames of such videos carry 16-bit little endian samples in every plane,
+    /// scaled to the full 16-bit range.
+    pub fn is_high_bit_depth(&self) -> bool {
+        self.high_bit_depth
+    }
 
-        self.current_frame += 1;
+    /// Move the frames decoded in the background into the queue, without waiting
+    fn receive_frames(&mut self) -> Result<(), VideoError> {
+        while self.presentation_queue.len() < self.max_queue_size {
+            match self.decode_thread.try_recv()? {
+                Some(decoded) => {
+                    self.handle_decoded(decoded)?;
+                }
+                None => break,
+            }
+        }
         Ok(())
     }
 
-    /// Get the next frame from the queue or decode if needed
+    /// Wait for the decode thread to queue the next frame
+    ///
+    /// Returns `false` if there are no more frames to decode.
+    fn receive_frame(&mut self) -> Result<bool, VideoError> {
+        while !self.reached_end() && !self.decode_thread.is_finished() {
+            let decoded = self.decode_thread.recv()?;
+            if self.handle_decoded(decoded)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Apply a message from the decode thread, returns whether a frame was queued
+    fn handle_decoded(&mut self, decoded: Decoded) -> Result<bool, VideoError> {
+        match decoded {
+            Decoded::Frame(frame, latency) => {
+                debug!(
+                    "Adding frame {} to queue (queue size: {}/{})",
+                    frame.frame_number,
+                    self.presentation_queue.len(),
+                    self.max_queue_size
+                );
+                if let Some(latency) = latency {
+                    self.record_decode_latency(latency);
+                }
+                self.decoded_frame = frame.frame_number + 1;
+                self.presentation_queue.push_back(frame);
+                Ok(true)
+            }
+            Decoded::End => {
+                debug!("Decoder drained at frame {}", self.decoded_frame);
+                self.end_of_file = true;
+                Ok(false)
+            }
+            Decoded::Failed(e) => Err(e),
+            Decoded::Grabbed(_) => {
+                warn!("Dropping grabbed frame nobody is waiting for");
+                Ok(false)
+            }
+        }
+    }
+
+    /// Get the next frame from the queue, taking the frames decoded in the meantime
     pub fn next_frame(&mut self) -> Result<Option<Vec<u8>>, VideoError> {
         trace!("Retrieving next frame");
+        self.receive_frames()?;
+
+        // The queue ran dry during playback, buffer before presenting more frames
+        if self.presentation_queue.is_empty() && !self.reached_end() && !self.buffering {
+            self.underruns += 1;
+            warn!(
+                "Frame queue underrun at frame {} ({} total), buffering",
+                self.decoded_frame, self.underruns
+            );
+            self.buffering = true;
+        }
+
+        if self.buffering {
+            return self.refill_queue();
+        }
 
-        // Fill the queue if empty
-        if self.presentation_queue.is_empty() {
-            debug!("Frame queue empty, filling buffer");
-            while self.presentation_queue.len() < self.max_queue_size {
-                self.decode_next_frame()?;
+        // Hold the last frame on screen once the end has been reached, or
+        // start over once it has been shown for a frame when looping
+        if self.presentation_queue.len() == 1 && self.reached_end() {
+            if self.looping && self.should_process_frame() {
+                debug!(
+                    "End of stream reached, looping to frame {}",
+                    self.start_frame
+                );
+                self.seek(
+                    self.frames_to_secs(self.start_frame.saturating_sub(1)),
+                    SeekMode::Accurate,
+                )?;
+                self.loops += 1;
+                return Ok(self.presentation_queue.front().map(|f| f.data.clone()));
             }
+            trace!("End of stream reached, holding last frame");
+            return Ok(self.presentation_queue.front().map(|f| f.data.clone()));
         }
 
         // If we have frames and it's time to show the next one
         if !self.presentation_queue.is_empty() && self.should_process_frame() {
             debug!(
                 "Processing frame {} from queue (queue size: {}/{})",
-                self.current_frame,
+                self.current_frame(),
                 self.presentation_queue.len(),
                 self.max_queue_size
             );
 
-            // Get the next frame
+            // Get the next frame, the decode thread refills the queue meanwhile
             let frame = self.presentation_queue.pop_front().map(|f| f.data);
-
-            // Try to keep buffer full
-            if self.presentation_queue.len() < self.max_queue_size {
-                let _ = self.decode_next_frame()?;
-            }
-
             return Ok(frame);
         }
 
@@ -280,11 +1182,51 @@ impl VideoStream {
         Ok(self.presentation_queue.front().map(|f| f.data.clone()))
     }
 
+    /// Hold the current frame while the decode thread refills the queue
+    ///
+    /// Frames are not advanced until the queue is full again (or the end has been
+    /// reached), the frames decoded meanwhile are taken on every update.
+    fn refill_queue(&mut self) -> Result<Option<Vec<u8>>, VideoError> {
+        if self.presentation_queue.len() >= self.max_queue_size || self.reached_end() {
+            debug!(
+                "Buffering finished with {} frames queued, resuming playback",
+                self.presentation_queue.len()
+            );
+            self.buffering = false;
+            // Don't try to catch up on the frames missed while buffering
+            self.frame_timer = Instant::now();
+        } else {
+            trace!(
+                "Buffering: {}/{} frames queued",
+                self.presentation_queue.len(),
+                self.max_queue_size
+            );
+        }
+
+        Ok(self.get_current_frame())
+    }
+
     /// Determine if it's time to process the next frame based on timing
+    ///
+    /// While audio is playing the next frame is due once the audio reaches it,
+    /// otherwise frames are timed by the frame rate and playback speed.
     pub fn should_process_frame(&mut self) -> bool {
         let now = Instant::now();
+
+        if let Some(clock) = self.audio_clock() {
+            let due = self.presentation_queue.front().map_or(true, |frame| {
+                self.frames_to_secs(frame.frame_number.saturating_sub(1)) <= clock
+            });
+            if due {
+                trace!("Time to process frame: audio clock={:.3}s", clock);
+                // Continue from here if the audio stops
+                self.frame_timer = now;
+            }
+            return due;
+        }
+
         let elapsed = now.duration_since(self.frame_timer);
-        let frame_duration = Duration::from_secs_f64(1.0 / self.get_fps());
+        let frame_duration = self.get_frame_duration();
 
         if elapsed >= frame_duration {
             // Update timer by exact frame duration to prevent drift
@@ -320,211 +1262,529 @@ impl VideoStream {
     }
 
     /// Get the current frame for display without advancing
-    fn get_current_frame(&self) -> Option<Vec<u8>> {
-        static mut LAST_FRAME_NUMBER: u64 = 0;
+    fn get_current_frame(&mut self) -> Option<Vec<u8>> {
+        let frame = self.presentation_queue.front()?;
+        // Only log if frame number changed (to reduce log spam)
+        if self.logged_frame != Some(frame.frame_number) {
+            trace!(
+                "Current frame: {} (previous: {:?})",
+                frame.frame_number,
+                self.logged_frame
+            );
+            self.logged_frame = Some(frame.frame_number);
+        }
+        Some(frame.data.clone())
+    }
 
-        if let Some(frame) = self.presentation_queue.front() {
-            // Only log if frame number changed (to reduce log spam)
-            unsafe {
-                if LAST_FRAME_NUMBER != frame.frame_number {
-                    trace!(
-                        "Current frame: {} (previous: {})",
-                        frame.frame_number,
-                        LAST_FRAME_NUMBER
-                    );
-                    LAST_FRAME_NUMBER = frame.frame_number;
-                }
+    /// Convert the current frame to packed RGB24 on the CPU
+    ///
+    /// This doesn't depend on the GPU pipeline, so it can be used for thumbnails
+    /// and tests on systems without a usable adapter. Returns the pixels with the
+    /// frame width and height, or `None` if no frame has been decoded yet.
+    pub fn frame_as_rgb(&self) -> Option<(Vec<u8>, u32, u32)> {
+        let frame = self.presentation_queue.front()?;
+        match Self::convert_to_rgb(
+            &frame.data,
+            self.width,
+            self.height,
+            self.high_bit_depth,
+            self.color_info,
+        ) {
+            Ok(rgb) => Some(rgb),
+            Err(e) => {
+                warn!(
+                    "Failed to convert frame {} to RGB: {:?}",
+                    frame.frame_number, e
+                );
+                None
             }
-            Some(frame.data.clone())
-        } else {
-            None
         }
     }
 
-    /// Decode the next frame from the video stream
-    fn decode_next_frame(&mut self) -> Result<(), VideoError> {
-        // Skip if queue is already full
-        if self.presentation_queue.len() >= self.max_queue_size {
-            return Ok(());
+    /// Convert queued frame data (Y plane followed by interleaved UV) to RGB24
+    ///
+    /// Uses the matrix and range of `color_info`, like the video shaders.
+    fn convert_to_rgb(
+        data: &[u8],
+        width: u32,
+        height: u32,
+        high_bit_depth: bool,
+        color_info: ColorInfo,
+    ) -> Result<(Vec<u8>, u32, u32), VideoError> {
+        let (uv_width, uv_height) = chroma_size(width, height);
+        // High bit depth frames match P016 instead of NV12
+        let (pixel_format, sample_size) = if high_bit_depth {
+            (ffmpeg::format::Pixel::P016LE, 2)
+        } else {
+            (ffmpeg::format::Pixel::NV12, 1)
+        };
+        let y_size = width as usize * height as usize * sample_size;
+        let uv_size = uv_width as usize * uv_height as usize * 2 * sample_size;
+
+        if data.len() < y_size + uv_size {
+            return Err(VideoError::FrameProcessing(format!(
+                "Frame data is {} bytes, expected {}",
+                data.len(),
+                y_size + uv_size
+            )));
         }
 
-        let mut packets_sent = 0;
-        let mut frame = ffmpeg::frame::Video::empty();
-
-        loop {
-            // Prevent infinite loop by limiting packet processing
-            if packets_sent >= MAX_PACKETS_PER_FRAME {
-                return Err(VideoError::Decode(
-                    "Too many packets sent without decoding frame".into(),
-                ));
+        // The queued layout matches NV12, copy it row by row into the padded planes
+        let mut nv12 = ffmpeg::frame::Video::new(pixel_format, width, height);
+        let planes = [
+            (
+                &data[..y_size],
+                width as usize * sample_size,
+                height as usize,
+            ),
+            (
+                &data[y_size..y_size + uv_size],
+                uv_width as usize * 2 * sample_size,
+                uv_height as usize,
+            ),
+        ];
+        for (index, (plane, row_size, rows)) in planes.into_iter().enumerate() {
+            let stride = nv12.stride(index);
+            let target = nv12.data_mut(index);
+            for y in 0..rows {
+                target[y * stride..y * stride + row_size]
+                    .copy_from_slice(&plane[y * row_size..(y + 1) * row_size]);
             }
+        }
 
-            match self.decoder.receive_frame(&mut frame) {
-                Ok(_) => {
-                    trace!(
-                        "Decoded frame - PTS: {}, Timestamp: {}",
-                        frame.pts().unwrap_or(-1),
-                        frame.timestamp().unwrap_or(-1),
-                    );
-                    self.add_frame_to_queue(frame)?;
-                    return Ok(());
-                }
-                Err(ffmpeg::Error::Other { errno: EAGAIN }) => {
-                    // Need more input data
-                    if let Some((stream, packet)) = self.format_context.packets().next() {
-                        if stream.index() == self.video_stream_index {
-                            self.decoder.send_packet(&packet)?;
-                            packets_sent += 1;
-                        }
-                    } else {
-                        // End of stream, flush decoder
-                        self.decoder.send_packet(&ffmpeg::Packet::empty())?;
-                        return Ok(());
-                    }
-                }
-                Err(e) => return Err(VideoError::Decode(e.to_string())),
-            }
+        let mut scaler = ffmpeg::software::scaling::Context::get(
+            pixel_format,
+            width,
+            height,
+            ffmpeg::format::Pixel::RGB24,
+            width,
+            height,
+            ffmpeg::software::scaling::Flags::BILINEAR
+                | ffmpeg::software::scaling::Flags::ACCURATE_RND,
+        )?;
+
+        // Queued frames keep the range of the source, expand it to full range RGB
+        let full_range = color_info.range == ColorRange::Full;
+        unsafe {
+            let coefficients = ffmpeg::sys::sws_getCoefficients(sws_color_space(color_info.space));
+            ffmpeg::sys::sws_setColorspaceDetails(
+                scaler.as_mut_ptr(),
+                coefficients,
+                full_range as i32, // Range of the queued frame
+                coefficients,
+                1, // Full range output
+                0,
+                1 << 16,
+                1 << 16,
+            );
+        }
+
+        let mut rgb_frame = ffmpeg::frame::Video::empty();
+        scaler.run(&nv12, &mut rgb_frame)?;
+
+        // Pack the rows without the line padding
+        let row_size = width as usize * 3;
+        let stride = rgb_frame.stride(0);
+        let mut rgb = Vec::with_capacity(row_size * height as usize);
+        for y in 0..height as usize {
+            rgb.extend_from_slice(&rgb_frame.data(0)[y * stride..y * stride + row_size]);
         }
+
+        Ok((rgb, width, height))
     }
 
-    /// Pre-buffer frames starting from current position or a target timestamp
-    fn pre_buffer_with_seek(&mut self, target_ts: Option<i64>) -> Result<(), VideoError> {
-        debug!("Pre-buffering frames...");
-
-        // If we have a target timestamp, we need to find that frame first
-        if let Some(target_ts) = target_ts {
-            let mut found_target = false;
-            while !found_target {
-                let mut frame = ffmpeg::frame::Video::empty();
-                match self.decoder.receive_frame(&mut frame) {
-                    Ok(_) => {
-                        let pts = frame.pts().unwrap_or(-1);
-                        if pts >= target_ts {
-                            // Process frame and add to queue
-                            let combined = self.process_video_frame(&frame)?;
-                            self.presentation_queue.push_back(QueuedFrame {
-                                data: combined,
-                                frame_number: self.current_frame,
-                            });
-                            found_target = true;
-                            debug!("Found target frame at PTS: {}", pts);
-                        }
-                    }
-                    Err(ffmpeg::Error::Other { errno: EAGAIN }) => {
-                        match self.format_context.packets().next() {
-                            Some((stream, packet)) if stream.index() == self.video_stream_index => {
-                                self.decoder.send_packet(&packet)?;
-                            }
-                            None => {
-                                // End of file reached during seek
-                                if !found_target {
-                                    debug!(
-                                        "Reached end of file during seek without finding target"
-                                    );
-                                    // If we haven't found our target frame, it means we're seeking past the end
-                                    return Ok(());
-                                }
-                                break;
-                            }
-                            _ => continue,
-                        }
-                    }
-                    Err(e) => return Err(VideoError::Decode(e.to_string())),
-                }
-            }
+    /// Check if there are no more frames to queue
+    ///
+    /// This is the case once the configured end frame has been queued or the
+    /// decode thread has drained the decoder at the end of the file.
+    fn reached_end(&self) -> bool {
+        self.end_of_file || past_end_frame(self.decoded_frame, self.end_frame)
+    }
+
+    /// Check if playback has reached the end of the stream
+    ///
+    /// This is true once the end frame (or the end of the file) has been
+    /// decoded and only the last frame is left in the queue.
+    pub fn is_end_of_stream(&self) -> bool {
+        self.reached_end() && self.presentation_queue.len() <= 1
+    }
+
+    /// Track the time a frame took to decode, adapting the queue size to it
+    fn record_decode_latency(&mut self, latency: Duration) {
+        self.decode_latency = latency.max(self.decode_latency.mul_f64(DECODE_LATENCY_DECAY));
+        if !self.adaptive_queue_size {
+            return;
         }
 
-        // Now fill the rest of the buffer
-        while self.presentation_queue.len() < self.max_queue_size {
-            self.decode_next_frame()?;
+        let target = adaptive_queue_size(
+            self.queue_size,
+            self.decode_latency,
+            self.get_frame_duration(),
+        );
+        if target != self.max_queue_size {
+            debug!(
+                "Decode latency {:?}, queue size {} -> {}",
+                self.decode_latency, self.max_queue_size, target
+            );
+            self.max_queue_size = target;
         }
+    }
 
-        info!("Pre-buffered {} frames", self.presentation_queue.len());
-        Ok(())
+    /// Set the number of decoded frames kept ahead, at least 1
+    ///
+    /// A larger queue is filled as playback continues. Frames already queued
+    /// beyond a smaller size are still shown, the queue just isn't refilled
+    /// until it drains below the new size.
+    pub fn set_queue_size(&mut self, size: usize) {
+        let size = size.max(1);
+        debug!("Queue size {} -> {}", self.queue_size, size);
+        self.queue_size = size;
+        self.max_queue_size = size;
+        // Adapt from scratch to the new size
+        self.decode_latency = Duration::ZERO;
+    }
+
+    /// Get the number of frames the queue is currently filled to
+    ///
+    /// Larger than the configured size while `adaptive_queue_size` is covering slow decodes.
+    pub fn queue_size(&self) -> usize {
+        self.max_queue_size
+    }
+
+    /// Get the unique ID of this stream
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Get the owner token of this stream, see [`VideoPrimitive::with_owner`](crate::video::VideoPrimitive::with_owner)
+    pub fn owner(&self) -> Weak<VideoOwner> {
+        Arc::downgrade(&self.owner)
+    }
+
+    /// Get the color properties detected for the video
+    ///
+    /// The range is the one of the decoded frames, which is limited for JPEG
+    /// range pixel formats as the scaler converts them.
+    pub fn color_info(&self) -> ColorInfo {
+        self.color_info
+    }
+
+    /// Get the codec and format details read when opening the video
+    pub fn snapshot_metadata(&self) -> VideoMetadata {
+        self.metadata.clone()
     }
 
-    /// Get the width of the video in pixels
+    /// Get the width of the decoded frames in pixels
+    ///
+    /// This is the scaled width for a `VideoStreamOptions::target_size`, the
+    /// native one is in `snapshot_metadata`.
     pub fn width(&self) -> u32 {
-        self.decoder.width() as u32
+        self.width
     }
 
-    /// Get the height of the video in pixels
+    /// Get the height of the decoded frames in pixels
     pub fn height(&self) -> u32 {
-        self.decoder.height() as u32
+        self.height
     }
 
-    /// Get the current playback time in seconds
+    /// Get the playback time of the displayed frame, relative to the start frame
     pub fn current_time(&self) -> Duration {
-        if self.current_frame() > 0 {
-            let current_second = (self.current_frame() - self.start_frame) as f64 / self.get_fps();
-            Duration::from_secs_f64(current_second)
-        } else {
-            Duration::from_secs_f64(0.)
-        }
+        let frames = self.current_frame().saturating_sub(self.start_frame);
+        Duration::from_secs_f64(self.frames_to_secs(frames))
+    }
+
+    /// Get the timecode rate of the video, drop-frame for NTSC rates
+    pub fn timecode_rate(&self) -> TimecodeRate {
+        TimecodeRate::from_fps(self.get_fps())
+    }
+
+    /// Get the current position as an SMPTE timecode (HH:MM:SS:FF)
+    pub fn current_timecode(&self) -> String {
+        format_timecode(
+            self.current_frame().saturating_sub(self.start_frame),
+            self.timecode_rate(),
+        )
+    }
+
+    /// Seek to an SMPTE timecode (HH:MM:SS:FF), relative to the start frame
+    ///
+    /// Counts from the start frame like `current_timecode`, so a displayed
+    /// timecode seeks back to the same frame.
+    pub fn seek_to_timecode(&mut self, timecode: &str) -> Result<(), VideoError> {
+        let frame = parse_timecode(timecode, self.timecode_rate())?;
+        debug!("Timecode {} is frame {} after the start", timecode, frame);
+        self.seek_to_frame(self.start_frame.saturating_add(frame))
     }
 
     /// Get the total duration of the video
     pub fn total_time(&self) -> Result<Duration, VideoError> {
-        let video_stream = self.get_video_stream()?;
-        let raw_duration = video_stream.duration();
-        let time_base = video_stream.time_base();
+        let time_base = self.timing.time_base;
 
         // Precise calculation using time base
         let seconds =
-            (raw_duration * time_base.numerator() as i64) as f64 / time_base.denominator() as f64;
+            (self.duration * time_base.numerator() as i64) as f64 / time_base.denominator() as f64;
 
         Ok(Duration::from_secs_f64(seconds))
     }
 
     /// Seek to a specific time in seconds
-    pub fn seek_to_time(&mut self, time_s: f64) -> Result<(), VideoError> {
-        let total_time = self.total_time()?.as_secs_f64();
+    ///
+    /// Times before the start or past the end are clamped, so seeking to or
+    /// past the end lands on the last decodable frame. See [`SeekMode`] for
+    /// trading precision for speed.
+    pub fn seek_to_time(&mut self, time_s: f64, mode: SeekMode) -> Result<(), VideoError> {
+        self.seek(time_s, mode)?;
+        self.seeks += 1;
+        Ok(())
+    }
+
+    /// Seek to a frame number, counting from 1
+    ///
+    /// Frames outside the start and end frame are clamped. Decodes forward from
+    /// the keyframe before the frame, so it is the current frame afterwards.
+    pub fn seek_to_frame(&mut self, frame: u64) -> Result<(), VideoError> {
+        let last_frame = self.known_end_frame().unwrap_or(u64::MAX);
+        let frame = frame.clamp(self.start_frame, last_frame.max(self.start_frame));
+        self.seek_to_time(self.frames_to_secs(frame - 1), SeekMode::Accurate)?;
+
+        // Rounding the time can land next to the frame
+        if self.current_frame() != frame {
+            self.decode_until_frame(frame)?;
+        }
+        debug!("Sought to frame {}", self.current_frame());
+        Ok(())
+    }
+
+    /// Rewind to the start frame, e.g. for a replay button after the end
+    ///
+    /// Drops the queued frames and refills the queue from the start frame,
+    /// which is the current frame afterwards. The frame timer restarts so it is
+    /// shown for a full frame. Doesn't count as a seek and keeps playing or
+    /// paused as before.
+    pub fn reset(&mut self) -> Result<(), VideoError> {
+        info!("Resetting to start frame {}", self.start_frame);
+        self.seek(
+            self.frames_to_secs(self.start_frame.saturating_sub(1)),
+            SeekMode::Accurate,
+        )?;
+        if self.current_frame() != self.start_frame {
+            self.decode_until_frame(self.start_frame)?;
+        }
+        self.frame_timer = Instant::now();
+        Ok(())
+    }
+
+    /// Show the keyframe near a time without waiting for it, e.g. while scrubbing
+    ///
+    /// Seeks like `SeekMode::Fast` on the decode thread and returns right away,
+    /// `update` shows the frame once it arrives and the last frame until then.
+    /// Requests made while a preview is decoding replace each other and the
+    /// latest is decoded next, so fast drags don't pile up seeks. Finish with
+    /// an accurate `seek_to_time` to land exactly on the time.
+    pub fn preview_at(&mut self, time_s: f64) -> Result<(), VideoError> {
+        let time_s = self.clamp_seek_time(time_s)?;
+        if self.previewing {
+            trace!("Preview in progress, previewing {:.2}s next", time_s);
+            self.pending_preview = Some(time_s);
+            return Ok(());
+        }
+        self.start_preview(time_s)
+    }
+
+    fn start_preview(&mut self, time_s: f64) -> Result<(), VideoError> {
+        trace!("Previewing time: {:.2}s", time_s);
+        self.end_of_file = false;
+        self.decode_thread.seek(time_s, SeekMode::Fast)?;
+        self.decoded_frame = self.timing.frame_at_time(time_s);
+        self.previewing = true;
+        Ok(())
+    }
+
+    /// Replace the queue with the preview frame once it arrives, then request the pending one
+    fn receive_preview(&mut self) -> Result<(), VideoError> {
+        while self.previewing {
+            let Some(decoded) = self.decode_thread.try_recv()? else {
+                break;
+            };
+            if matches!(decoded, Decoded::Frame(..)) {
+                self.presentation_queue.clear();
+            }
+            if self.handle_decoded(decoded)? || self.decode_thread.is_finished() {
+                self.previewing = false;
+            }
+        }
 
-        if time_s < 0.0 || time_s > total_time {
-            warn!("Invalid seek time: {} (total time: {})", time_s, total_time);
-            return Err(VideoError::InvalidTimestamp);
+        if !self.previewing {
+            if let Some(time_s) = self.pending_preview.take() {
+                self.start_preview(time_s)?;
+            }
         }
+        Ok(())
+    }
 
-        info!("Seeking to time: {:.2}s", time_s);
-        let stream = self.get_video_stream()?;
+    /// Seek to a time without counting it in `seeks`, e.g. for loops
+    ///
+    /// Waits for the frame at the time, the rest of the queue is filled in the background.
+    fn seek(&mut self, time_s: f64, mode: SeekMode) -> Result<(), VideoError> {
+        let time_s = self.clamp_seek_time(time_s)?;
+        info!("Seeking to time: {:.2}s ({:?})", time_s, mode);
 
-        let time_base = stream.time_base();
-        let target_ts = (time_s * time_base.denominator() as f64) as i64;
-        let fps = stream.avg_frame_rate();
-        let stream_index = stream.index() as i32;
+        // Previews still decoding are dropped with the other frames
+        self.previewing = false;
+        self.pending_preview = None;
 
-        // Clear queue and flush decoder
+        // Clear queue, frames decoded before the seek are dropped
         self.presentation_queue.clear();
-        self.decoder.flush();
+        self.end_of_file = false;
+        self.decode_thread.seek(time_s, mode)?;
 
-        // Perform the seek operation
-        unsafe {
-            ffmpeg::sys::avformat_seek_file(
-                self.format_context.as_mut_ptr(),
-                stream_index,
-                i64::MIN,
-                target_ts,
-                target_ts,
-                ffmpeg::sys::AVSEEK_FLAG_BACKWARD,
-            )
+        // The first frame at or after the target is queued
+        self.decoded_frame = self.timing.frame_at_time(time_s);
+        debug!("New decode position after seek: {}", self.decoded_frame);
+
+        self.receive_frame()?;
+        // Fill the queue before playing on
+        self.buffering = !self.reached_end();
+        self.last_error = None;
+        Ok(())
+    }
+
+    /// Check a seek time and clamp it to the video
+    fn clamp_seek_time(&self, time_s: f64) -> Result<f64, VideoError> {
+        let total_time = self.total_time()?.as_secs_f64();
+
+        // Stay within the start and end frame
+        let start_time = self.start_time().as_secs_f64();
+        let end_time = match self.end_frame {
+            Some(end_frame) => self.frames_to_secs(end_frame.saturating_sub(1)),
+            None => total_time,
         };
+        clamp_time(time_s, start_time, end_time)
+    }
+
+    /// Decode the frame at a time without changing the playback position
+    ///
+    /// Meant for thumbnails, e.g. a scrubbing filmstrip. Returns the frame in
+    /// the same layout as `next_frame`. The queued frames and timer are kept,
+    /// decoding continues after them as before, and the audio is rebuffered
+    /// from the frame on screen.
+    pub fn grab_frame_at(&mut self, time_s: f64) -> Result<Vec<u8>, VideoError> {
+        let time_s = self.clamp_seek_time(time_s)?;
+        self.decode_thread.grab(time_s, self.current_frame())?;
+
+        // Frames decoded before the grab are queued as usual until it arrives
+        loop {
+            match self.decode_thread.recv()? {
+                Decoded::Grabbed(result) => {
+                    debug!("Grabbed frame at {:.3}s", time_s);
+                    return result;
+                }
+                decoded => {
+                    self.handle_decoded(decoded)?;
+                }
+            }
+        }
+    }
 
-        // Update current frame based on time
-        self.current_frame = (time_s * fps.numerator() as f64 / fps.denominator() as f64) as u64;
-        debug!("New current frame after seek: {}", self.current_frame);
+    /// Decode up to the given frame and return its data
+    ///
+    /// Unlike playback this doesn't wait for frames to be due, which makes
+    /// decoding deterministic for tests and headless use. Frames before the
+    /// target are dropped from the queue and earlier frames are reached by
+    /// seeking, so the target stays at the front of the queue afterwards.
+    pub fn decode_until_frame(&mut self, frame_number: u64) -> Result<Vec<u8>, VideoError> {
+        let behind = self
+            .presentation_queue
+            .front()
+            .is_some_and(|frame| frame.frame_number > frame_number);
+        if behind {
+            debug!("Frame {} already dropped, seeking back", frame_number);
+            self.seek_to_time(
+                self.frames_to_secs(frame_number.saturating_sub(1)),
+                SeekMode::Accurate,
+            )?;
+        }
 
-        // Refill buffer
-        self.pre_buffer_with_seek(Some(target_ts))?;
-        Ok(())
+        loop {
+            while let Some(frame) = self.presentation_queue.front() {
+                match frame.frame_number.cmp(&frame_number) {
+                    std::cmp::Ordering::Less => {
+                        self.presentation_queue.pop_front();
+                    }
+                    std::cmp::Ordering::Equal => return Ok(frame.data.clone()),
+                    std::cmp::Ordering::Greater => {
+                        return Err(VideoError::Decode(format!(
+                            "Decoding continued at frame {} past frame {}",
+                            frame.frame_number, frame_number
+                        )));
+                    }
+                }
+            }
+
+            if !self.receive_frame()? {
+                return Err(VideoError::Decode(format!(
+                    "End of stream reached before frame {}",
+                    frame_number
+                )));
+            }
+        }
+    }
+
+    /// Advance exactly one frame and return it, pausing playback
+    ///
+    /// Does nothing at the end frame and returns the current frame instead.
+    pub fn step_forward(&mut self) -> Result<Option<Vec<u8>>, VideoError> {
+        self.pause();
+
+        let current = self.current_frame();
+        let at_end = self.known_end_frame().is_some_and(|end| current >= end)
+            || (self.presentation_queue.len() <= 1 && self.reached_end());
+        if at_end {
+            debug!(
+                "Already at the last frame {}, not stepping forward",
+                current
+            );
+            return Ok(self.get_current_frame());
+        }
+
+        let frame = self.decode_until_frame(current + 1)?;
+
+        // Keep the queue topped up for when playback resumes
+        self.receive_frames()?;
+
+        trace!("Stepped forward to frame {}", self.current_frame());
+        Ok(Some(frame))
+    }
+
+    /// Go back exactly one frame and return it, pausing playback
+    ///
+    /// The queue only holds upcoming frames, so this seeks to the previous frame.
+    /// Does nothing at the start frame and returns the current frame instead.
+    pub fn step_backward(&mut self) -> Result<Option<Vec<u8>>, VideoError> {
+        self.pause();
+
+        let current = self.current_frame();
+        if current <= self.start_frame {
+            debug!("Already at the first frame {}, not stepping back", current);
+            return Ok(self.get_current_frame());
+        }
+
+        let frame = self.decode_until_frame(current - 1)?;
+        trace!("Stepped back to frame {}", self.current_frame());
+        Ok(Some(frame))
     }
 
-    /// Get the current frame number
+    /// Get the number of the displayed frame
+    ///
+    /// This is the frame at the front of the queue, which `current_time` and
+    /// `current_timecode` report. The decoder usually runs ahead of it, while
+    /// the queue is empty (e.g. right after a seek) it is the next frame to be
+    /// decoded.
     pub fn current_frame(&self) -> u64 {
-        // Always use the first frame in queue if available
         self.presentation_queue
             .front()
             .map(|f| f.frame_number)
-            .unwrap_or(self.current_frame)
+            .unwrap_or(self.decoded_frame)
     }
 
     /// Get the starting frame number
@@ -533,11 +1793,54 @@ impl VideoStream {
     }
 
     /// Get the ending frame number (if specified, otherwise total frames)
-    pub fn end_frame(&self) -> Result<u64, VideoError> {
-        if let Some(value) = self.end_frame {
-            Ok(value)
-        } else {
-            self.total_frames()
+    ///
+    /// 0 when no end frame is set and the container has no duration, see
+    /// `known_end_frame` to tell that case apart.
+    pub fn end_frame(&self) -> u64 {
+        self.end_frame.unwrap_or(self.duration_frames)
+    }
+
+    /// Get the ending frame number, `None` if it is unknown
+    ///
+    /// The end is unknown for containers without a duration, e.g. live
+    /// streams, unless an end frame is set.
+    pub fn known_end_frame(&self) -> Option<u64> {
+        Some(self.end_frame()).filter(|&frame| frame > 0)
+    }
+
+    /// Change the first and last frame played, e.g. to trim the video
+    ///
+    /// Frames are numbered from 1 and the range is inclusive, `None` plays to
+    /// the end of the video. Ends past the last frame are clamped like the end
+    /// frame of `VideoStreamOptions`. Seeks to the new start frame, and looping
+    /// playback continues from it.
+    pub fn set_frame_range(&mut self, start: u64, end: Option<u64>) -> Result<(), VideoError> {
+        let end = clamp_end_frame(start, end, self.total_frames())?;
+
+        info!("Setting frame range to {}-{:?}", start, end);
+        self.start_frame = start;
+        self.end_frame = end;
+        self.decode_thread.set_end_frame(end)?;
+        self.seek(self.start_time().as_secs_f64(), SeekMode::Accurate)
+    }
+
+    /// Get the time of the start frame in the video
+    pub fn start_time(&self) -> Duration {
+        Duration::from_secs_f64(self.frames_to_secs(self.start_frame.saturating_sub(1)))
+    }
+
+    /// Get the duration of the frames from the start frame to the end frame
+    ///
+    /// Like `total_time` for the trimmed video, `current_time` counts from the start frame.
+    pub fn trimmed_time(&self) -> Duration {
+        match self.end_frame {
+            Some(end_frame) => Duration::from_secs_f64(
+                self.frames_to_secs((end_frame + 1).saturating_sub(self.start_frame)),
+            ),
+            None => self
+                .total_time()
+                .unwrap_or_default()
+                .saturating_sub(self.start_time()),
         }
     }
 
@@ -546,33 +1849,52 @@ impl VideoStream {
         self.looping
     }
 
+    /// Enable or disable looping playback
+    ///
+    /// When enabled, playback continues from the start frame after the end
+    /// frame instead of holding it.
+    pub fn set_looping(&mut self, looping: bool) {
+        debug!("Looping: {}", looping);
+        self.looping = looping;
+    }
+
     /// Get the total number of frames in the video
-    pub fn total_frames(&self) -> Result<u64, VideoError> {
-        let video_stream = self.get_video_stream()?;
-        let duration = video_stream.duration() as f64 * f64::from(video_stream.time_base());
-        Ok((duration * self.get_fps()).ceil() as u64)
+    ///
+    /// Computed once when opening, see `FrameTiming::count_frames`.
+    pub fn total_frames(&self) -> u64 {
+        self.duration_frames
+    }
+
+    /// Get the frame rate as the exact rational of the stream, e.g. (24000, 1001)
+    ///
+    /// Falls back to 30 fps if the stream has no valid rate.
+    pub fn frame_rate(&self) -> (u32, u32) {
+        self.timing.frame_rate
     }
 
     /// Get the frames per second of the video
     pub fn get_fps(&self) -> f64 {
-        let frame_rate = self
-            .get_video_stream()
-            .map(|stream| stream.rate())
-            .unwrap_or((DEFAULT_FPS, 1).into()); // fallback to 30fps if we can't get the rate
+        let (numerator, denominator) = self.frame_rate();
+        numerator as f64 / denominator as f64
+    }
 
-        frame_rate.0 as f64 / frame_rate.1 as f64
+    /// Convert a number of frames to seconds, frame n starts at `frames_to_secs(n - 1)`
+    fn frames_to_secs(&self, frames: u64) -> f64 {
+        self.timing.frames_to_secs(frames)
     }
 
     /// Start playing the video
     pub fn play(&mut self) {
         debug!("Video playback started");
         self.is_playing = true;
+        self.sync_audio();
     }
 
     /// Pause the video playback
     pub fn pause(&mut self) {
         debug!("Video playback paused");
         self.is_playing = false;
+        self.sync_audio();
     }
 
     /// Check if video is currently playing
@@ -583,26 +1905,219 @@ impl VideoStream {
     /// Update the video state and get the current frame
     pub fn update(&mut self) -> Result<Option<Vec<u8>>, VideoError> {
         // Only get a new frame if we're playing and it's time
-        if self.is_playing {
-            self.next_frame()
+        let result = self.receive_preview().and_then(|_| {
+            if self.is_playing {
+                self.next_frame()
+            } else {
+                // When paused, return current frame without advancing
+                Ok(self.get_current_frame())
+            }
+        });
+
+        match result {
+            Ok(Some(data)) => {
+                self.presented = true;
+                Ok(Some(data))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                let frame = self.current_frame();
+                error!("Failed to update video stream at frame {}: {}", frame, e);
+                self.last_error = Some((frame, e.to_string()));
+                Err(VideoError::PlaybackFailed {
+                    frame,
+                    message: e.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Get the error that stopped playback, if any
+    ///
+    /// Set when `update` fails, e.g. on a file corrupt past the packets that can
+    /// be skipped, and cleared by seeking.
+    pub fn last_error(&self) -> Option<VideoError> {
+        self.last_error
+            .as_ref()
+            .map(|(frame, message)| VideoError::PlaybackFailed {
+                frame: *frame,
+                message: message.clone(),
+            })
+    }
+
+    /// Get the current playback state
+    pub fn playback_state(&self) -> PlaybackState {
+        if self.last_error.is_some() {
+            PlaybackState::Error
+        } else if !self.presented {
+            PlaybackState::Opening
+        } else if self.is_end_of_stream() {
+            PlaybackState::Ended
+        } else if !self.is_playing {
+            PlaybackState::Paused
+        } else if self.buffering {
+            PlaybackState::Buffering
         } else {
-            // When paused, return current frame without advancing
-            Ok(self.get_current_frame())
+            PlaybackState::Playing
         }
     }
 
-    /// Get the duration of a single frame
+    /// Check if playback is held while the queue is refilled, e.g. to show a spinner
+    ///
+    /// The last frame stays on screen meanwhile.
+    pub fn is_buffering(&self) -> bool {
+        self.playback_state() == PlaybackState::Buffering
+    }
+
+    /// Get the playback state if it changed since the last call
+    pub fn take_state_change(&mut self) -> Option<PlaybackState> {
+        let state = self.playback_state();
+        if state == self.reported_state {
+            return None;
+        }
+
+        debug!(
+            "Playback state changed: {:?} -> {:?}",
+            self.reported_state, state
+        );
+        self.reported_state = state;
+        Some(state)
+    }
+
+    /// Get the number of times the frame queue ran dry during playback
+    pub fn underruns(&self) -> u64 {
+        self.underruns
+    }
+
+    /// Get the number of seeks completed so far, loops aren't counted
+    pub fn seeks(&self) -> u64 {
+        self.seeks
+    }
+
+    /// Get the number of times looping playback started over from the start frame
+    pub fn loops(&self) -> u64 {
+        self.loops
+    }
+
+    /// Get the number of corrupt packets skipped so far, usually one frame each
+    ///
+    /// Keeps counting across seeks. See `VideoStreamOptions::max_corrupt_packets`.
+    pub fn corrupt_frames(&self) -> u64 {
+        self.decode_thread.corrupt_frames()
+    }
+
+    /// Get the total size in bytes of the video packets decoded so far
+    ///
+    /// Keeps counting across seeks, including packets decoded to reach the target.
+    pub fn bytes_decoded(&self) -> u64 {
+        self.decode_thread.bytes_decoded()
+    }
+
+    /// Get the bitrate of the file in bits per second, if known
+    ///
+    /// Read from the container, so it covers all streams and not just the video.
+    pub fn bitrate(&self) -> Option<u32> {
+        (self.bit_rate > 0).then(|| self.bit_rate.min(u32::MAX as i64) as u32)
+    }
+
+    /// Get the time a single frame is shown, shortened or lengthened by the playback speed
     pub fn get_frame_duration(&self) -> Duration {
         let fps = self.get_fps();
-        Duration::from_secs_f64(1.0 / fps)
+        Duration::from_secs_f64(1.0 / fps / self.playback_speed as f64)
+    }
+
+    /// Set the playback speed, e.g. 2.0 plays twice as fast and 0.5 at half speed
+    ///
+    /// Clamped to 0.1-8.0. Positions such as `current_time` stay in video time.
+    pub fn set_playback_speed(&mut self, speed: f32) {
+        let speed = if speed.is_finite() {
+            speed.clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED)
+        } else {
+            1.0
+        };
+        debug!("Playback speed: {} -> {}", self.playback_speed, speed);
+        self.playback_speed = speed;
+        self.sync_audio();
+    }
+
+    /// Get the playback speed as a multiplier of the source frame rate
+    pub fn playback_speed(&self) -> f32 {
+        self.playback_speed
+    }
+
+    /// Check if the audio of the video is played
+    pub fn has_audio(&self) -> bool {
+        self.audio.is_some()
+    }
+
+    /// Set the audio volume from 0.0 (silent) to 1.0 (unchanged)
+    pub fn set_volume(&mut self, volume: f32) {
+        let volume = if volume.is_finite() {
+            volume.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        if let Some(audio) = &self.audio {
+            debug!("Volume: {}", volume);
+            audio.set_volume(volume);
+        }
+    }
+
+    /// Get the audio volume, 1.0 without audio
+    pub fn volume(&self) -> f32 {
+        self.audio.as_ref().map_or(1.0, AudioOutput::volume)
+    }
+
+    /// Mute or unmute the audio, keeping the volume
+    pub fn mute(&mut self, muted: bool) {
+        if let Some(audio) = &self.audio {
+            debug!("Audio muted: {}", muted);
+            audio.set_muted(muted);
+        }
+    }
+
+    /// Check if the audio is muted
+    pub fn is_muted(&self) -> bool {
+        self.audio.as_ref().is_some_and(AudioOutput::is_muted)
+    }
+
+    /// Get the audio position used as the playback clock
+    ///
+    /// `None` without audio, while the audio is paused or starved, and at other
+    /// speeds than 1.0 where the audio is paused.
+    fn audio_clock(&self) -> Option<f64> {
+        self.audio.as_ref().and_then(AudioOutput::clock)
+    }
+
+    /// Pause or resume the audio with the video, resuming at the current frame
+    ///
+    /// The audio only plays at normal speed, as it isn't time-stretched.
+    fn sync_audio(&mut self) {
+        let time = self
+            .presentation_queue
+            .front()
+            .map(|frame| self.frames_to_secs(frame.frame_number.saturating_sub(1)));
+        let Some(audio) = &self.audio else {
+            return;
+        };
+
+        let active = self.is_playing && self.playback_speed == 1.0;
+        if active {
+            // Catch up with frames stepped through while paused
+            if let Some(time) = time {
+                audio.skip_to(time);
+            }
+        }
+        audio.set_paused(!active);
     }
 }
 
 impl std::fmt::Debug for VideoStream {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("VideoStream")
-            .field("current_frame", &self.current_frame)
+            .field("decoded_frame", &self.decoded_frame)
             .field("is_playing", &self.is_playing)
+            .field("state", &self.playback_state())
             .field("queue_size", &self.presentation_queue.len())
             .finish()
     }
@@ -610,7 +2125,358 @@ impl std::fmt::Debug for VideoStream {
 
 impl Drop for VideoStream {
     fn drop(&mut self) {
-        debug!("Dropping VideoStream and flushing decoder");
-        let _ = self.decoder.send_packet(&ffmpeg::Packet::empty());
+        // Pipelines free the textures of this stream once `owner` is dropped
+        debug!("Dropping VideoStream");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video::decoder::copy_plane;
+
+    #[test]
+    fn chroma_size_rounds_up_odd_sizes() {
+        assert_eq!(chroma_size(5, 5), (3, 3));
+        assert_eq!(chroma_size(1920, 1080), (960, 540));
+    }
+
+    #[test]
+    fn chroma_scale_of_odd_sizes() {
+        assert_eq!(chroma_size(1919, 1079), (960, 540));
+        assert_eq!(chroma_scale(1919, 1079), [1919.0 / 1920.0, 1079.0 / 1080.0]);
+        assert_eq!(chroma_scale(1920, 1080), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn rgb_conversion_uses_the_matrix_of_the_video() {
+        assert_eq!(
+            sws_color_space(ColorSpace::Bt601),
+            ffmpeg::sys::SWS_CS_ITU601 as i32
+        );
+        assert_eq!(
+            sws_color_space(ColorSpace::Bt2020),
+            ffmpeg::sys::SWS_CS_BT2020 as i32
+        );
+        assert_eq!(
+            sws_color_space(ColorSpace::Unspecified),
+            ffmpeg::sys::SWS_CS_ITU709 as i32
+        );
+    }
+
+    #[test]
+    fn rgb_conversion_of_known_yuv() {
+        // 2x2 NV12 frame, white top row and black bottom row without chroma
+        let convert = |y: [u8; 4], range| {
+            let data = [y[0], y[1], y[2], y[3], 128, 128];
+            let color_info = ColorInfo {
+                space: ColorSpace::Bt709,
+                range,
+                ..Default::default()
+            };
+            VideoStream::convert_to_rgb(&data, 2, 2, false, color_info).unwrap()
+        };
+        let close = |rgb: &[u8], expected: [u8; 12]| {
+            rgb.iter()
+                .zip(expected)
+                .all(|(&actual, expected)| actual.abs_diff(expected) <= 1)
+        };
+
+        let (rgb, width, height) = convert([235, 235, 16, 16], ColorRange::Limited);
+        assert_eq!((width, height), (2, 2));
+        assert!(close(
+            &rgb,
+            [255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0]
+        ));
+
+        // Full range spans 0-255, limited range conversion would clip it
+        let (rgb, _, _) = convert([255, 255, 0, 0], ColorRange::Full);
+        assert!(close(
+            &rgb,
+            [255, 255, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0]
+        ));
+        let (rgb, _, _) = convert([128, 128, 128, 128], ColorRange::Full);
+        assert!(close(&rgb, [128; 12]));
+    }
+
+    #[test]
+    fn seeks_are_clamped_to_the_video() {
+        // 30 frames at 30 fps, the last frame starts at 29/30 s
+        let (start, end) = (0.0, 29.0 / 30.0);
+        let epsilon = 1e-6;
+
+        assert_eq!(
+            clamp_time(end - epsilon, start, end).unwrap(),
+            end - epsilon
+        );
+        assert_eq!(clamp_time(end, start, end).unwrap(), end);
+        assert_eq!(clamp_time(5.0, start, end).unwrap(), end);
+        assert_eq!(clamp_time(0.0, start, end).unwrap(), start);
+        assert_eq!(clamp_time(-1.0, start, end).unwrap(), start);
+        assert!(clamp_time(f64::NAN, start, end).is_err());
+        assert!(clamp_time(f64::INFINITY, start, end).is_err());
+
+        // Trimmed videos stay within their start frame, even past a shorter end
+        assert_eq!(clamp_time(0.0, 0.5, end).unwrap(), 0.5);
+        assert_eq!(clamp_time(1.0, 0.5, 0.25).unwrap(), 0.5);
+    }
+
+    #[test]
+    fn end_frame_is_clamped_to_the_video() {
+        // A 30 frame clip
+        let timing = FrameTiming {
+            frame_rate: (30, 1),
+            time_base: ffmpeg::Rational::new(1, 30),
+        };
+        let total = timing.count_frames(30);
+        assert_eq!(total, 30);
+
+        assert_eq!(clamp_end_frame(1, Some(45), total).unwrap(), Some(30));
+        assert_eq!(clamp_end_frame(1, Some(20), total).unwrap(), Some(20));
+        assert_eq!(clamp_end_frame(1, None, total).unwrap(), None);
+        // The duration is unknown for some containers
+        assert_eq!(clamp_end_frame(1, Some(45), 0).unwrap(), Some(45));
+        // Single frame ranges are fine, ends before the start are not
+        assert_eq!(clamp_end_frame(10, Some(10), total).unwrap(), Some(10));
+        assert_eq!(clamp_end_frame(total, None, total).unwrap(), None);
+        assert!(clamp_end_frame(10, Some(9), total).is_err());
+        assert!(clamp_end_frame(0, None, total).is_err());
+    }
+
+    /// Open the 1039 frame, 29.97 fps clip of the assets without audio
+    fn open_test_video(start_frame: u64, end_frame: Option<u64>) -> VideoStream {
+        VideoStream::new(VideoStreamOptions {
+            video_path: concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../../assets/videos/video1.mp4"
+            )
+            .to_owned(),
+            start_frame,
+            end_frame,
+            assume_bt709: false,
+            image_sequence_fps: None,
+            max_read_retries: 3,
+            stream_index: None,
+            decode_threads: None,
+            max_corrupt_packets: 10,
+            repeat_frame_on_corrupt: true,
+            output_pixel_format: OutputPixelFormat::Nv12,
+            hw_accel: None,
+            queue_size: DEFAULT_QUEUE_SIZE,
+            adaptive_queue_size: false,
+            audio: false,
+            target_size: None,
+            target_scale: TargetScale::Fit,
+        })
+        .unwrap()
+    }
+
+    /// Play at the highest speed until the end, returning the numbers of the frames shown
+    fn play_to_end(video: &mut VideoStream) -> Vec<u64> {
+        video.set_playback_speed(MAX_PLAYBACK_SPEED);
+        video.play();
+
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let mut shown = vec![video.current_frame()];
+        while !video.is_end_of_stream() {
+            assert!(
+                Instant::now() < deadline,
+                "playback stalled at {:?}",
+                shown.last()
+            );
+            video.update().unwrap();
+            let frame = video.current_frame();
+            if shown.last() != Some(&frame) {
+                shown.push(frame);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        shown
+    }
+
+    #[test]
+    fn playback_stops_after_the_end_frame() {
+        let start = 10;
+        let mut video = open_test_video(start, Some(start + 30));
+        assert_eq!(video.end_frame(), start + 30);
+
+        // The end frame is inclusive, so 31 frames are shown
+        let shown = play_to_end(&mut video);
+        assert_eq!(shown, (start..=start + 30).collect::<Vec<_>>());
+        assert_eq!(shown.len(), 31);
+
+        // The last frame is held instead of playing on
+        for _ in 0..10 {
+            video.update().unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(video.current_frame(), start + 30);
+        assert!(!past_end_frame(start + 30, Some(start + 30)));
+        assert!(past_end_frame(start + 31, Some(start + 30)));
+    }
+
+    #[test]
+    fn looping_seeks_back_to_the_start_frame() {
+        for frame_rate in [
+            (25, 1),
+            (30, 1),
+            (24000, 1001),
+            (30000, 1001),
+            (60000, 1001),
+        ] {
+            let timing = FrameTiming {
+                frame_rate,
+                time_base: ffmpeg::Rational::new(1, 90000),
+            };
+            for start_frame in [1, 2, 17, 1000, 86_400] {
+                // The time `next_frame` seeks to after the end frame when looping
+                let loop_time = timing.frames_to_secs(start_frame - 1);
+                assert_eq!(
+                    timing.frame_at_time(loop_time),
+                    start_frame,
+                    "{:?} at frame {}",
+                    frame_rate,
+                    start_frame
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ntsc_frame_counts_are_exact() {
+        // 240 frames of 24000/1001 fps, as counted by ffprobe -count_frames
+        for time_base in [
+            ffmpeg::Rational::new(1, 24000),
+            ffmpeg::Rational::new(1, 90000),
+            ffmpeg::Rational::new(1001, 24000),
+        ] {
+            let timing = FrameTiming {
+                frame_rate: (24000, 1001),
+                time_base,
+            };
+            let duration = 240 * 1001 * time_base.denominator() as i64
+                / (24000 * time_base.numerator() as i64);
+            assert_eq!(timing.count_frames(duration), 240, "{:?}", time_base);
+            // A partial last frame counts
+            assert_eq!(timing.count_frames(duration + 1), 241, "{:?}", time_base);
+        }
+    }
+
+    #[test]
+    fn queue_grows_with_decode_latency() {
+        let frame = Duration::from_millis(10); // 100 fps
+
+        assert_eq!(adaptive_queue_size(3, Duration::ZERO, frame), 3);
+        assert_eq!(adaptive_queue_size(3, Duration::from_millis(9), frame), 3);
+        assert_eq!(adaptive_queue_size(3, Duration::from_millis(25), frame), 5);
+        assert_eq!(
+            adaptive_queue_size(3, Duration::from_secs(1), frame),
+            MAX_ADAPTIVE_QUEUE_SIZE
+        );
+        // A configured size above the limit is kept
+        assert_eq!(adaptive_queue_size(40, Duration::from_secs(1), frame), 40);
+    }
+
+    #[test]
+    fn frame_seeks_land_on_the_frame() {
+        for (frame_rate, time_base) in [
+            ((30, 1), ffmpeg::Rational::new(1, 30)),
+            ((25, 1), ffmpeg::Rational::new(1, 1000)),
+            ((24000, 1001), ffmpeg::Rational::new(1, 24000)),
+            ((24000, 1001), ffmpeg::Rational::new(1, 90000)),
+            ((30000, 1001), ffmpeg::Rational::new(1, 90000)),
+        ] {
+            let timing = FrameTiming {
+                frame_rate,
+                time_base,
+            };
+            for frame in [1, 2, 3, 100, 1001, 24_000] {
+                // Seek target of `seek_to_frame` and the timestamp the decoder seeks to
+                let time_s = timing.frames_to_secs(frame - 1);
+                assert_eq!(timing.frame_at_time(time_s), frame, "{:?}", frame_rate);
+                let target_ts = timing.time_to_ts(time_s);
+
+                // Muxers round frame timestamps to the time base
+                let pts = (time_s * time_base.denominator() as f64).round() as i64;
+                assert!((pts - target_ts).abs() <= 1, "{:?}", frame_rate);
+                assert_eq!(
+                    timing.frame_at_time(timing.ts_to_time(pts)),
+                    frame,
+                    "{:?} in {:?}",
+                    frame_rate,
+                    time_base
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn timestamps_use_the_whole_time_base() {
+        // One tick per frame of 29.97 fps video
+        let timing = FrameTiming {
+            frame_rate: (30000, 1001),
+            time_base: ffmpeg::Rational::new(1001, 30000),
+        };
+        assert!((timing.ts_to_time(300) - 10.01).abs() < 1e-9);
+        assert_eq!(timing.frame_at_time(timing.ts_to_time(300)), 301);
+
+        // Times are truncated to ticks, step past rounding errors of the round trip
+        for ts in [0, 1, 29, 30, 300, 1000, 86_400] {
+            assert_eq!(timing.time_to_ts(timing.ts_to_time(ts) + 1e-9), ts);
+        }
+    }
+
+    #[test]
+    fn reset_restarts_at_the_start_frame() {
+        let (start, end) = (10, 40);
+        let mut video = open_test_video(start, Some(end));
+        let start_frame_data = video.get_last_frame();
+        assert_eq!(video.current_frame(), start);
+
+        let shown = play_to_end(&mut video);
+        assert_eq!(shown.last(), Some(&end));
+        assert!(video.is_end_of_stream());
+
+        video.reset().unwrap();
+        assert_eq!(video.current_frame(), start);
+        assert!(!video.is_end_of_stream());
+        // The start frame is presented next, with the same picture as before
+        assert_eq!(video.get_last_frame(), start_frame_data);
+
+        // Frame numbers count up from the start frame again
+        let shown = play_to_end(&mut video);
+        assert_eq!(shown, (start..=end).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn plane_copy_skips_padding_of_odd_sizes() {
+        // 5x5 NV12 frame with lines padded to 8 bytes, as ffmpeg aligns them
+        let (width, height) = (5, 5);
+        let (uv_width, uv_height) = chroma_size(width, height);
+        let stride = 8;
+        let y_plane = vec![16u8; stride * height as usize];
+        let uv_plane = vec![128u8; stride * uv_height as usize];
+
+        let mut buffer = Vec::new();
+        copy_plane(
+            &mut buffer,
+            &y_plane,
+            stride,
+            width as usize,
+            height as usize,
+        );
+        copy_plane(
+            &mut buffer,
+            &uv_plane,
+            stride,
+            uv_width as usize * 2,
+            uv_height as usize,
+        );
+
+        assert_eq!(buffer.len(), 5 * 5 + 3 * 3 * 2);
+        assert_eq!(
+            buffer.len(),
+            VideoStream::calculate_buffer_size(width, height, false, false)
+        );
     }
 }
@@ -82,7 +82,8 @@ impl TextureManager {
             format: self.format,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT
                 | wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::COPY_DST,
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC, // Read back by `debug_read_texture`
             view_formats: &[self.format],
         });
 
@@ -0,0 +1,192 @@
+use orbit_video_player_common::VideoError;
+
+/// How frames are counted in SMPTE timecodes (HH:MM:SS:FF) of a video
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimecodeRate {
+    pub frames_per_second: u32, // Nominal rate, e.g. 30 for 29.97 fps
+    pub drop_frame: bool,       // Skip frame numbers to stay in sync with NTSC rates
+}
+
+impl TimecodeRate {
+    /// Get the timecode rate for a frame rate
+    ///
+    /// NTSC rates (29.97 and 59.94 fps) use drop-frame timecode, every other
+    /// rate is counted at its rounded value.
+    pub fn from_fps(fps: f64) -> Self {
+        let frames_per_second = (fps.round() as u32).max(1);
+        let is_ntsc = (fps - frames_per_second as f64 * 1000.0 / 1001.0).abs() < 0.01;
+        Self {
+            frames_per_second,
+            drop_frame: is_ntsc && frames_per_second % 30 == 0,
+        }
+    }
+
+    /// Frame numbers skipped at the start of every minute except each tenth
+    fn dropped_per_minute(&self) -> u64 {
+        if self.drop_frame {
+            self.frames_per_second as u64 / 15 // 2 at 29.97, 4 at 59.94
+        } else {
+            0
+        }
+    }
+}
+
+/// Format a frame index (0 at the start of the video) as a timecode
+///
+/// Drop-frame timecodes use `;` before the frames, e.g. `00:01:00;02`.
+pub fn format_timecode(frame: u64, rate: TimecodeRate) -> String {
+    let fps = rate.frames_per_second as u64;
+    let dropped = rate.dropped_per_minute();
+
+    // Add back the skipped frame numbers to count as if no frames were dropped
+    let mut frame_number = frame;
+    if dropped > 0 {
+        let frames_per_minute = fps * 60 - dropped;
+        let frames_per_ten_minutes = fps * 600 - dropped * 9;
+        let tens = frame / frames_per_ten_minutes;
+        let remainder = frame % frames_per_ten_minutes;
+        frame_number += dropped * 9 * tens;
+        if remainder > dropped {
+            frame_number += dropped * ((remainder - dropped) / frames_per_minute);
+        }
+    }
+
+    format!(
+        "{:02}:{:02}:{:02}{}{:02}",
+        frame_number / (fps * 3600),
+        frame_number / (fps * 60) % 60,
+        frame_number / fps % 60,
+        if rate.drop_frame { ';' } else { ':' },
+        frame_number % fps
+    )
+}
+
+/// Parse a `HH:MM:SS:FF` timecode into a frame index (0 at the start of the video)
+///
+/// The frames may also be separated by `;` or `.`. Frame numbers skipped by
+/// drop-frame timecode are rejected.
+pub fn parse_timecode(timecode: &str, rate: TimecodeRate) -> Result<u64, VideoError> {
+    let invalid = |reason: &str| VideoError::InvalidTimecode(format!("{}: {}", timecode, reason));
+
+    let parts = timecode
+        .trim()
+        .split([':', ';', '.'])
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid("expected HH:MM:SS:FF"))?;
+    let [hours, minutes, seconds, frames] = parts[..] else {
+        return Err(invalid("expected HH:MM:SS:FF"));
+    };
+
+    let fps = rate.frames_per_second as u64;
+    if minutes >= 60 || seconds >= 60 {
+        return Err(invalid("minutes and seconds must be below 60"));
+    }
+    if frames >= fps {
+        return Err(invalid(&format!("frames must be below {}", fps)));
+    }
+
+    let dropped = rate.dropped_per_minute();
+    if seconds == 0 && minutes % 10 != 0 && frames < dropped {
+        return Err(invalid("frame number is skipped in drop-frame timecode"));
+    }
+
+    // Huge hours fields would overflow
+    let too_long = || invalid("timecode is too long");
+    let total_minutes = hours
+        .checked_mul(60)
+        .and_then(|minutes_of_hours| minutes_of_hours.checked_add(minutes))
+        .ok_or_else(too_long)?;
+    let frame_number = total_minutes
+        .checked_mul(60)
+        .and_then(|total_seconds| total_seconds.checked_add(seconds))
+        .and_then(|total_seconds| total_seconds.checked_mul(fps))
+        .and_then(|frame_number| frame_number.checked_add(frames))
+        .ok_or_else(too_long)?;
+    Ok(frame_number - dropped * (total_minutes - total_minutes / 10))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NTSC_30: TimecodeRate = TimecodeRate {
+        frames_per_second: 30,
+        drop_frame: true,
+    };
+    const NTSC_60: TimecodeRate = TimecodeRate {
+        frames_per_second: 60,
+        drop_frame: true,
+    };
+
+    #[test]
+    fn ntsc_rates_use_drop_frame() {
+        assert_eq!(TimecodeRate::from_fps(30000.0 / 1001.0), NTSC_30);
+        assert_eq!(TimecodeRate::from_fps(60000.0 / 1001.0), NTSC_60);
+        assert!(!TimecodeRate::from_fps(24000.0 / 1001.0).drop_frame);
+        assert!(!TimecodeRate::from_fps(30.0).drop_frame);
+    }
+
+    #[test]
+    fn drop_frame_timecodes_of_known_frames() {
+        let pairs = [
+            (0, "00:00:00;00"),
+            (1799, "00:00:59;29"),
+            (1800, "00:01:00;02"),
+            (17981, "00:09:59;29"),
+            (17982, "00:10:00;00"),
+            (17983, "00:10:00;01"),
+            (19780, "00:10:59;28"),
+            (19782, "00:11:00;02"),
+            (107892, "01:00:00;00"),
+        ];
+        for (frame, timecode) in pairs {
+            assert_eq!(format_timecode(frame, NTSC_30), timecode);
+            assert_eq!(parse_timecode(timecode, NTSC_30).unwrap(), frame);
+        }
+
+        assert_eq!(format_timecode(3600, NTSC_60), "00:01:00;04");
+        assert_eq!(format_timecode(35964, NTSC_60), "00:10:00;00");
+    }
+
+    #[test]
+    fn drop_frame_timecodes_round_trip() {
+        // Past the 20 minute mark, covering each minute and ten minute boundary
+        for rate in [NTSC_30, NTSC_60] {
+            let frames = rate.frames_per_second as u64 * 60 * 21;
+            for frame in 0..frames {
+                let timecode = format_timecode(frame, rate);
+                assert_eq!(
+                    parse_timecode(&timecode, rate).unwrap(),
+                    frame,
+                    "{}",
+                    timecode
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn skipped_frame_numbers_are_rejected() {
+        for timecode in ["00:01:00;00", "00:01:00;01", "00:59:00;01"] {
+            assert!(parse_timecode(timecode, NTSC_30).is_err(), "{}", timecode);
+        }
+        assert!(parse_timecode("00:01:00;03", NTSC_60).is_err());
+        // Every tenth minute keeps its frame numbers
+        assert_eq!(parse_timecode("00:10:00;00", NTSC_30).unwrap(), 17982);
+        assert_eq!(parse_timecode("00:01:00;02", NTSC_30).unwrap(), 1800);
+    }
+
+    #[test]
+    fn invalid_timecodes_are_rejected() {
+        let rate = TimecodeRate::from_fps(25.0);
+        assert_eq!(parse_timecode("00:00:01:05", rate).unwrap(), 30);
+        for timecode in ["00:00:01", "00:60:00:00", "00:00:00:25", "aa:00:00:00"] {
+            assert!(parse_timecode(timecode, rate).is_err(), "{}", timecode);
+        }
+        assert!(matches!(
+            parse_timecode("99999999999999999:00:00:00", rate),
+            Err(VideoError::InvalidTimecode(_))
+        ));
+    }
+}
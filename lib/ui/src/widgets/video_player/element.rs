@@ -1,43 +1,139 @@
-use iced::widget::{column, horizontal_space, Checkbox};
+use iced::widget::{column, horizontal_space, Checkbox, PickList, Radio};
 use iced::{
     advanced::{self, graphics::core::event::Status, layout, widget, Widget},
     widget::Stack,
+    window,
     Alignment::Center,
-    Element, Length,
+    Element, Length, Task,
 };
 use iced_wgpu::primitive::Renderer as PrimitiveRenderer;
-use orbit_video_player_core::video::{primitive::VideoPrimitive, stream::VideoStream};
+use orbit_video_player_common::VideoError;
+use orbit_video_player_core::video::{
+    pipeline::{
+        effects::{
+            comparison::ComparisonOrientation, simple_color::SimpleColorMode, stereo::StereoLayout,
+            upscale::UpscaleAlgorithm, EffectParameters,
+        },
+        SamplingMode,
+    },
+    primitive::{GridVideo, VideoPrimitive},
+    shader::UniformValue,
+    stream::{
+        OutputPixelFormat, PlaybackEvent, PlaybackState, SeekMode, TargetScale, VideoStream,
+        VideoStreamOptions, DEFAULT_QUEUE_SIZE,
+    },
+};
 use std::collections::HashMap;
 use std::{
     cell::RefCell,
-    time::{Duration, Instant},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use iced::widget::{Button, Column, Container, Row, Slider, Text};
+use iced::widget::{Button, Column, Container, Row, Slider, Text, TextInput};
 
-use super::icons::{comparison, pause, play};
+use super::icons::{
+    camera, comparison, pause, play, repeat, step_backward, step_forward, volume as volume_icon,
+    volume_mute,
+};
 use super::theme::{
     controls_container, primary_button, secondary_button, text_time, video_container, video_slider,
 };
-use super::{compariosn_slider::comparison_slider_style, Video};
+use super::time_format::{format_frames, format_percent, format_time, TimeDisplay};
+use super::{Shortcut, Video};
 
 pub struct Player {
     stream: RefCell<VideoStream>,
     position: f64,
     dragging: bool,
+    letterbox_color: iced::Color, // Bars around the video
+    sampling: SamplingMode,
     // Comparison
     comparison_enabled: bool,
     comparison_position: f32,
+    comparison_orientation: ComparisonOrientation,
     dragging_comparison: bool,
     // Shader selections
     shader_selections: HashMap<String, bool>,
+    shader_parameters: HashMap<String, EffectParameters>,
+    bypass_effects: bool,
+    // Color balance
+    tone_range: ToneRange,
+    // Stereo 3D
+    stereo_enabled: bool,
+    stereo_layout: StereoLayout,
+    playback_state: PlaybackState,
+    error: Option<String>, // Message of the last playback failure
+    time_display: TimeDisplay,
+    timecode_input: String,
+    // Audio
+    last_volume: f32, // Last non-zero volume, restored when unmuting at zero volume
+    // Keyboard shortcuts
+    seek_step: f64, // Seconds skipped by the arrow keys
+    fullscreen: bool,
+    // Timecode overlay
+    show_timecode: bool,
+    timecode_corner: OverlayCorner,
+    // Screenshots
+    screenshot_dir: PathBuf,
+    pending_screenshot: RefCell<Option<PathBuf>>, // Passed to the next view
+}
+
+/// Corner of the video an overlay is placed in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverlayCorner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl OverlayCorner {
+    fn alignment(&self) -> (iced::alignment::Horizontal, iced::alignment::Vertical) {
+        use iced::alignment::{Horizontal, Vertical};
+        match self {
+            OverlayCorner::TopLeft => (Horizontal::Left, Vertical::Top),
+            OverlayCorner::TopRight => (Horizontal::Right, Vertical::Top),
+            OverlayCorner::BottomLeft => (Horizontal::Left, Vertical::Bottom),
+            OverlayCorner::BottomRight => (Horizontal::Right, Vertical::Bottom),
+        }
+    }
+}
+
+/// Tonal range edited by the color balance controls
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneRange {
+    Shadows,
+    Midtones,
+    Highlights,
+}
+
+impl ToneRange {
+    /// Name of the color balance parameter for this range
+    pub fn parameter_name(&self) -> &'static str {
+        match self {
+            ToneRange::Shadows => "shadows",
+            ToneRange::Midtones => "midtones",
+            ToneRange::Highlights => "highlights",
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum Event {
     Pause,
+    Restart, // Rewind to the start frame and play, e.g. after the end
     Loop,
+    SetSpeed(f32),
+    ToggleMute,
+    SetVolume(f32),
+    // Frame by frame review, pauses playback
+    StepForward,
+    StepBackward,
     Seek(f64),
+    SeekToFrame(u64), // Frame-precise seek, e.g. from a frame number entry
     SeekRelease,
     EndOfStream,
     NewFrame,
@@ -46,8 +142,27 @@ pub enum Event {
     UpdateComparisonPosition(f32),
     ComparisonDragStart,
     ComparisonDragEnd,
+    SelectComparisonOrientation(ComparisonOrientation),
     // New event for shader selection
     ToggleShader(String, bool),
+    ToggleBypassEffects(bool), // Draw without any effects, to compare their cost
+    // Shader parameters (effect name, parameter name, value)
+    UpdateShaderParameter(String, String, UniformValue),
+    SelectToneRange(ToneRange),
+    // Stereo 3D
+    ToggleStereo(bool),
+    SelectStereoLayout(StereoLayout),
+    PlaybackStateChanged(PlaybackState),
+    PlaybackFailed(String),
+    ToggleTimeDisplay,
+    // Timecode entry
+    TimecodeInputChanged(String),
+    SeekToTimecode,
+    // Save the displayed frame with effects as a PNG
+    Screenshot,
+    // Keyboard shortcuts
+    SeekBy(f64), // Relative seek in seconds, keeps playing
+    ToggleFullscreen,
 }
 
 impl Player {
@@ -61,14 +176,140 @@ impl Player {
             stream,
             position,
             dragging,
+            letterbox_color: iced::Color::TRANSPARENT,
+            sampling: SamplingMode::default(),
             // Comparison
             comparison_enabled: false,
             comparison_position: 0.5, // Start at middle
+            comparison_orientation: ComparisonOrientation::default(),
             dragging_comparison: false,
             shader_selections,
+            bypass_effects: false,
+            shader_parameters: HashMap::new(),
+            tone_range: ToneRange::Midtones,
+            stereo_enabled: false,
+            stereo_layout: StereoLayout::default(),
+            playback_state: PlaybackState::Opening,
+            error: None,
+            time_display: TimeDisplay::default(),
+            timecode_input: String::new(),
+            last_volume: 1.0,
+            seek_step: DEFAULT_SEEK_STEP,
+            fullscreen: false,
+            show_timecode: false,
+            timecode_corner: OverlayCorner::default(),
+            screenshot_dir: dirs::picture_dir().unwrap_or_else(std::env::temp_dir),
+            pending_screenshot: RefCell::default(),
+        }
+    }
+
+    /// Seconds skipped by the left and right arrow keys
+    pub fn seek_step(&self) -> f64 {
+        self.seek_step
+    }
+
+    /// Set the seconds skipped by the left and right arrow keys
+    pub fn set_seek_step(&mut self, secs: f64) {
+        self.seek_step = secs.max(0.0);
+    }
+
+    /// Set the directory screenshots are saved to, the pictures directory by default
+    pub fn set_screenshot_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.screenshot_dir = dir.into();
+    }
+
+    /// Set the color of the bars around the video, transparent by default
+    pub fn set_letterbox_color(&mut self, color: iced::Color) {
+        self.letterbox_color = color;
+    }
+
+    /// Set how the video is filtered when scaled, e.g. nearest to inspect pixels
+    pub fn set_sampling(&mut self, sampling: SamplingMode) {
+        self.sampling = sampling;
+    }
+
+    /// Show the frame number and SMPTE timecode over the video
+    pub fn set_show_timecode(&mut self, show: bool) {
+        self.show_timecode = show;
+    }
+
+    /// Set the corner of the video the timecode overlay is placed in
+    pub fn set_timecode_corner(&mut self, corner: OverlayCorner) {
+        self.timecode_corner = corner;
+    }
+
+    /// Create a player for the video at the given path
+    pub fn from_path(path: &str) -> Result<Self, VideoError> {
+        let stream = Self::load_stream(path)?;
+        Ok(Self::new(RefCell::new(stream), 0.0, false))
+    }
+
+    /// Replace the current video with the one at the given path
+    ///
+    /// Shader, comparison, playback speed and volume settings are kept.
+    pub fn open(&mut self, path: &str) -> Result<(), VideoError> {
+        let mut stream = Self::load_stream(path)?;
+        {
+            let previous = self.stream.borrow();
+            stream.set_playback_speed(previous.playback_speed());
+            stream.set_volume(previous.volume());
+            stream.mute(previous.is_muted());
         }
+        self.stream = RefCell::new(stream);
+        self.position = 0.0;
+        self.dragging = false;
+        self.playback_state = PlaybackState::Opening;
+        self.error = None;
+        Ok(())
     }
-    pub fn update(&mut self, message: Event) {
+
+    /// Load a video stream from the start of the file
+    pub(crate) fn load_stream(path: &str) -> Result<VideoStream, VideoError> {
+        VideoStream::new(VideoStreamOptions {
+            video_path: path.to_owned(),
+            start_frame: 1, // Frames are numbered from 1
+            end_frame: None,
+            assume_bt709: false,
+            image_sequence_fps: None,
+            max_read_retries: 3,
+            stream_index: None,
+            decode_threads: None,
+            max_corrupt_packets: 10,
+            repeat_frame_on_corrupt: true,
+            output_pixel_format: OutputPixelFormat::Nv12,
+            hw_accel: None, // Hosts can open their own stream with `HwAccelType::platform_default()`
+            queue_size: DEFAULT_QUEUE_SIZE,
+            adaptive_queue_size: true,
+            audio: true,
+            target_size: None,
+            target_scale: TargetScale::Fit,
+        })
+    }
+
+    /// Current value of a shader parameter set from the UI
+    fn shader_parameter(&self, effect: &str, name: &str) -> Option<&UniformValue> {
+        self.shader_parameters
+            .get(effect)
+            .and_then(|parameters| parameters.get(name))
+    }
+
+    /// Current RGB gain of a color balance range, defaulting to neutral
+    fn color_balance_gain(&self, range: ToneRange) -> [f32; 3] {
+        match self.shader_parameter("color_balance", range.parameter_name()) {
+            Some(UniformValue::Vec3(gain)) => *gain,
+            _ => [1.0, 1.0, 1.0],
+        }
+    }
+
+    fn seek_to_position(&mut self, mode: SeekMode) {
+        // The position counts from the start frame
+        let time_s = self.stream.borrow().start_time().as_secs_f64() + self.position;
+        if let Err(e) = self.stream.borrow_mut().seek_to_time(time_s, mode) {
+            tracing::error!("Failed to seek: {:?}", e)
+        }
+    }
+
+    pub fn update(&mut self, message: Event) -> Task<Event> {
         match message {
             Event::Pause => {
                 if !self.stream.borrow().is_playing {
@@ -77,33 +318,158 @@ impl Player {
                     self.stream.borrow_mut().pause();
                 }
             }
+            Event::Restart => {
+                let mut stream = self.stream.borrow_mut();
+                if let Err(e) = stream.reset() {
+                    tracing::error!("Failed to restart playback: {:?}", e);
+                }
+                stream.play();
+                self.position = stream.current_time().as_secs_f64();
+            }
+            Event::StepForward => {
+                let result = self.stream.borrow_mut().step_forward();
+                self.finish_step(result);
+            }
+            Event::StepBackward => {
+                let result = self.stream.borrow_mut().step_backward();
+                self.finish_step(result);
+            }
+            Event::SetSpeed(speed) => {
+                self.stream.borrow_mut().set_playback_speed(speed);
+            }
+            Event::ToggleMute => {
+                let mut stream = self.stream.borrow_mut();
+                if stream.is_muted() || stream.volume() == 0.0 {
+                    // Unmuting a slider dragged to zero restores the last audible volume
+                    if stream.volume() == 0.0 {
+                        stream.set_volume(self.last_volume);
+                    }
+                    stream.mute(false);
+                } else {
+                    stream.mute(true);
+                }
+            }
+            Event::SetVolume(volume) => {
+                let mut stream = self.stream.borrow_mut();
+                if volume > 0.0 {
+                    self.last_volume = volume;
+                    stream.mute(false);
+                }
+                stream.set_volume(volume);
+            }
             Event::ToggleShader(name, enabled) => {
+                // The simple color modes share one effect, only one can be selected
+                let is_simple_color = |key: &str| {
+                    SimpleColorMode::ALL
+                        .iter()
+                        .any(|mode| mode.selection_key() == Some(key))
+                };
+                if enabled && is_simple_color(&name) {
+                    for key in SimpleColorMode::ALL
+                        .iter()
+                        .filter_map(|m| m.selection_key())
+                    {
+                        self.shader_selections.insert(key.to_string(), false);
+                    }
+                }
                 self.shader_selections.insert(name, enabled);
             }
+            Event::UpdateShaderParameter(effect, name, value) => {
+                self.shader_parameters
+                    .entry(effect)
+                    .or_default()
+                    .insert(name, value);
+            }
+            Event::ToggleBypassEffects(bypass) => {
+                self.bypass_effects = bypass;
+            }
+            Event::SelectToneRange(range) => {
+                self.tone_range = range;
+            }
+            Event::ToggleStereo(enabled) => {
+                self.stereo_enabled = enabled;
+            }
+            Event::SelectStereoLayout(layout) => {
+                self.stereo_layout = layout;
+            }
+            Event::PlaybackStateChanged(state) => {
+                self.playback_state = state;
+            }
+            Event::PlaybackFailed(error) => {
+                tracing::error!("Playback failed: {}", error);
+                self.stream.borrow_mut().pause();
+                self.error = Some(error);
+            }
+            Event::ToggleTimeDisplay => {
+                self.time_display = self.time_display.next();
+            }
+            Event::Screenshot => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+                let path = self
+                    .screenshot_dir
+                    .join(format!("screenshot_{}.png", timestamp));
+                *self.pending_screenshot.borrow_mut() = Some(path);
+            }
+            Event::TimecodeInputChanged(input) => {
+                self.timecode_input = input;
+            }
+            Event::SeekToTimecode => {
+                // A plain number is a frame number
+                let input = self.timecode_input.trim();
+                let result = match input.parse::<u64>() {
+                    Ok(frame) => self.stream.borrow_mut().seek_to_frame(frame),
+                    Err(_) => self.stream.borrow_mut().seek_to_timecode(input),
+                };
+                match result {
+                    Ok(()) => {
+                        self.position = self.stream.borrow().current_time().as_secs_f64();
+                        self.timecode_input.clear();
+                    }
+                    Err(e) => tracing::warn!("Failed to seek to timecode: {}", e),
+                }
+            }
             Event::Loop => {
-                self.stream.borrow_mut().looping();
+                let mut stream = self.stream.borrow_mut();
+                let looping = !stream.looping();
+                stream.set_looping(looping);
             }
             Event::Seek(secs) => {
-                self.dragging = true;
-                self.stream.borrow_mut().pause(); // Pause while seeking
+                if !self.dragging {
+                    self.dragging = true;
+                    self.stream.borrow_mut().pause(); // Pause while seeking
+                }
 
                 self.position = secs;
-                let seek_result = self.stream.borrow_mut().seek_to_time(self.position);
-                match seek_result {
-                    Ok(_) => {}
-                    Err(e) => {
-                        tracing::error!("Failed to seek: {:?}", e)
-                    }
+
+                // Preview keyframes while dragging without waiting for them, the
+                // slider fires on every pointer move and the stream only decodes
+                // the latest position once the previous preview is shown
+                let mut stream = self.stream.borrow_mut();
+                let time_s = stream.start_time().as_secs_f64() + self.position;
+                if let Err(e) = stream.preview_at(time_s) {
+                    tracing::error!("Failed to preview: {:?}", e)
                 }
             }
             Event::SeekRelease => {
                 self.dragging = false;
+                // Land exactly where the drag ended
+                self.seek_to_position(SeekMode::Accurate);
                 self.stream.borrow_mut().pause();
             }
             Event::EndOfStream => {
                 self.stream.borrow_mut().pause();
             }
             Event::NewFrame => {
+                // Advance playback when ticked by the subscription, a no-op if the
+                // widget has already advanced to the current frame
+                if self.stream.borrow().is_playing() {
+                    if let Err(e) = self.stream.borrow_mut().update() {
+                        tracing::warn!("Failed to advance playback: {:?}", e);
+                    }
+                }
                 if !self.dragging {
                     let current = self.stream.borrow().current_time().as_secs_f64();
                     // Only update if the difference is significant
@@ -125,70 +491,526 @@ impl Player {
             Event::ComparisonDragEnd => {
                 self.dragging_comparison = false;
             }
+            Event::SelectComparisonOrientation(orientation) => {
+                self.comparison_orientation = orientation;
+            }
+            Event::SeekToFrame(frame) => {
+                if let Err(e) = self.stream.borrow_mut().seek_to_frame(frame) {
+                    tracing::error!("Failed to seek to frame {}: {:?}", frame, e);
+                }
+                self.position = self.stream.borrow().current_time().as_secs_f64();
+            }
+            Event::SeekBy(delta) => {
+                let current = self.stream.borrow().current_time().as_secs_f64();
+                self.position = (current + delta).max(0.0);
+                self.seek_to_position(SeekMode::Accurate);
+                // The stream clamps past the end, show where it landed
+                self.position = self.stream.borrow().current_time().as_secs_f64();
+            }
+            Event::ToggleFullscreen => {
+                self.fullscreen = !self.fullscreen;
+                let mode = if self.fullscreen {
+                    window::Mode::Fullscreen
+                } else {
+                    window::Mode::Windowed
+                };
+                return window::get_latest().and_then(move |id| window::change_mode(id, mode));
+            }
         }
+        Task::none()
     }
 
-    pub fn view(&self) -> Element<Event> {
-        let is_playing = self.stream.borrow().is_playing;
-        let _is_looping = self.stream.borrow().looping();
-        let current = self.stream.borrow().current_time();
-        let total = self.stream.borrow().total_time().unwrap();
-        let shader_controls = Container::new(
-            Column::new()
+    /// Move the timeline to the frame stepped to
+    fn finish_step(&mut self, result: Result<Option<Vec<u8>>, VideoError>) {
+        if let Err(e) = result {
+            tracing::warn!("Failed to step frame: {:?}", e);
+        }
+        self.position = self.stream.borrow().current_time().as_secs_f64();
+    }
+
+    /// Tone range selection and RGB gain sliders for the color balance effect
+    fn color_balance_controls(&self) -> Element<'_, Event> {
+        let range = self.tone_range;
+        let gain = self.color_balance_gain(range);
+
+        let range_row = [
+            ("Shadows", ToneRange::Shadows),
+            ("Midtones", ToneRange::Midtones),
+            ("Highlights", ToneRange::Highlights),
+        ]
+        .into_iter()
+        .fold(Row::new().spacing(10), |row, (label, value)| {
+            row.push(Radio::new(
+                label,
+                value,
+                Some(range),
+                Event::SelectToneRange,
+            ))
+        });
+
+        let channel_sliders = ["R", "G", "B"].into_iter().enumerate().fold(
+            Column::new().spacing(5),
+            |column, (channel, label)| {
+                column.push(
+                    Row::new()
+                        .spacing(10)
+                        .align_y(Center)
+                        .push(Text::new(label).style(text_time).width(20))
+                        .push(
+                            Slider::new(0.0..=2.0, gain[channel], move |value| {
+                                let mut gain = gain;
+                                gain[channel] = value;
+                                Event::UpdateShaderParameter(
+                                    "color_balance".to_string(),
+                                    range.parameter_name().to_string(),
+                                    UniformValue::Vec3(gain),
+                                )
+                            })
+                            .step(0.01)
+                            .style(video_slider),
+                        )
+                        .push(Text::new(format!("{:.2}", gain[channel])).style(text_time)),
+                )
+            },
+        );
+
+        Column::new()
+            .spacing(10)
+            .push(range_row)
+            .push(channel_sliders)
+            .into()
+    }
+
+    /// Resampling algorithm selection for the upscale effect
+    fn upscale_controls(&self) -> Element<'_, Event> {
+        let algorithm = match self.shader_parameter("upscale", "algorithm") {
+            Some(UniformValue::Uint(index)) => UpscaleAlgorithm::from_index(*index),
+            _ => None,
+        }
+        .unwrap_or_default();
+
+        Row::new()
+            .spacing(10)
+            .align_y(Center)
+            .push(Text::new("Upscale method").style(text_time))
+            .push(
+                PickList::new(UpscaleAlgorithm::ALL, Some(algorithm), |algorithm| {
+                    Event::UpdateShaderParameter(
+                        "upscale".to_string(),
+                        "algorithm".to_string(),
+                        UniformValue::Uint(algorithm as u32),
+                    )
+                })
+                .text_size(14),
+            )
+            .into()
+    }
+
+    /// Strength slider for the temporal denoise effect
+    fn temporal_denoise_controls(&self) -> Element<'_, Event> {
+        let blend = match self.shader_parameter("temporal_denoise", "blend") {
+            Some(UniformValue::Float(blend)) => *blend,
+            _ => 0.6,
+        };
+
+        Row::new()
+            .spacing(10)
+            .align_y(Center)
+            .push(Text::new("Denoise strength").style(text_time))
+            .push(
+                Slider::new(0.0..=0.95, blend, |value| {
+                    Event::UpdateShaderParameter(
+                        "temporal_denoise".to_string(),
+                        "blend".to_string(),
+                        UniformValue::Float(value),
+                    )
+                })
+                .step(0.01)
+                .style(video_slider),
+            )
+            .push(Text::new(format!("{:.2}", blend)).style(text_time))
+            .into()
+    }
+
+    /// Amount slider for the sharpen effect
+    fn sharpen_controls(&self) -> Element<'_, Event> {
+        let amount = match self.shader_parameter("sharpen", "amount") {
+            Some(UniformValue::Float(amount)) => *amount,
+            _ => 0.5,
+        };
+
+        Row::new()
+            .spacing(10)
+            .align_y(Center)
+            .push(Text::new("Sharpen amount").style(text_time))
+            .push(
+                Slider::new(0.0..=2.0, amount, |value| {
+                    Event::UpdateShaderParameter(
+                        "sharpen".to_string(),
+                        "amount".to_string(),
+                        UniformValue::Float(value),
+                    )
+                })
+                .step(0.05)
+                .style(video_slider),
+            )
+            .push(Text::new(format!("{:.2}", amount)).style(text_time))
+            .into()
+    }
+
+    /// Threshold and smoothness sliders for the chroma key effect
+    fn chroma_key_controls(&self) -> Element<'_, Event> {
+        let slider = |label, name: &'static str, default| {
+            let value = match self.shader_parameter("chroma_key", name) {
+                Some(UniformValue::Float(value)) => *value,
+                _ => default,
+            };
+
+            Row::new()
                 .spacing(10)
-                .push(Text::new("Active Shaders:").style(text_time))
+                .align_y(Center)
+                .push(Text::new(label).style(text_time))
                 .push(
-                    Row::new().spacing(10).push(
-                        Checkbox::new(
-                            "Upscale",
-                            *self.shader_selections.get("upscale").unwrap_or(&false),
+                    Slider::new(0.0..=0.5, value, move |value| {
+                        Event::UpdateShaderParameter(
+                            "chroma_key".to_string(),
+                            name.to_string(),
+                            UniformValue::Float(value),
                         )
-                        .on_toggle(|enabled| Event::ToggleShader("upscale".to_string(), enabled)),
-                    ),
-                ),
+                    })
+                    .step(0.01)
+                    .style(video_slider),
+                )
+                .push(Text::new(format!("{:.2}", value)).style(text_time))
+        };
+
+        column![
+            slider("Key threshold", "threshold", 0.15),
+            slider("Key smoothness", "smoothness", 0.1),
+        ]
+        .spacing(5)
+        .into()
+    }
+
+    /// Level count slider for the posterize effect
+    fn posterize_controls(&self) -> Element<'_, Event> {
+        let levels = match self.shader_parameter("posterize", "levels") {
+            Some(UniformValue::Uint(levels)) => *levels,
+            _ => 4,
+        };
+
+        Row::new()
+            .spacing(10)
+            .align_y(Center)
+            .push(Text::new("Posterize levels").style(text_time))
+            .push(
+                Slider::new(2..=16, levels, |value| {
+                    Event::UpdateShaderParameter(
+                        "posterize".to_string(),
+                        "levels".to_string(),
+                        UniformValue::Uint(value),
+                    )
+                })
+                .style(video_slider),
+            )
+            .push(Text::new(levels.to_string()).style(text_time))
+            .into()
+    }
+
+    /// Threshold and intensity sliders for the bloom effect
+    fn bloom_controls(&self) -> Element<'_, Event> {
+        let parameter = |name, default| match self.shader_parameter("bloom", name) {
+            Some(UniformValue::Float(value)) => *value,
+            _ => default,
+        };
+        let slider = |name: &'static str, range, value: f32| {
+            Slider::new(range, value, move |value| {
+                Event::UpdateShaderParameter(
+                    "bloom".to_string(),
+                    name.to_string(),
+                    UniformValue::Float(value),
+                )
+            })
+            .step(0.01)
+            .style(video_slider)
+        };
+        let threshold = parameter("threshold", 0.7);
+        let intensity = parameter("intensity", 0.8);
+
+        Row::new()
+            .spacing(10)
+            .align_y(Center)
+            .push(Text::new("Bloom threshold").style(text_time))
+            .push(slider("threshold", 0.0..=1.0, threshold))
+            .push(Text::new(format!("{:.2}", threshold)).style(text_time))
+            .push(Text::new("Intensity").style(text_time))
+            .push(slider("intensity", 0.0..=2.0, intensity))
+            .push(Text::new(format!("{:.2}", intensity)).style(text_time))
+            .into()
+    }
+
+    /// Radius and sigma sliders for the blur effect
+    fn blur_controls(&self) -> Element<'_, Event> {
+        let parameter = |name, default| match self.shader_parameter("blur", name) {
+            Some(UniformValue::Float(value)) => *value,
+            _ => default,
+        };
+        let slider = |name: &'static str, range, value: f32| {
+            Slider::new(range, value, move |value| {
+                Event::UpdateShaderParameter(
+                    "blur".to_string(),
+                    name.to_string(),
+                    UniformValue::Float(value),
+                )
+            })
+            .step(0.1)
+            .style(video_slider)
+        };
+        let radius = parameter("radius", 8.0);
+        let sigma = parameter("sigma", 4.0);
+
+        Row::new()
+            .spacing(10)
+            .align_y(Center)
+            .push(Text::new("Blur radius").style(text_time))
+            .push(slider("radius", 0.0..=32.0, radius))
+            .push(Text::new(format!("{:.1}", radius)).style(text_time))
+            .push(Text::new("Sigma").style(text_time))
+            .push(slider("sigma", 0.1..=16.0, sigma))
+            .push(Text::new(format!("{:.1}", sigma)).style(text_time))
+            .into()
+    }
+
+    /// Packing selection for stereoscopic video
+    fn stereo_controls(&self) -> Element<'_, Event> {
+        Row::new()
+            .spacing(10)
+            .align_y(Center)
+            .push(Text::new("Stereo layout").style(text_time))
+            .push(
+                PickList::new(
+                    StereoLayout::ALL,
+                    Some(self.stereo_layout),
+                    Event::SelectStereoLayout,
+                )
+                .text_size(14),
+            )
+            .into()
+    }
+
+    /// Advance playback on a timer, independent of the widget being drawn
+    ///
+    /// Emits `Event::NewFrame` at the frame interval while playing. The widget
+    /// keeps requesting redraws as well, but frames are only advanced when they
+    /// are due by the stream clock, so both paths never skip frames.
+    pub fn subscription(&self) -> iced::Subscription<Event> {
+        let stream = self.stream.borrow();
+        if stream.is_playing() {
+            iced::time::every(stream.get_frame_duration()).map(|_| Event::NewFrame)
+        } else {
+            iced::Subscription::none()
+        }
+    }
+
+    /// Playback position in the selected display mode
+    fn position_text(&self, current: Duration, total: Duration) -> String {
+        let stream = self.stream.borrow();
+        match self.time_display {
+            TimeDisplay::Time => format!("{} / {}", format_time(current), format_time(total)),
+            TimeDisplay::Frames => {
+                // Zero when the container doesn't know the duration
+                let total_frames = Some(stream.total_frames()).filter(|&total| total > 0);
+                format_frames(stream.current_frame(), total_frames)
+            }
+            TimeDisplay::Timecode => stream.current_timecode(),
+            TimeDisplay::Percent => format_percent(current, total),
+        }
+    }
+
+    /// Frame number and timecode drawn over the video, refreshed on each new frame
+    fn timecode_overlay(&self) -> Container<'_, Event> {
+        let stream = self.stream.borrow();
+        let (align_x, align_y) = self.timecode_corner.alignment();
+        Container::new(
+            Text::new(format!(
+                "{}  {}",
+                stream.current_frame(),
+                stream.current_timecode()
+            ))
+            .size(14)
+            .style(text_time),
         )
+        .width(iced::Length::Fill)
+        .height(iced::Length::Fill)
         .padding(10)
-        .style(controls_container);
-        let video_row = {
-            let mut row = Stack::new().push(
-                Container::new(
-                    Video::new(&self.stream)
-                        .width(iced::Length::Fill)
-                        .height(iced::Length::Fill)
-                        .content_fit(iced::ContentFit::Contain)
-                        .comparison_enabled(self.comparison_enabled)
-                        .comparison_position(self.comparison_position)
-                        .shader_selections(self.shader_selections.clone())
-                        .on_comparison_drag_start(Event::ComparisonDragStart)
-                        .on_comparison_drag_end(Event::ComparisonDragEnd)
-                        .on_comparison_position_change(Event::UpdateComparisonPosition(
-                            self.comparison_position,
+        .align_x(align_x)
+        .align_y(align_y)
+    }
+
+    pub fn view(&self) -> Element<Event> {
+        let is_playing = self.stream.borrow().is_playing;
+        let is_looping = self.stream.borrow().looping();
+        // Playing again after the end starts over
+        let at_end = !is_playing && self.stream.borrow().is_end_of_stream();
+        let has_audio = self.stream.borrow().has_audio();
+        let is_muted = self.stream.borrow().is_muted() || self.stream.borrow().volume() == 0.0;
+        let volume = if is_muted {
+            0.0
+        } else {
+            self.stream.borrow().volume()
+        };
+        let current = self.stream.borrow().current_time();
+        let total = self.stream.borrow().trimmed_time();
+        let color_balance_enabled = *self
+            .shader_selections
+            .get("color_balance")
+            .unwrap_or(&false);
+        let temporal_denoise_enabled = *self
+            .shader_selections
+            .get("temporal_denoise")
+            .unwrap_or(&false);
+        let upscale_enabled = *self.shader_selections.get("upscale").unwrap_or(&false);
+        let sharpen_enabled = *self.shader_selections.get("sharpen").unwrap_or(&false);
+        let posterize_enabled = *self.shader_selections.get("posterize").unwrap_or(&false);
+        let bloom_enabled = *self.shader_selections.get("bloom").unwrap_or(&false);
+        let blur_enabled = *self.shader_selections.get("blur").unwrap_or(&false);
+        let chroma_key_enabled = *self.shader_selections.get("chroma_key").unwrap_or(&false);
+        let mut shader_column = Column::new()
+            .spacing(10)
+            .push(Text::new("Active Shaders:").style(text_time))
+            .push(
+                Checkbox::new("Bypass Effects", self.bypass_effects)
+                    .on_toggle(Event::ToggleBypassEffects),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .push(
+                        Checkbox::new("Upscale", upscale_enabled).on_toggle(|enabled| {
+                            Event::ToggleShader("upscale".to_string(), enabled)
+                        }),
+                    )
+                    .push(
+                        Checkbox::new("Sharpen", sharpen_enabled).on_toggle(|enabled| {
+                            Event::ToggleShader("sharpen".to_string(), enabled)
+                        }),
+                    )
+                    .push(
+                        Checkbox::new("Color Balance", color_balance_enabled).on_toggle(
+                            |enabled| Event::ToggleShader("color_balance".to_string(), enabled),
+                        ),
+                    )
+                    .push(
+                        Checkbox::new("Denoise", temporal_denoise_enabled).on_toggle(|enabled| {
+                            Event::ToggleShader("temporal_denoise".to_string(), enabled)
+                        }),
+                    )
+                    .push(
+                        Checkbox::new("Posterize", posterize_enabled).on_toggle(|enabled| {
+                            Event::ToggleShader("posterize".to_string(), enabled)
+                        }),
+                    )
+                    .push(
+                        Checkbox::new("Bloom", bloom_enabled)
+                            .on_toggle(|enabled| Event::ToggleShader("bloom".to_string(), enabled)),
+                    )
+                    .push(
+                        Checkbox::new("Blur", blur_enabled)
+                            .on_toggle(|enabled| Event::ToggleShader("blur".to_string(), enabled)),
+                    )
+                    .push(
+                        Checkbox::new("Chroma Key", chroma_key_enabled).on_toggle(|enabled| {
+                            Event::ToggleShader("chroma_key".to_string(), enabled)
+                        }),
+                    )
+                    .push(
+                        Checkbox::new("Stereo 3D", self.stereo_enabled)
+                            .on_toggle(Event::ToggleStereo),
+                    ),
+            )
+            .push(
+                SimpleColorMode::ALL
+                    .iter()
+                    .filter_map(|mode| mode.selection_key().map(|key| (mode, key)))
+                    .fold(Row::new().spacing(10), |row, (mode, key)| {
+                        let selected = *self.shader_selections.get(key).unwrap_or(&false);
+                        row.push(Checkbox::new(mode.to_string(), selected).on_toggle(
+                            move |enabled| Event::ToggleShader(key.to_string(), enabled),
                         ))
-                        .on_end_of_stream(Event::EndOfStream)
-                        .on_new_frame(Event::NewFrame),
-                )
-                .width(iced::Length::Fill)
-                .height(iced::Length::Fill)
-                .style(video_container),
+                    }),
             );
-
-            if self.comparison_enabled {
-                row = row.push(
+        if upscale_enabled {
+            shader_column = shader_column.push(self.upscale_controls());
+        }
+        if sharpen_enabled {
+            shader_column = shader_column.push(self.sharpen_controls());
+        }
+        if color_balance_enabled {
+            shader_column = shader_column.push(self.color_balance_controls());
+        }
+        if temporal_denoise_enabled {
+            shader_column = shader_column.push(self.temporal_denoise_controls());
+        }
+        if posterize_enabled {
+            shader_column = shader_column.push(self.posterize_controls());
+        }
+        if bloom_enabled {
+            shader_column = shader_column.push(self.bloom_controls());
+        }
+        if blur_enabled {
+            shader_column = shader_column.push(self.blur_controls());
+        }
+        if chroma_key_enabled {
+            shader_column = shader_column.push(self.chroma_key_controls());
+        }
+        if self.stereo_enabled {
+            shader_column = shader_column.push(self.stereo_controls());
+        }
+        let shader_controls = Container::new(shader_column)
+            .padding(10)
+            .style(controls_container);
+        let seek_step = self.seek_step;
+        let video_row = {
+            Stack::new()
+                .push(
                     Container::new(
-                        Slider::new(0.0..=1.0, self.comparison_position, |pos| {
-                            Event::UpdateComparisonPosition(pos)
-                        })
-                        .style(comparison_slider_style)
-                        .step(0.001),
+                        Video::new(&self.stream)
+                            .width(iced::Length::Fill)
+                            .height(iced::Length::Fill)
+                            .content_fit(iced::ContentFit::Contain)
+                            .letterbox_color(self.letterbox_color)
+                            .zoomable(true)
+                            .sampling(self.sampling)
+                            .bypass_effects(self.bypass_effects)
+                            .comparison_enabled(self.comparison_enabled)
+                            .comparison_position(self.comparison_position)
+                            .comparison_orientation(self.comparison_orientation)
+                            .shader_selections(self.shader_selections.clone())
+                            .shader_parameters(self.shader_parameters.clone())
+                            .stereo_layout(self.stereo_enabled.then_some(self.stereo_layout))
+                            .screenshot(self.pending_screenshot.take())
+                            .on_comparison_drag_start(Event::ComparisonDragStart)
+                            .on_comparison_drag_end(Event::ComparisonDragEnd)
+                            .on_comparison_position_change(Event::UpdateComparisonPosition)
+                            .on_comparison_nudge(Event::UpdateComparisonPosition)
+                            .on_shortcut(move |shortcut| match shortcut {
+                                Shortcut::PlayPause => Event::Pause,
+                                Shortcut::SeekBackward => Event::SeekBy(-seek_step),
+                                Shortcut::SeekForward => Event::SeekBy(seek_step),
+                                Shortcut::StepBackward => Event::StepBackward,
+                                Shortcut::StepForward => Event::StepForward,
+                                Shortcut::Fullscreen => Event::ToggleFullscreen,
+                            })
+                            .on_end_of_stream(Event::EndOfStream)
+                            .on_new_frame(Event::NewFrame)
+                            .on_state_change(Event::PlaybackStateChanged)
+                            .on_error(|e| Event::PlaybackFailed(e.to_string())),
                     )
                     .width(iced::Length::Fill)
                     .height(iced::Length::Fill)
-                    .align_y(Center)
-                    .align_x(Center),
-                );
-            }
-
-            row
+                    .style(video_container),
+                )
+                .push_maybe(self.show_timecode.then(|| self.timecode_overlay()))
         };
 
         Column::new()
@@ -224,12 +1046,43 @@ impl Player {
                                     } else {
                                         pause(16.0, None)
                                     })
-                                    .push(Text::new(if !is_playing { "Play" } else { "Pause" })),
+                                    .push(Text::new(if at_end {
+                                        "Replay"
+                                    } else if !is_playing {
+                                        "Play"
+                                    } else {
+                                        "Pause"
+                                    })),
                             )
                             .width(100.0)
-                            .on_press(Event::Pause)
+                            .on_press(if at_end { Event::Restart } else { Event::Pause })
                             .style(primary_button),
                         )
+                        .push(
+                            Button::new(step_backward(16.0, None))
+                                .on_press(Event::StepBackward)
+                                .style(secondary_button),
+                        )
+                        .push(
+                            Button::new(step_forward(16.0, None))
+                                .on_press(Event::StepForward)
+                                .style(secondary_button),
+                        )
+                        .push(
+                            Button::new(camera(16.0, None))
+                                .on_press(Event::Screenshot)
+                                .style(secondary_button),
+                        )
+                        .push(
+                            // Highlighted while looping
+                            Button::new(repeat(16.0, None)).on_press(Event::Loop).style(
+                                if is_looping {
+                                    primary_button
+                                } else {
+                                    secondary_button
+                                },
+                            ),
+                        )
                         .push(
                             Button::new(
                                 Row::new()
@@ -246,16 +1099,64 @@ impl Player {
                             .on_press(Event::ToggleComparison)
                             .style(secondary_button),
                         )
+                        .push_maybe(self.comparison_enabled.then(|| {
+                            PickList::new(
+                                ComparisonOrientation::ALL,
+                                Some(self.comparison_orientation),
+                                Event::SelectComparisonOrientation,
+                            )
+                            .text_size(14)
+                        }))
+                        .push(Text::new("Speed").style(text_time))
+                        .push(
+                            PickList::new(
+                                PLAYBACK_SPEEDS,
+                                Some(self.stream.borrow().playback_speed()),
+                                Event::SetSpeed,
+                            )
+                            .text_size(14),
+                        )
+                        .push_maybe(has_audio.then(|| {
+                            Button::new(if is_muted {
+                                volume_mute(16.0, None)
+                            } else {
+                                volume_icon(16.0, None)
+                            })
+                            .on_press(Event::ToggleMute)
+                            .style(secondary_button)
+                        }))
+                        .push_maybe(has_audio.then(|| {
+                            Slider::new(0.0..=1.0, volume, Event::SetVolume)
+                                .step(0.01)
+                                .width(100.0)
+                                .style(video_slider)
+                        }))
                         .push(horizontal_space())
                         .push(
-                            Text::new(format!(
-                                "{:02}:{:02} / {:02}:{:02}",
-                                (current.as_secs_f64() / 60.0).floor() as u64,
-                                (current.as_secs_f64() % 60.0).floor() as u64,
-                                (total.as_secs_f64() / 60.0).floor() as u64,
-                                (total.as_secs_f64() % 60.0).floor() as u64
-                            ))
+                            Text::new(match self.playback_state {
+                                PlaybackState::Buffering => "Buffering...",
+                                PlaybackState::Error => {
+                                    self.error.as_deref().unwrap_or("Playback error")
+                                }
+                                _ => "",
+                            })
                             .style(text_time),
+                        )
+                        .push(
+                            // Jump to an SMPTE timecode or frame number on Enter
+                            TextInput::new("HH:MM:SS:FF", &self.timecode_input)
+                                .on_input(Event::TimecodeInputChanged)
+                                .on_submit(Event::SeekToTimecode)
+                                .width(120.0)
+                                .size(14),
+                        )
+                        .push(
+                            // Click to cycle between time, frames, timecode and percent
+                            Button::new(
+                                Text::new(self.position_text(current, total)).style(text_time),
+                            )
+                            .on_press(Event::ToggleTimeDisplay)
+                            .style(iced::widget::button::text),
                         ),
                 )
                 .style(controls_container)
@@ -267,12 +1168,120 @@ impl Player {
     }
 }
 
+// Playback speeds offered in the controls, as multipliers of the frame rate
+const PLAYBACK_SPEEDS: [f32; 7] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 4.0];
+
+// Default seconds skipped by the left and right arrow keys
+const DEFAULT_SEEK_STEP: f64 = 5.0;
+
+// Comparison position steps for the arrow keys, without and with Shift
+const COMPARISON_NUDGE_STEP: f32 = 0.01;
+const COMPARISON_NUDGE_STEP_LARGE: f32 = 0.1;
+
+// Distance in pixels from the comparison divider within which a click grabs it
+const COMPARISON_GRAB_DISTANCE: f32 = 10.0;
+
+// Zoom limit and the factor of one scroll wheel line
+const MAX_ZOOM: f32 = 16.0;
+const ZOOM_STEP: f32 = 1.1;
+// Pixels of smooth scrolling counted as one line
+const PIXELS_PER_LINE: f32 = 60.0;
+
+/// Zoom and pan of the video, see [`Video::zoomable`]
+#[derive(Clone, Copy, Debug)]
+struct Zoom {
+    scale: f32,           // Magnification of the fitted video, 1.0 shows it as fitted
+    offset: iced::Vector, // Center of the zoomed video from the center of the fitted one
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset: iced::Vector::ZERO,
+        }
+    }
+}
+
+impl Zoom {
+    fn is_zoomed(&self) -> bool {
+        self.scale > 1.0
+    }
+
+    /// Rectangle of the video fitted to `fitted` after zooming and panning
+    fn apply(&self, fitted: iced::Rectangle) -> iced::Rectangle {
+        let size = fitted.size() * self.scale;
+        let center = fitted.center() + self.offset;
+        iced::Rectangle::new(
+            iced::Point::new(center.x - size.width / 2.0, center.y - size.height / 2.0),
+            size,
+        )
+    }
+
+    /// Zoom in or out by scroll wheel lines, keeping the point under the cursor in place
+    fn zoom_at(&mut self, fitted: iced::Rectangle, cursor: iced::Point, lines: f32) {
+        let scale = (self.scale * ZOOM_STEP.powf(lines)).clamp(1.0, MAX_ZOOM);
+        let center = fitted.center() + self.offset;
+        let zoomed_center = cursor - (cursor - center) * (scale / self.scale);
+        self.scale = scale;
+        self.offset = zoomed_center - fitted.center();
+        self.clamp(fitted);
+    }
+
+    /// Move the zoomed video by a cursor movement
+    fn pan(&mut self, fitted: iced::Rectangle, delta: iced::Vector) {
+        self.offset = self.offset + delta;
+        self.clamp(fitted);
+    }
+
+    /// Keep the fitted area covered, so the video can't be panned off-screen
+    fn clamp(&mut self, fitted: iced::Rectangle) {
+        let max = fitted.size() * ((self.scale - 1.0) / 2.0);
+        self.offset = iced::Vector::new(
+            self.offset.x.clamp(-max.width, max.width),
+            self.offset.y.clamp(-max.height, max.height),
+        );
+    }
+}
+
+/// Widget state kept across frames
+#[derive(Default)]
+struct State {
+    dragging_comparison: bool, // The comparison divider is being dragged
+    zoom: Zoom,
+    panning: Option<iced::Point>, // Last cursor position while dragging the zoomed video
+    last_click: Option<advanced::mouse::Click>, // For double clicks resetting the zoom
+    is_focused: bool,             // Receives keyboard input, set by clicking the video
+    last_published_frame: Option<u64>, // Frame number of the last new frame event
+    seeks: u64,                   // Stream counters when events were last published
+    loops: u64,
+    underruns: u64,
+    error_reported: bool, // The stream's error has been published
+    buffering: bool,      // Buffering state last published
+    ended: bool,          // Ended has been published, cleared once playback leaves the end
+}
+
 impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
     for Video<'a, Message, Theme, Renderer>
 where
     Message: Clone,
     Renderer: PrimitiveRenderer,
 {
+    fn tag(&self) -> widget::tree::Tag {
+        widget::tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> widget::tree::State {
+        // Only report seeks, loops and underruns that happen while shown
+        let video = self.video.borrow();
+        widget::tree::State::new(State {
+            seeks: video.seeks(),
+            loops: video.loops(),
+            underruns: video.underruns(),
+            ..State::default()
+        })
+    }
+
     fn size(&self) -> iced::Size<iced::Length> {
         iced::Size {
             width: self.width,
@@ -286,12 +1295,7 @@ where
         _renderer: &Renderer,
         limits: &layout::Limits,
     ) -> layout::Node {
-        let (video_width, video_height) = (
-            self.video.borrow().decoder.width() as f32,
-            self.video.borrow().decoder.height() as f32,
-        );
-
-        let image_size = iced::Size::new(video_width, video_height);
+        let image_size = self.cropped_size(&self.video.borrow());
         let raw_size = limits.resolve(self.width, self.height, image_size);
         let full_size = self.content_fit.fit(image_size, raw_size);
         let final_size = iced::Size {
@@ -309,7 +1313,7 @@ where
     }
     fn draw(
         &self,
-        _tree: &widget::Tree,
+        tree: &widget::Tree,
         renderer: &mut Renderer,
         _theme: &Theme,
         _style: &advanced::renderer::Style,
@@ -319,58 +1323,106 @@ where
     ) {
         let mut video = self.video.borrow_mut();
         let bounds = layout.bounds();
-        let image_size =
-            iced::Size::new(video.decoder.width() as f32, video.decoder.height() as f32);
+        let frame_size = (video.width(), video.height());
+        let zoom = tree.state.downcast_ref::<State>().zoom;
+        let drawing_bounds = zoom.apply(self.drawing_bounds(&video, bounds));
 
-        let adjusted_fit = self.content_fit.fit(image_size, bounds.size());
-        let scale = iced::Vector::new(
-            adjusted_fit.width / image_size.width,
-            adjusted_fit.height / image_size.height,
-        );
-        let final_size = image_size * scale;
-
-        let position = match self.content_fit {
-            iced::ContentFit::None => iced::Point::new(
-                bounds.x + (image_size.width - adjusted_fit.width) / 2.0,
-                bounds.y + (image_size.height - adjusted_fit.height) / 2.0,
-            ),
-            _ => iced::Point::new(
-                bounds.center_x() - final_size.width / 2.0,
-                bounds.center_y() - final_size.height / 2.0,
-            ),
-        };
-
-        let drawing_bounds = iced::Rectangle::new(position, final_size);
+        // Pad the area around the fitted video, independent of the parent container
+        if self.letterbox_color.a > 0.0 {
+            renderer.fill_quad(
+                advanced::renderer::Quad {
+                    bounds,
+                    ..advanced::renderer::Quad::default()
+                },
+                self.letterbox_color,
+            );
+        }
 
-        // Get frame data, whether playing or not
-        let frame_data = if let Ok(Some(data)) = video.update() {
-            Some(data)
-        } else {
-            None
-        };
+        // Both videos update and upload their own frames, laid out by the primitive
+        if let Some(other) = self.side_by_side {
+            let mut other = other.borrow_mut();
+            let grid_video = |video: &mut VideoStream| GridVideo {
+                video_id: video.id(),
+                frame: video.update().ok().flatten().map(Arc::new),
+                size: (video.width(), video.height()),
+                color_space: video.color_info().space,
+                color_range: video.color_info().range,
+                owner: Some(video.owner()),
+            };
+            let primitive = VideoPrimitive::new(
+                video.id(),
+                true,
+                Arc::default(),
+                frame_size,
+                false, // Frames are uploaded per grid video
+                video.color_info().space,
+            )
+            .with_grid(vec![grid_video(&mut video), grid_video(&mut other)])
+            .with_pipeline_config(self.pipeline_config.clone());
+            renderer.draw_primitive(bounds, primitive);
+            return;
+        }
 
-        // Render frame if we have data
-        if let Some(frame_data) = frame_data {
-            let frame_id = video.current_frame();
-            // tracing::info!("Rendering frame {}", frame_id);
+        // Get frame data, whether playing or not. Errors are kept by the stream
+        // and reported from `on_event`
+        let frame_data = video.update().ok().flatten();
 
+        // Keep showing the last uploaded frame while the queue is refilled
+        if frame_data.is_some() || video.playback_state() != PlaybackState::Opening {
+            let upload_frame = frame_data.is_some();
             let primitive = VideoPrimitive::new(
-                frame_id, // Use current frame as unique ID
-                true,     // Force update
-                frame_data,
-                (image_size.width as _, image_size.height as _),
-                true, // Always create new texture
-                video.color_space,
+                video.id(), // Reuse the GPU resources of this stream across frames
+                true,       // Force update
+                Arc::new(frame_data.unwrap_or_default()),
+                frame_size,
+                upload_frame,
+                video.color_info().space,
             )
+            .with_color_range(video.color_info().range)
+            .with_color_transfer(video.color_info().transfer)
+            .with_owner(video.owner())
+            .with_crop(self.crop)
             .with_comparison(self.comparison_enabled)
             .with_comparison_position(self.comparison_position)
-            .with_shader_selections(self.shader_selections.clone());
+            .with_comparison_orientation(self.comparison_orientation)
+            .with_comparison_line(self.comparison_line_color, self.comparison_line_width)
+            .with_shader_selections(self.shader_selections.clone())
+            .with_shader_parameters(self.shader_parameters.clone())
+            .with_custom_effects(self.custom_effects.clone())
+            .with_lut(self.lut.clone())
+            .with_stereo_layout(self.stereo_layout)
+            .with_pipeline_config(self.pipeline_config.clone());
+            let primitive = match self.screenshot.take() {
+                Some(path) => primitive.with_screenshot(path),
+                None => primitive,
+            };
+
+            // Second view of the same frame, drawn after the main one
+            let pip = self.pip.map(|(region, pip_bounds)| {
+                let pip_primitive = VideoPrimitive::new(
+                    video.id(),
+                    true,
+                    Arc::default(),
+                    frame_size,
+                    false, // Frame is uploaded by the main view
+                    video.color_info().space,
+                )
+                .with_color_range(video.color_info().range)
+                .with_pip_region(region);
+                (
+                    pip_bounds + iced::Vector::new(bounds.x, bounds.y),
+                    pip_primitive,
+                )
+            });
 
             let render = |renderer: &mut Renderer| {
                 renderer.draw_primitive(drawing_bounds, primitive.clone());
+                if let Some((pip_bounds, pip_primitive)) = &pip {
+                    renderer.draw_primitive(*pip_bounds, pip_primitive.clone());
+                }
             };
 
-            if adjusted_fit.width > bounds.width || adjusted_fit.height > bounds.height {
+            if drawing_bounds.width > bounds.width || drawing_bounds.height > bounds.height {
                 renderer.with_layer(bounds, render);
             } else {
                 render(renderer);
@@ -380,7 +1432,7 @@ where
 
     fn on_event(
         &mut self,
-        _state: &mut widget::Tree,
+        tree: &mut widget::Tree,
         event: iced::Event,
         layout: advanced::Layout<'_>,
         cursor: advanced::mouse::Cursor,
@@ -390,43 +1442,138 @@ where
         _viewport: &iced::Rectangle,
     ) -> Status {
         if let iced::Event::Window(iced::window::Event::RedrawRequested(_)) = event {
-            let video = self.video.borrow_mut();
+            let mut video = self.video.borrow_mut();
+
+            // Report playback state transitions, e.g. for buffering indicators
+            if let Some(state) = video.take_state_change() {
+                if let Some(ref on_state_change) = self.on_state_change {
+                    shell.publish(on_state_change(state));
+                }
+            }
 
-            if video.is_playing() {
+            let mut events = Vec::new();
+            let state = tree.state.downcast_mut::<State>();
+            let buffering = video.is_buffering();
+            if buffering != state.buffering {
+                state.buffering = buffering;
+                if let Some(ref on_buffering) = self.on_buffering {
+                    shell.publish(on_buffering(buffering));
+                }
+            }
+            if video.underruns() != state.underruns {
+                state.underruns = video.underruns();
+                events.push(PlaybackEvent::BufferUnderrun);
+            }
+            if video.loops() != state.loops {
+                state.loops = video.loops();
+                events.push(PlaybackEvent::Looped);
+            }
+            if video.seeks() != state.seeks {
+                state.seeks = video.seeks();
+                events.push(PlaybackEvent::SeekCompleted);
+            }
+
+            // Only report frames that were actually advanced to, not every redraw
+            let frame = video.current_frame();
+            if state.last_published_frame != Some(frame) {
+                state.last_published_frame = Some(frame);
+                events.push(PlaybackEvent::FramePresented {
+                    frame,
+                    pts: video.current_time(),
+                });
+            }
+
+            // Report a failed update once and stop redrawing until it's cleared
+            let failed = match video.last_error() {
+                Some(error) => {
+                    if !state.error_reported {
+                        state.error_reported = true;
+                        if let Some(ref on_error) = self.on_error {
+                            shell.publish(on_error(error));
+                        }
+                    }
+                    true
+                }
+                None => {
+                    state.error_reported = false;
+                    false
+                }
+            };
+
+            if failed {
+                tracing::trace!("Playback failed, not requesting redraws");
+            } else if video.is_playing() {
                 // Get the video's frame duration
                 let frame_duration = video.get_frame_duration();
                 shell.request_redraw(iced::window::RedrawRequest::NextFrame);
 
-                if let Some(ref message) = self.on_new_frame {
-                    shell.publish(message.clone());
-                }
-                // Check for end of video
-                if video.current_frame() >= video.end_frame().unwrap() {
-                    if let Some(ref message) = self.on_end_of_stream {
-                        shell.publish(message.clone());
-                    }
+                // Check for end of video, looping videos start over instead
+                // The end frame is unknown for containers without a duration
+                let at_end = video.is_end_of_stream()
+                    || video
+                        .known_end_frame()
+                        .is_some_and(|end| video.current_frame() >= end);
+                let ended = at_end && !video.looping();
+                if ended && !state.ended {
+                    events.push(PlaybackEvent::Ended);
                 }
+                state.ended = ended;
 
                 // Only schedule one redraw
                 shell.request_redraw(iced::window::RedrawRequest::At(
                     Instant::now() + frame_duration,
                 ));
+            } else if let Some(other) = self
+                .side_by_side
+                .filter(|other| other.borrow().is_playing())
+            {
+                // Keep the side by side video advancing while this one is paused
+                shell.request_redraw(iced::window::RedrawRequest::At(
+                    Instant::now() + other.borrow().get_frame_duration(),
+                ));
             } else {
                 shell.request_redraw(iced::window::RedrawRequest::At(
                     Instant::now() + Duration::from_millis(32),
                 ));
             }
+
+            for event in events {
+                for on_event in &self.on_event {
+                    if let Some(message) = on_event(event) {
+                        shell.publish(message);
+                    }
+                }
+            }
             Status::Captured
         } else if let iced::Event::Mouse(mouse_event) = event {
             let bounds = layout.bounds();
 
+            // Clicking the video focuses it for keyboard input, clicking elsewhere unfocuses it
+            if let iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left) = mouse_event {
+                tree.state.downcast_mut::<State>().is_focused = cursor.is_over(bounds);
+            }
+
+            let fitted = self.drawing_bounds(&self.video.borrow(), bounds);
             if self.comparison_enabled {
-                let split_x = bounds.x + (bounds.width * self.comparison_position);
+                // The divider splits the zoomed video, not the letterbox bars around it
+                let video_bounds = tree.state.downcast_ref::<State>().zoom.apply(fitted);
+                let vertical = self.comparison_orientation == ComparisonOrientation::Vertical;
+                // Coordinate across the split and the start and length of the video along it
+                let (start, length) = if vertical {
+                    (video_bounds.y, video_bounds.height)
+                } else {
+                    (video_bounds.x, video_bounds.width)
+                };
+                let across = |position: iced::Point| if vertical { position.y } else { position.x };
+                let split = start + length * self.comparison_position;
+                let state = tree.state.downcast_mut::<State>();
 
                 match mouse_event {
                     iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left) => {
-                        if let Some(position) = cursor.position() {
-                            if (position.x - split_x).abs() < 10.0 {
+                        // Only a thin region around the divider grabs it
+                        if let Some(position) = cursor.position_over(bounds) {
+                            if (across(position) - split).abs() < COMPARISON_GRAB_DISTANCE {
+                                state.dragging_comparison = true;
                                 if let Some(ref message) = self.on_comparison_drag_start {
                                     shell.publish(message.clone());
                                 }
@@ -435,21 +1582,23 @@ where
                         }
                     }
                     iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left) => {
-                        if let Some(ref message) = self.on_comparison_drag_end {
-                            shell.publish(message.clone());
+                        if state.dragging_comparison {
+                            state.dragging_comparison = false;
+                            if let Some(ref message) = self.on_comparison_drag_end {
+                                shell.publish(message.clone());
+                            }
+                            return Status::Captured;
                         }
-                        return Status::Captured;
                     }
                     iced::mouse::Event::CursorMoved { position } => {
-                        if self.dragging_comparison {
-                            let bounds = layout.bounds();
+                        if state.dragging_comparison {
                             let new_position =
-                                ((position.x - bounds.x) / bounds.width).clamp(0.0, 1.0);
+                                ((across(position) - start) / length).clamp(0.0, 1.0);
 
                             self.comparison_position = new_position;
 
-                            if let Some(ref message) = self.on_comparison_position_change {
-                                shell.publish(message.clone());
+                            if let Some(ref on_change) = self.on_comparison_position_change {
+                                shell.publish(on_change(new_position));
                             }
                             return Status::Captured;
                         }
@@ -458,7 +1607,130 @@ where
                 }
             }
 
+            if self.zoomable {
+                let state = tree.state.downcast_mut::<State>();
+                let handled = match mouse_event {
+                    iced::mouse::Event::WheelScrolled { delta } => {
+                        match cursor.position_over(bounds) {
+                            Some(position) => {
+                                let lines = match delta {
+                                    iced::mouse::ScrollDelta::Lines { y, .. } => y,
+                                    iced::mouse::ScrollDelta::Pixels { y, .. } => {
+                                        y / PIXELS_PER_LINE
+                                    }
+                                };
+                                state.zoom.zoom_at(fitted, position, lines);
+                                true
+                            }
+                            None => false,
+                        }
+                    }
+                    iced::mouse::Event::ButtonPressed(iced::mouse::Button::Left) => {
+                        match cursor.position_over(bounds) {
+                            Some(position) => {
+                                let click = advanced::mouse::Click::new(
+                                    position,
+                                    iced::mouse::Button::Left,
+                                    state.last_click,
+                                );
+                                state.last_click = Some(click);
+                                if matches!(click.kind(), advanced::mouse::click::Kind::Double) {
+                                    state.zoom = Zoom::default();
+                                    state.panning = None;
+                                    true
+                                } else if state.zoom.is_zoomed() {
+                                    // Only the zoomed video has anything to pan to
+                                    state.panning = Some(position);
+                                    true
+                                } else {
+                                    false
+                                }
+                            }
+                            None => false,
+                        }
+                    }
+                    iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left) => {
+                        state.panning.take().is_some()
+                    }
+                    iced::mouse::Event::CursorMoved { position } => match state.panning {
+                        Some(last) => {
+                            state.zoom.pan(fitted, position - last);
+                            state.panning = Some(position);
+                            true
+                        }
+                        None => false,
+                    },
+                    _ => false,
+                };
+                if handled {
+                    shell.request_redraw(iced::window::RedrawRequest::NextFrame);
+                    return Status::Captured;
+                }
+            }
+
             return Status::Ignored;
+        } else if let iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
+            key,
+            modifiers,
+            ..
+        }) = event
+        {
+            use iced::keyboard::key::Named;
+            use iced::keyboard::Key;
+
+            let state = tree.state.downcast_ref::<State>();
+            if !state.is_focused {
+                return Status::Ignored;
+            }
+
+            if self.comparison_enabled {
+                let step = if modifiers.shift() {
+                    COMPARISON_NUDGE_STEP_LARGE
+                } else {
+                    COMPARISON_NUDGE_STEP
+                };
+                // Arrows along the split move the divider
+                let vertical = self.comparison_orientation == ComparisonOrientation::Vertical;
+                let position = match key.as_ref() {
+                    Key::Named(Named::ArrowLeft) if !vertical => {
+                        Some(self.comparison_position - step)
+                    }
+                    Key::Named(Named::ArrowRight) if !vertical => {
+                        Some(self.comparison_position + step)
+                    }
+                    Key::Named(Named::ArrowUp) if vertical => Some(self.comparison_position - step),
+                    Key::Named(Named::ArrowDown) if vertical => {
+                        Some(self.comparison_position + step)
+                    }
+                    Key::Named(Named::Home) => Some(0.0),
+                    Key::Named(Named::End) => Some(1.0),
+                    _ => None,
+                };
+
+                if let Some(position) = position {
+                    self.comparison_position = position.clamp(0.0, 1.0);
+                    if let Some(ref on_nudge) = self.on_comparison_nudge {
+                        shell.publish(on_nudge(self.comparison_position));
+                    }
+                    return Status::Captured;
+                }
+            }
+
+            let Some(ref on_shortcut) = self.on_shortcut else {
+                return Status::Ignored;
+            };
+            let shortcut = match key.as_ref() {
+                Key::Named(Named::Space) => Shortcut::PlayPause,
+                Key::Named(Named::ArrowLeft) => Shortcut::SeekBackward,
+                Key::Named(Named::ArrowRight) => Shortcut::SeekForward,
+                Key::Character(",") => Shortcut::StepBackward,
+                Key::Character(".") => Shortcut::StepForward,
+                Key::Character(c) if c.eq_ignore_ascii_case("f") => Shortcut::Fullscreen,
+                _ => return Status::Ignored,
+            };
+
+            shell.publish(on_shortcut(shortcut));
+            Status::Captured
         } else {
             Status::Ignored
         }
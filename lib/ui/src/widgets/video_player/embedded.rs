@@ -0,0 +1,97 @@
+use orbit_video_player_common::VideoError;
+use orbit_video_player_core::video::stream::{PlaybackState, SeekMode, VideoStream};
+use std::{cell::RefCell, time::Duration};
+
+use super::{element::Player, Video};
+
+/// A video without built-in controls, for embedding in a larger app
+///
+/// Owns the stream and exposes playback methods, so the host can build its
+/// own controls around the bare `Video` surface:
+///
+/// ```ignore
+/// // In update
+/// Message::TogglePlayback => self.video.toggle_playback(),
+/// Message::Seek(secs) => self.video.seek(secs)?,
+///
+/// // In view
+/// self.video.view().width(Length::Fill).on_new_frame(Message::NewFrame)
+/// ```
+///
+/// Playback advances while the surface is drawn, use `on_new_frame` to
+/// refresh any position display of the host.
+pub struct EmbeddedVideo {
+    stream: RefCell<VideoStream>,
+}
+
+impl EmbeddedVideo {
+    /// Wrap an already opened video stream
+    pub fn new(stream: VideoStream) -> Self {
+        Self {
+            stream: RefCell::new(stream),
+        }
+    }
+
+    /// Open the video at the given path
+    pub fn from_path(path: &str) -> Result<Self, VideoError> {
+        Ok(Self::new(Player::load_stream(path)?))
+    }
+
+    /// Get the bare video surface, configure it with the `Video` builder methods
+    pub fn view<'a, Message: Clone + 'a>(&'a self) -> Video<'a, Message> {
+        Video::new(&self.stream)
+    }
+
+    pub fn play(&self) {
+        self.stream.borrow_mut().play();
+    }
+
+    pub fn pause(&self) {
+        self.stream.borrow_mut().pause();
+    }
+
+    /// Play if paused, pause if playing
+    pub fn toggle_playback(&self) {
+        let mut stream = self.stream.borrow_mut();
+        if stream.is_playing() {
+            stream.pause();
+        } else {
+            stream.play();
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.stream.borrow().is_playing()
+    }
+
+    /// Seek to the given time in seconds, clamped to the video duration
+    pub fn seek(&self, secs: f64) -> Result<(), VideoError> {
+        self.stream
+            .borrow_mut()
+            .seek_to_time(secs, SeekMode::Accurate)
+    }
+
+    /// Seek to the keyframe at or before the given time, e.g. while scrubbing
+    pub fn seek_fast(&self, secs: f64) -> Result<(), VideoError> {
+        self.stream.borrow_mut().seek_to_time(secs, SeekMode::Fast)
+    }
+
+    /// Current playback position
+    pub fn position(&self) -> Duration {
+        self.stream.borrow().current_time()
+    }
+
+    /// Total duration of the video
+    pub fn duration(&self) -> Result<Duration, VideoError> {
+        self.stream.borrow().total_time()
+    }
+
+    pub fn playback_state(&self) -> PlaybackState {
+        self.stream.borrow().playback_state()
+    }
+
+    /// Access the underlying stream, e.g. for frame-accurate queries
+    pub fn stream(&self) -> &RefCell<VideoStream> {
+        &self.stream
+    }
+}
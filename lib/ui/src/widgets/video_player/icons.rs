@@ -33,6 +33,36 @@ pub fn pause<'a, Message: 'a>(size: f32, color: Option<iced::Color>) -> Element<
     create("pause", size, size, color)
 }
 
+pub fn repeat<'a, Message: 'a>(size: f32, color: Option<iced::Color>) -> Element<'a, Message> {
+    create("loop", size, size, color)
+}
+
+pub fn camera<'a, Message: 'a>(size: f32, color: Option<iced::Color>) -> Element<'a, Message> {
+    create("camera", size, size, color)
+}
+
+pub fn volume<'a, Message: 'a>(size: f32, color: Option<iced::Color>) -> Element<'a, Message> {
+    create("volume", size, size, color)
+}
+
+pub fn volume_mute<'a, Message: 'a>(size: f32, color: Option<iced::Color>) -> Element<'a, Message> {
+    create("volume_mute", size, size, color)
+}
+
+pub fn step_backward<'a, Message: 'a>(
+    size: f32,
+    color: Option<iced::Color>,
+) -> Element<'a, Message> {
+    create("step_backward", size, size, color)
+}
+
+pub fn step_forward<'a, Message: 'a>(
+    size: f32,
+    color: Option<iced::Color>,
+) -> Element<'a, Message> {
+    create("step_forward", size, size, color)
+}
+
 pub fn comparison<'a, Message: 'a>(size: f32, color: Option<iced::Color>) -> Element<'a, Message> {
     create("comparison", size, size, color)
 }
@@ -1,11 +1,35 @@
 use iced_wgpu::primitive::Renderer as PrimitiveRenderer;
-use orbit_video_player_core::video::stream::VideoStream;
-use std::{cell::RefCell, collections::HashMap, marker::PhantomData};
+use orbit_video_player_common::VideoError;
+use orbit_video_player_core::video::{
+    pipeline::{
+        effects::{
+            comparison::ComparisonOrientation, custom::CustomEffectSource, lut::CubeLut,
+            stereo::StereoLayout, EffectParameters,
+        },
+        video::{clamp_region, FULL_REGION},
+        PipelineConfig, SamplingMode,
+    },
+    stream::{PlaybackEvent, PlaybackState, VideoStream},
+};
+use std::{cell::RefCell, collections::HashMap, marker::PhantomData, path::PathBuf, sync::Arc};
 
 pub mod compariosn_slider;
 pub mod element;
+pub mod embedded;
 pub mod icons;
 pub mod theme;
+pub mod time_format;
+
+/// Playback action bound to a key, see [`Video::on_shortcut`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    PlayPause,    // Space
+    SeekBackward, // Left arrow
+    SeekForward,  // Right arrow
+    StepBackward, // Comma
+    StepForward,  // Period
+    Fullscreen,   // F
+}
 
 pub struct Video<'a, Message, Theme = iced::Theme, Renderer = iced::Renderer>
 where
@@ -13,17 +37,34 @@ where
 {
     video: &'a RefCell<VideoStream>,
     content_fit: iced::ContentFit,
+    letterbox_color: iced::Color, // Fills the widget bounds behind the video
+    zoomable: bool,               // Scroll wheel zooms and dragging pans the video
     width: iced::Length,
     height: iced::Length,
-    on_end_of_stream: Option<Message>,
-    on_new_frame: Option<Message>,
+    on_event: Vec<Box<dyn Fn(PlaybackEvent) -> Option<Message> + 'a>>, // Handlers in the order set
+    on_state_change: Option<Box<dyn Fn(PlaybackState) -> Message + 'a>>,
+    on_buffering: Option<Box<dyn Fn(bool) -> Message + 'a>>,
+    on_error: Option<Box<dyn Fn(VideoError) -> Message + 'a>>,
     comparison_enabled: bool,
     comparison_position: f32,
-    dragging_comparison: bool,
+    comparison_line_color: iced::Color,
+    comparison_line_width: f32,
+    comparison_orientation: ComparisonOrientation,
     on_comparison_drag_start: Option<Message>,
     on_comparison_drag_end: Option<Message>,
-    on_comparison_position_change: Option<Message>,
+    on_comparison_position_change: Option<Box<dyn Fn(f32) -> Message + 'a>>,
+    on_comparison_nudge: Option<Box<dyn Fn(f32) -> Message + 'a>>,
+    on_shortcut: Option<Box<dyn Fn(Shortcut) -> Message + 'a>>,
     shader_selections: HashMap<String, bool>,
+    shader_parameters: HashMap<String, EffectParameters>,
+    custom_effects: Arc<Vec<CustomEffectSource>>,
+    lut: Option<Arc<CubeLut>>,
+    stereo_layout: Option<StereoLayout>,
+    pip: Option<(iced::Rectangle, iced::Rectangle)>, // Source region and widget-relative bounds
+    side_by_side: Option<&'a RefCell<VideoStream>>,  // Second video drawn next to this one
+    crop: iced::Rectangle,                           // Normalized region of the video to show
+    pipeline_config: PipelineConfig,
+    screenshot: RefCell<Option<PathBuf>>, // Taken by the first draw, see `screenshot`
     _phantom: PhantomData<(Theme, Renderer)>,
 }
 
@@ -35,24 +76,162 @@ where
         Video {
             video,
             content_fit: iced::ContentFit::default(),
+            letterbox_color: iced::Color::TRANSPARENT,
+            zoomable: false,
             width: iced::Length::Shrink,
             height: iced::Length::Shrink,
-            on_end_of_stream: None,
-            on_new_frame: None,
+            on_event: Vec::new(),
+            on_state_change: None,
+            on_buffering: None,
+            on_error: None,
             comparison_enabled: false,
             comparison_position: 0.5,
-            dragging_comparison: false,
+            comparison_line_color: theme::ACCENT_YELLOW,
+            comparison_line_width: 2.0,
+            comparison_orientation: ComparisonOrientation::default(),
             shader_selections: HashMap::new(),
+            shader_parameters: HashMap::new(),
+            custom_effects: Arc::default(),
+            lut: None,
+            stereo_layout: None,
+            pip: None,
+            side_by_side: None,
+            crop: FULL_REGION,
+            pipeline_config: PipelineConfig::default(),
+            screenshot: RefCell::default(),
             _phantom: Default::default(),
             on_comparison_drag_start: None,
             on_comparison_drag_end: None,
             on_comparison_position_change: None,
+            on_comparison_nudge: None,
+            on_shortcut: None,
         }
     }
     pub fn shader_selections(mut self, selections: HashMap<String, bool>) -> Self {
         self.shader_selections = selections;
         self
     }
+    pub fn shader_parameters(mut self, parameters: HashMap<String, EffectParameters>) -> Self {
+        self.shader_parameters = parameters;
+        self
+    }
+    /// Add effects compiled from custom WGSL shaders after the built-in ones
+    ///
+    /// Effects are enabled unless turned off in the shader selections, and their
+    /// uniforms can be changed through the shader parameters. Effects whose
+    /// shader fails to compile are skipped.
+    pub fn custom_effects(mut self, effects: Arc<Vec<CustomEffectSource>>) -> Self {
+        self.custom_effects = effects;
+        self
+    }
+    /// Color grade the video with a 3D LUT, e.g. loaded with [`CubeLut::from_file`]
+    ///
+    /// The LUT is applied after all other effects. Its strength is the "intensity"
+    /// parameter (0.0-1.0) of the "lut" effect, and it can be turned off in the
+    /// shader selections.
+    pub fn lut(mut self, lut: Option<Arc<CubeLut>>) -> Self {
+        self.lut = lut;
+        self
+    }
+    /// Show stereoscopic (3D) video as one eye or an anaglyph
+    ///
+    /// `None` shows the frame as is.
+    pub fn stereo_layout(mut self, layout: Option<StereoLayout>) -> Self {
+        self.stereo_layout = layout;
+        self
+    }
+    /// Set the video pipeline options, such as mipmaps for downscaled video
+    pub fn pipeline_config(mut self, config: PipelineConfig) -> Self {
+        self.pipeline_config = config;
+        self
+    }
+    /// Save the next drawn frame as a PNG, with the shader effects applied
+    ///
+    /// Only the first draw of this widget saves the frame, so pass the path
+    /// to a single `view`. The file is written a few frames later.
+    pub fn screenshot(self, path: Option<PathBuf>) -> Self {
+        *self.screenshot.borrow_mut() = path;
+        self
+    }
+    /// Set how the video is filtered when scaled
+    ///
+    /// `SamplingMode::Nearest` shows the source pixels as sharp squares, e.g.
+    /// to compare upscalers pixel by pixel.
+    pub fn sampling(mut self, sampling: SamplingMode) -> Self {
+        self.pipeline_config.sampling = sampling;
+        self
+    }
+    /// Draw the video without any effects, e.g. to measure their cost
+    ///
+    /// The selected effects are kept and come back when the bypass is turned off.
+    pub fn bypass_effects(mut self, bypass: bool) -> Self {
+        self.pipeline_config.bypass_effects = bypass;
+        self
+    }
+    /// Show only a region of the video, e.g. to cut black bars or zoom in
+    ///
+    /// The region is in normalized (0.0-1.0) coordinates of the frame and is
+    /// fitted to the widget like a video of that size.
+    pub fn crop(mut self, region: iced::Rectangle) -> Self {
+        self.crop = clamp_region(region);
+        self
+    }
+
+    /// Size of the shown part of the video in pixels
+    fn cropped_size(&self, video: &VideoStream) -> iced::Size {
+        iced::Size::new(
+            video.width() as f32 * self.crop.width,
+            video.height() as f32 * self.crop.height,
+        )
+    }
+
+    /// Rectangle the video is drawn in after fitting it into the widget `bounds`
+    ///
+    /// Excludes letterbox bars, and extends past `bounds` when the fit crops the video.
+    fn drawing_bounds(&self, video: &VideoStream, bounds: iced::Rectangle) -> iced::Rectangle {
+        let image_size = self.cropped_size(video);
+        let adjusted_fit = self.content_fit.fit(image_size, bounds.size());
+        let scale = iced::Vector::new(
+            adjusted_fit.width / image_size.width,
+            adjusted_fit.height / image_size.height,
+        );
+        let final_size = image_size * scale;
+
+        let position = match self.content_fit {
+            iced::ContentFit::None => iced::Point::new(
+                bounds.x + (image_size.width - adjusted_fit.width) / 2.0,
+                bounds.y + (image_size.height - adjusted_fit.height) / 2.0,
+            ),
+            _ => iced::Point::new(
+                bounds.center_x() - final_size.width / 2.0,
+                bounds.center_y() - final_size.height / 2.0,
+            ),
+        };
+
+        iced::Rectangle::new(position, final_size)
+    }
+    /// Draw a second view of the video on top of the main one
+    ///
+    /// `region` is the normalized (0.0-1.0) part of the frame to show and
+    /// `bounds` where to draw it, relative to the widget. Shader effects are
+    /// not applied to this view.
+    pub fn with_pip(mut self, region: iced::Rectangle, bounds: iced::Rectangle) -> Self {
+        self.pip = Some((region, bounds));
+        self
+    }
+    /// Draw another video next to this one, e.g. to compare two source files
+    ///
+    /// Each video is fitted into half of the widget and plays with its own
+    /// timing, so play, pause and seek the streams separately. Shader effects,
+    /// the comparison divider and picture-in-picture aren't drawn in this mode.
+    pub fn side_by_side(mut self, other: &'a RefCell<VideoStream>) -> Self {
+        if std::ptr::eq(other, self.video) {
+            tracing::warn!("Ignoring side by side view of the same video");
+        } else {
+            self.side_by_side = Some(other);
+        }
+        self
+    }
     pub fn comparison_enabled(mut self, enabled: bool) -> Self {
         self.comparison_enabled = enabled;
         self
@@ -63,6 +242,26 @@ where
         self
     }
 
+    /// Split the comparison left/right (the default) or top/bottom
+    ///
+    /// The position is then measured from the left or top edge of the video.
+    pub fn comparison_orientation(mut self, orientation: ComparisonOrientation) -> Self {
+        self.comparison_orientation = orientation;
+        self
+    }
+
+    /// Set the color of the comparison line, alpha is its opacity
+    pub fn comparison_line_color(mut self, color: iced::Color) -> Self {
+        self.comparison_line_color = color;
+        self
+    }
+
+    /// Set the width of the comparison line in physical pixels
+    pub fn comparison_line_width(mut self, width: f32) -> Self {
+        self.comparison_line_width = width;
+        self
+    }
+
     pub fn on_comparison_drag_start(mut self, message: Message) -> Self {
         self.on_comparison_drag_start = Some(message);
         self
@@ -73,8 +272,37 @@ where
         self
     }
 
-    pub fn on_comparison_position_change(mut self, message: Message) -> Self {
-        self.on_comparison_position_change = Some(message);
+    /// Set the message produced with the new comparison position while the
+    /// divider is dragged
+    ///
+    /// The divider is grabbed by pressing the mouse within a few pixels of it,
+    /// clicks elsewhere on the video are left to other widgets.
+    pub fn on_comparison_position_change(
+        mut self,
+        on_change: impl Fn(f32) -> Message + 'a,
+    ) -> Self {
+        self.on_comparison_position_change = Some(Box::new(on_change));
+        self
+    }
+
+    /// Set the message produced with the new comparison position when it is
+    /// moved with the keyboard
+    ///
+    /// While comparison is enabled and the video has been clicked, the arrow
+    /// keys along the split (left/right, or up/down for a top/bottom split)
+    /// move the divider by 1% (10% with Shift) and Home/End snap it to the edges.
+    pub fn on_comparison_nudge(mut self, on_nudge: impl Fn(f32) -> Message + 'a) -> Self {
+        self.on_comparison_nudge = Some(Box::new(on_nudge));
+        self
+    }
+
+    /// Set the message produced for keyboard shortcuts
+    ///
+    /// Keys are only handled after the video has been clicked, so they don't
+    /// reach the widget while typing elsewhere. The arrow keys along the split
+    /// move the comparison divider instead while comparison is enabled.
+    pub fn on_shortcut(mut self, on_shortcut: impl Fn(Shortcut) -> Message + 'a) -> Self {
+        self.on_shortcut = Some(Box::new(on_shortcut));
         self
     }
     pub fn width(self, width: impl Into<iced::Length>) -> Self {
@@ -98,16 +326,83 @@ where
         }
     }
 
-    pub fn on_end_of_stream(self, message: Message) -> Self {
+    /// Set the color of the bars around the video when it doesn't fill the widget
+    ///
+    /// Transparent by default, showing whatever is behind the widget.
+    pub fn letterbox_color(self, letterbox_color: iced::Color) -> Self {
+        Video {
+            letterbox_color,
+            ..self
+        }
+    }
+
+    /// Let the user zoom into the video, e.g. to inspect upscaler artifacts
+    ///
+    /// The scroll wheel zooms around the cursor, dragging pans the zoomed video
+    /// and a double click resets it. The zoomed video always covers the area it
+    /// was fitted to. Not applied to side by side videos.
+    pub fn zoomable(self, zoomable: bool) -> Self {
+        Video { zoomable, ..self }
+    }
+
+    /// Set the message produced for each playback event, e.g. for progress UIs
+    pub fn on_event(mut self, on_event: impl Fn(PlaybackEvent) -> Message + 'a) -> Self {
+        self.on_event
+            .push(Box::new(move |event| Some(on_event(event))));
+        self
+    }
+
+    /// Set the message produced when the end of the video is reached
+    ///
+    /// Shorthand for `on_event` with `PlaybackEvent::Ended`.
+    pub fn on_end_of_stream(mut self, message: Message) -> Self
+    where
+        Message: Clone + 'a,
+    {
+        self.on_event.push(Box::new(move |event| {
+            (event == PlaybackEvent::Ended).then(|| message.clone())
+        }));
+        self
+    }
+
+    /// Set the message produced when a new frame is shown
+    ///
+    /// Shorthand for `on_event` with `PlaybackEvent::FramePresented`.
+    pub fn on_new_frame(mut self, message: Message) -> Self
+    where
+        Message: Clone + 'a,
+    {
+        self.on_event.push(Box::new(move |event| {
+            matches!(event, PlaybackEvent::FramePresented { .. }).then(|| message.clone())
+        }));
+        self
+    }
+
+    /// Set the message produced when the playback state changes (e.g. buffering)
+    pub fn on_state_change(self, on_state_change: impl Fn(PlaybackState) -> Message + 'a) -> Self {
+        Video {
+            on_state_change: Some(Box::new(on_state_change)),
+            ..self
+        }
+    }
+
+    /// Set the message produced when buffering starts (`true`) and stops (`false`)
+    ///
+    /// Shorthand for watching `on_state_change` for `PlaybackState::Buffering`.
+    pub fn on_buffering(self, on_buffering: impl Fn(bool) -> Message + 'a) -> Self {
         Video {
-            on_end_of_stream: Some(message),
+            on_buffering: Some(Box::new(on_buffering)),
             ..self
         }
     }
 
-    pub fn on_new_frame(self, message: Message) -> Self {
+    /// Set the message produced when decoding fails and playback stops
+    ///
+    /// The error holds the frame it failed at. Playback stays stopped, without
+    /// redrawing, until the video is sought, see `VideoStream::last_error`.
+    pub fn on_error(self, on_error: impl Fn(VideoError) -> Message + 'a) -> Self {
         Video {
-            on_new_frame: Some(message),
+            on_error: Some(Box::new(on_error)),
             ..self
         }
     }
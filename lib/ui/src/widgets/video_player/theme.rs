@@ -4,7 +4,7 @@ use iced::{Border, Color, Shadow, Theme};
 // Define custom colors - black and yellow bee theme
 const BACKGROUND: Color = Color::from_rgb(0.1, 0.1, 0.1);
 const SURFACE: Color = Color::from_rgb(0.15, 0.15, 0.15);
-const ACCENT_YELLOW: Color = Color::from_rgb(1.0, 0.8, 0.0);
+pub const ACCENT_YELLOW: Color = Color::from_rgb(1.0, 0.8, 0.0);
 const ACCENT_YELLOW_DARK: Color = Color::from_rgb(0.8, 0.6, 0.0);
 pub const TEXT_LIGHT: Color = Color::from_rgb(0.9, 0.9, 0.9);
 const TEXT_DARK: Color = Color::BLACK;
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+/// How the playback position is shown in the controls
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeDisplay {
+    #[default]
+    Time, // mm:ss / mm:ss
+    Frames,   // frame 1234 / 5000
+    Timecode, // 00:01:23:12
+    Percent,  // 24.7%
+}
+
+impl TimeDisplay {
+    /// The display mode that follows this one when cycling
+    pub fn next(self) -> Self {
+        match self {
+            TimeDisplay::Time => TimeDisplay::Frames,
+            TimeDisplay::Frames => TimeDisplay::Timecode,
+            TimeDisplay::Timecode => TimeDisplay::Percent,
+            TimeDisplay::Percent => TimeDisplay::Time,
+        }
+    }
+}
+
+/// Format a duration as `mm:ss`
+pub fn format_time(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    format!(
+        "{:02}:{:02}",
+        (secs / 60.0).floor() as u64,
+        (secs % 60.0).floor() as u64
+    )
+}
+
+/// Format a frame position, leaving out the total when it is unknown
+pub fn format_frames(current: u64, total: Option<u64>) -> String {
+    match total {
+        Some(total) => format!("frame {} / {}", current, total),
+        None => format!("frame {}", current),
+    }
+}
+
+/// Format a position as a percentage of the total duration
+pub fn format_percent(current: Duration, total: Duration) -> String {
+    if total.is_zero() {
+        return "0.0%".to_string();
+    }
+
+    let percent = current.as_secs_f64() / total.as_secs_f64() * 100.0;
+    format!("{:.1}%", percent.clamp(0.0, 100.0))
+}
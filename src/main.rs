@@ -1,7 +1,5 @@
-use iced::{widget::Container, Element};
-use orbit_video_player_core::video::stream::{VideoStream, VideoStreamOptions};
+use iced::{widget::Container, Element, Subscription, Task};
 use orbit_video_player_ui::widgets::video_player::element::Player;
-use std::cell::RefCell;
 use std::path::Path;
 use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
@@ -14,7 +12,9 @@ fn main() -> iced::Result {
 
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
-    iced::run("Orbit Video Player", App::update, App::view)
+    iced::application("Orbit Video Player", App::update, App::view)
+        .subscription(App::subscription)
+        .run()
 }
 
 pub struct App {
@@ -31,30 +31,24 @@ impl Default for App {
         if !Path::new(video_path).exists() {
             panic!("Video file not found at: {}", video_path);
         }
-        let start_frame = 1;
-        let end_frame = None;
-        let stream = RefCell::new(
-            VideoStream::new(VideoStreamOptions {
-                video_path,
-                start_frame,
-                end_frame,
-            })
-            .unwrap(),
-        );
 
         App {
-            video_player: Player::new(stream, 0.0, false),
+            video_player: Player::from_path(video_path).unwrap(),
         }
     }
 }
 
 impl App {
-    fn update(&mut self, message: Message) {
+    fn update(&mut self, message: Message) -> Task<Message> {
         match message {
-            Message::VideoPlayer(msg) => self.video_player.update(msg),
+            Message::VideoPlayer(msg) => self.video_player.update(msg).map(Message::VideoPlayer),
         }
     }
 
+    fn subscription(&self) -> Subscription<Message> {
+        self.video_player.subscription().map(Message::VideoPlayer)
+    }
+
     fn view(&self) -> Element<Message> {
         Container::new(self.video_player.view().map(Message::VideoPlayer)).into()
     }